@@ -0,0 +1,110 @@
+use std::io::{self, IsTerminal, Write};
+
+use anyhow::Result;
+use gitgraph_core::{
+    GitRefKind, GraphRow, TextGraphCellKind, TextGraphStyle, graph_cells, signature_badge,
+};
+
+// ANSI counterparts of the TUI lane colors, indexed by `color_group`.
+const ANSI_LANE_COLORS: [u8; 8] = [36, 94, 92, 33, 35, 91, 96, 97];
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_DIM: &str = "\x1b[2m";
+const ANSI_HASH: &str = "\x1b[33m";
+const ANSI_REFS: &str = "\x1b[1;32m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+pub fn print_log(rows: &[GraphRow], style: TextGraphStyle, color: ColorMode) -> Result<()> {
+    let stdout = io::stdout();
+    let use_color = match color {
+        ColorMode::Auto => stdout.is_terminal(),
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+    };
+    let mut out = io::BufWriter::new(stdout.lock());
+    for row in rows {
+        let line = render_log_line(row, style, use_color);
+        match writeln!(out, "{line}") {
+            Ok(()) => {}
+            // `gitgraph log | head` closes the pipe early; that is not an error.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    match out.flush() {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn render_log_line(row: &GraphRow, style: TextGraphStyle, use_color: bool) -> String {
+    let paint = |text: &str, code: &str| {
+        if use_color && !text.is_empty() {
+            format!("{code}{text}{ANSI_RESET}")
+        } else {
+            text.to_string()
+        }
+    };
+    let mut line = String::new();
+    for cell in graph_cells(row, style) {
+        match (cell.kind, cell.color_group) {
+            (TextGraphCellKind::Blank, _) | (_, None) => line.push_str(cell.glyph),
+            (kind, Some(group)) => {
+                let weight = if kind == TextGraphCellKind::Line {
+                    ANSI_DIM
+                } else {
+                    ANSI_BOLD
+                };
+                let color = ANSI_LANE_COLORS[group as usize % ANSI_LANE_COLORS.len()];
+                line.push_str(&paint(cell.glyph, &format!("{weight}\x1b[{color}m")));
+            }
+        }
+    }
+    line.push_str("  ");
+    line.push_str(&paint(&row.short_hash, ANSI_HASH));
+    if let Some(badge) = row
+        .signature
+        .as_ref()
+        .and_then(|signature| signature_badge(signature.status, style))
+    {
+        line.push(' ');
+        line.push_str(&paint(badge, ANSI_BOLD));
+    }
+    if !row.refs.is_empty() {
+        let refs = row
+            .refs
+            .iter()
+            .map(|git_ref| match (&git_ref.kind, git_ref.target.as_deref()) {
+                (GitRefKind::Head, Some(target)) => format!("HEAD -> {target}"),
+                (GitRefKind::Tag, _) => format!("tag: {}", git_ref.name),
+                _ => git_ref.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        line.push(' ');
+        line.push_str(&paint(&strip_control(&format!("({refs})")), ANSI_REFS));
+    }
+    line.push(' ');
+    line.push_str(&strip_control(&row.subject));
+    if row.collapsed_commits > 0 {
+        line.push_str(&paint(
+            &format!(" (+{} collapsed)", row.collapsed_commits),
+            ANSI_DIM,
+        ));
+    }
+    line.push_str(&paint(
+        &format!(" · {}", strip_control(&row.author_name)),
+        ANSI_DIM,
+    ));
+    line
+}
+
+fn strip_control(text: &str) -> String {
+    text.chars().filter(|ch| !ch.is_control()).collect()
+}
@@ -10,12 +10,14 @@ use async_openai::types::responses::{
 };
 use clap::{Args, Parser, Subcommand};
 use gitgraph_core::{
-    ActionContext, ActionRequest, CommitSearchQuery, GitLgService, GitOutput, GitRunner,
-    GraphQuery, StateStore,
+    ActionContext, ActionRequest, BisectMark, BlameOptions, CommitSearchQuery, ExportFormat,
+    GitLgService, GitOutput, GitRunner, GraphMode, GraphQuery, OperationLog, StateStore,
+    StatusEntry, export_graph,
 };
 use serde::Deserialize;
 use tokio_stream::StreamExt;
 
+mod log_view;
 mod tui;
 
 #[derive(Debug, Parser)]
@@ -28,10 +30,17 @@ struct Cli {
 #[derive(Debug, Subcommand)]
 enum Commands {
     Graph(GraphCmd),
+    Export(ExportCmd),
+    Log(LogCmd),
     Tui(TuiCmd),
     Search(SearchCmd),
+    Compare(CompareCmd),
+    History(HistoryCmd),
+    LineHistory(LineHistoryCmd),
     Blame(BlameCmd),
     CommitDesc(CommitDescCmd),
+    Bisect(BisectCmd),
+    Undo(UndoCmd),
     Actions(ActionsCmd),
     State(StateCmd),
     ValidateRepo(RepoCmd),
@@ -43,10 +52,27 @@ struct RepoCmd {
     repo: Option<PathBuf>,
 }
 
-#[derive(Debug, Args)]
-struct GraphCmd {
-    #[arg(long)]
-    repo: Option<PathBuf>,
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum GraphModeArg {
+    Full,
+    FirstParent,
+    DecoratedOnly,
+    CollapseLinear,
+}
+
+impl From<GraphModeArg> for GraphMode {
+    fn from(value: GraphModeArg) -> Self {
+        match value {
+            GraphModeArg::Full => GraphMode::Full,
+            GraphModeArg::FirstParent => GraphMode::FirstParent,
+            GraphModeArg::DecoratedOnly => GraphMode::DecoratedOnly,
+            GraphModeArg::CollapseLinear => GraphMode::CollapseLinear,
+        }
+    }
+}
+
+#[derive(Debug, Default, Args)]
+struct GraphQueryArgs {
     #[arg(long)]
     limit: Option<usize>,
     #[arg(long)]
@@ -55,26 +81,75 @@ struct GraphCmd {
     all: bool,
     #[arg(long)]
     no_stash: bool,
+    #[arg(long, value_enum)]
+    mode: Option<GraphModeArg>,
+    #[arg(long = "notes-ref")]
+    notes_refs: Vec<String>,
+    #[arg(long)]
+    verify_signatures: bool,
+    #[arg(long)]
+    uncommitted: bool,
     #[arg(long = "arg")]
     arg: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+struct GraphCmd {
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    #[command(flatten)]
+    query: GraphQueryArgs,
     #[arg(long)]
     pretty: bool,
 }
 
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum ExportFormatArg {
+    Dot,
+    Mermaid,
+    Svg,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(value: ExportFormatArg) -> Self {
+        match value {
+            ExportFormatArg::Dot => ExportFormat::Dot,
+            ExportFormatArg::Mermaid => ExportFormat::Mermaid,
+            ExportFormatArg::Svg => ExportFormat::Svg,
+        }
+    }
+}
+
 #[derive(Debug, Args)]
-struct TuiCmd {
+struct ExportCmd {
     #[arg(long)]
     repo: Option<PathBuf>,
+    #[command(flatten)]
+    query: GraphQueryArgs,
+    #[arg(long, value_enum)]
+    format: ExportFormatArg,
     #[arg(long)]
-    limit: Option<usize>,
-    #[arg(long)]
-    skip: Option<usize>,
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct LogCmd {
     #[arg(long)]
-    all: bool,
+    repo: Option<PathBuf>,
+    #[command(flatten)]
+    query: GraphQueryArgs,
+    #[arg(long, value_enum, default_value_t = tui::GraphStyle::Unicode)]
+    graph_style: tui::GraphStyle,
+    #[arg(long, value_enum, default_value_t = log_view::ColorMode::Auto)]
+    color: log_view::ColorMode,
+}
+
+#[derive(Debug, Args)]
+struct TuiCmd {
     #[arg(long)]
-    no_stash: bool,
-    #[arg(long = "arg")]
-    arg: Vec<String>,
+    repo: Option<PathBuf>,
+    #[command(flatten)]
+    query: GraphQueryArgs,
     #[arg(long, value_enum, default_value_t = tui::GraphStyle::Unicode)]
     graph_style: tui::GraphStyle,
     #[arg(long, default_value_t = 0)]
@@ -85,11 +160,7 @@ impl Default for TuiCmd {
     fn default() -> Self {
         Self {
             repo: None,
-            limit: None,
-            skip: None,
-            all: false,
-            no_stash: false,
-            arg: Vec::new(),
+            query: GraphQueryArgs::default(),
             graph_style: tui::GraphStyle::Unicode,
             max_patch_lines: 0,
         }
@@ -116,14 +187,59 @@ struct SearchCmd {
     pretty: bool,
 }
 
+#[derive(Debug, Args)]
+struct CompareCmd {
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    left: String,
+    right: String,
+    #[arg(long)]
+    patch: bool,
+    #[arg(long, default_value_t = 3)]
+    context: usize,
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Debug, Args)]
+struct HistoryCmd {
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    path: PathBuf,
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Debug, Args)]
+struct LineHistoryCmd {
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    path: PathBuf,
+    start: usize,
+    end: usize,
+    #[arg(long, default_value = "HEAD")]
+    rev: String,
+    #[arg(long)]
+    pretty: bool,
+}
+
 #[derive(Debug, Args)]
 struct BlameCmd {
     #[arg(long)]
     repo: Option<PathBuf>,
     #[arg(long)]
     file: PathBuf,
+    // Without `--line` the whole file is blamed.
     #[arg(long)]
-    line: usize,
+    line: Option<usize>,
+    #[arg(long = "ignore-rev", conflicts_with = "line")]
+    ignore_revs: Vec<String>,
+    #[arg(long, conflicts_with = "line")]
+    ignore_revs_file: Option<PathBuf>,
+    #[arg(short = 'M', long, conflicts_with = "line")]
+    detect_moves: bool,
+    #[arg(short = 'C', long, conflicts_with = "line")]
+    detect_copies: bool,
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum, Deserialize)]
@@ -163,10 +279,47 @@ struct CommitDescCmd {
     max_diff_chars: Option<usize>,
 }
 
+#[derive(Debug, Subcommand)]
+enum BisectSubcommand {
+    // Either order; the older revision is marked good.
+    Start { first: String, second: String },
+    Good { rev: Option<String> },
+    Bad { rev: Option<String> },
+    Skip { rev: Option<String> },
+    // Runs `command` through `sh -c` at each step: exit 0 is good, 125 skip,
+    // other codes up to 127 bad.
+    Run { command: String },
+    Status,
+    Reset,
+}
+
+#[derive(Debug, Args)]
+struct BisectCmd {
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    #[command(subcommand)]
+    subcommand: BisectSubcommand,
+}
+
+#[derive(Debug, Args)]
+struct UndoCmd {
+    #[arg(long)]
+    repo: Option<PathBuf>,
+    // Required when the undo moves HEAD or the checked-out branch.
+    #[arg(long)]
+    yes: bool,
+}
+
 #[derive(Debug, Subcommand)]
 enum ActionsSubcommand {
     List,
-    Run(RunActionCmd),
+    Run {
+        #[command(flatten)]
+        action: RunActionCmd,
+        // Required to run an action classified as destructive.
+        #[arg(long)]
+        yes: bool,
+    },
     Preview(RunActionCmd),
 }
 
@@ -324,21 +477,15 @@ fn run(cli: Cli) -> Result<()> {
             .clone()
             .unwrap_or_else(|| "git".to_string()),
     );
-    let service = GitLgService::new(runner.clone(), state.actions.clone());
+    let service = GitLgService::new(runner.clone(), state.actions.clone())
+        .with_operation_log(OperationLog::next_to_state(store.path()));
 
     let command = cli.command.unwrap_or(Commands::Tui(TuiCmd::default()));
 
     match command {
         Commands::Graph(cmd) => {
             let repo = resolve_repo(cmd.repo)?;
-            let query = build_query_from_graph_args(
-                state.graph_query.clone(),
-                cmd.limit,
-                cmd.skip,
-                cmd.all,
-                cmd.no_stash,
-                cmd.arg,
-            );
+            let query = build_query_from_graph_args(state.graph_query.clone(), cmd.query);
             let graph = service
                 .graph(&repo, &query)
                 .with_context(|| format!("failed to load graph for {}", repo.display()))?;
@@ -349,16 +496,30 @@ fn run(cli: Cli) -> Result<()> {
             };
             println!("{json}");
         }
+        Commands::Export(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            let query = build_query_from_graph_args(state.graph_query.clone(), cmd.query);
+            let graph = service
+                .graph(&repo, &query)
+                .with_context(|| format!("failed to load graph for {}", repo.display()))?;
+            let rendered = export_graph(&graph.commits, cmd.format.into());
+            match cmd.output {
+                Some(path) => fs::write(&path, rendered)
+                    .with_context(|| format!("failed to write {}", path.display()))?,
+                None => print!("{rendered}"),
+            }
+        }
+        Commands::Log(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            let query = build_query_from_graph_args(state.graph_query.clone(), cmd.query);
+            let graph = service
+                .graph(&repo, &query)
+                .with_context(|| format!("failed to load graph for {}", repo.display()))?;
+            log_view::print_log(&graph.commits, cmd.graph_style.into(), cmd.color)?;
+        }
         Commands::Tui(cmd) => {
             let repo = resolve_repo(cmd.repo)?;
-            let query = build_query_from_graph_args(
-                state.graph_query.clone(),
-                cmd.limit,
-                cmd.skip,
-                cmd.all,
-                cmd.no_stash,
-                cmd.arg,
-            );
+            let query = build_query_from_graph_args(state.graph_query.clone(), cmd.query);
             tui::run(
                 &service,
                 tui::TuiConfig {
@@ -367,6 +528,7 @@ fn run(cli: Cli) -> Result<()> {
                     graph_style: cmd.graph_style,
                     max_patch_lines: cmd.max_patch_lines,
                     git_binary: runner.git_binary().to_string(),
+                    default_remote_name: state.default_remote_name.clone(),
                 },
             )
             .with_context(|| format!("failed running TUI for {}", repo.display()))?;
@@ -397,22 +559,135 @@ fn run(cli: Cli) -> Result<()> {
             };
             println!("{json}");
         }
+        Commands::Compare(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            let mut comparison = service
+                .compare_revisions(&repo, &cmd.left, &cmd.right)
+                .with_context(|| format!("failed to compare {} and {}", cmd.left, cmd.right))?;
+            if cmd.patch {
+                comparison.patches = service.compare_patches(&repo, &comparison, cmd.context)?;
+            }
+            let json = if cmd.pretty {
+                serde_json::to_string_pretty(&comparison)?
+            } else {
+                serde_json::to_string(&comparison)?
+            };
+            println!("{json}");
+        }
+        Commands::History(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            let history = service
+                .file_history(&repo, &cmd.path)
+                .with_context(|| format!("failed to load history of {}", cmd.path.display()))?;
+            let json = if cmd.pretty {
+                serde_json::to_string_pretty(&history)?
+            } else {
+                serde_json::to_string(&history)?
+            };
+            println!("{json}");
+        }
+        Commands::LineHistory(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            let history = service
+                .line_history_at(&repo, &cmd.rev, &cmd.path, cmd.start, cmd.end)
+                .with_context(|| {
+                    format!(
+                        "failed to load history of {}:{}-{}",
+                        cmd.path.display(),
+                        cmd.start,
+                        cmd.end
+                    )
+                })?;
+            let json = if cmd.pretty {
+                serde_json::to_string_pretty(&history)?
+            } else {
+                serde_json::to_string(&history)?
+            };
+            println!("{json}");
+        }
         Commands::Blame(cmd) => {
             let repo = resolve_repo(cmd.repo)?;
-            let blame = service
-                .blame_line(&repo, &cmd.file, cmd.line)
-                .with_context(|| format!("failed to blame {}:{}", cmd.file.display(), cmd.line))?;
-            println!("{}", serde_json::to_string_pretty(&blame)?);
+            let json = if let Some(line) = cmd.line {
+                let blame = service
+                    .blame_line(&repo, &cmd.file, line)
+                    .with_context(|| format!("failed to blame {}:{}", cmd.file.display(), line))?;
+                serde_json::to_string_pretty(&blame)?
+            } else {
+                let options = BlameOptions {
+                    ignore_revs: cmd.ignore_revs,
+                    ignore_revs_file: cmd.ignore_revs_file,
+                    detect_moves: cmd.detect_moves,
+                    detect_copies: cmd.detect_copies,
+                };
+                let blame = service
+                    .blame_file(&repo, &cmd.file, &options)
+                    .with_context(|| format!("failed to blame {}", cmd.file.display()))?;
+                serde_json::to_string_pretty(&blame)?
+            };
+            println!("{json}");
         }
         Commands::CommitDesc(cmd) => {
             let repo = resolve_repo(cmd.repo.clone())?;
             let openai_cfg = load_or_create_gitgraph_openai_config(&repo)?;
             let settings = resolve_commit_desc_settings(&cmd, &openai_cfg);
-            let changes = collect_uncommitted_changes(&runner, &repo)?;
+            let changes = collect_uncommitted_changes(&service, &runner, &repo)?;
             let prompt = build_commit_description_prompt(&changes, settings.max_diff_chars);
             let generated = generate_commit_description_with_openai(&settings, prompt)?;
             println!("{}", generated.trim());
         }
+        Commands::Bisect(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            let status = match cmd.subcommand {
+                BisectSubcommand::Start { first, second } => {
+                    let first = service.commit_row(&repo, &first)?;
+                    let second = service.commit_row(&repo, &second)?;
+                    Some(service.start_bisect(&repo, &first, &second)?)
+                }
+                BisectSubcommand::Good { rev } => {
+                    Some(service.mark_bisect(&repo, BisectMark::Good, rev.as_deref())?)
+                }
+                BisectSubcommand::Bad { rev } => {
+                    Some(service.mark_bisect(&repo, BisectMark::Bad, rev.as_deref())?)
+                }
+                BisectSubcommand::Skip { rev } => {
+                    Some(service.mark_bisect(&repo, BisectMark::Skip, rev.as_deref())?)
+                }
+                BisectSubcommand::Run { command } => Some(service.run_bisect(&repo, &command)?),
+                BisectSubcommand::Status => service.bisect_status(&repo)?,
+                BisectSubcommand::Reset => {
+                    service.reset_bisect(&repo)?;
+                    None
+                }
+            };
+            if let Some(status) = &status {
+                eprintln!("bisect: {}", status.summary());
+            }
+            println!("{}", serde_json::to_string_pretty(&status)?);
+        }
+        Commands::Undo(cmd) => {
+            let repo = resolve_repo(cmd.repo)?;
+            match service.plan_undo_last(&repo)? {
+                None => println!("nothing to undo"),
+                Some(plan) => {
+                    eprintln!("{}", plan.summary());
+                    for update in &plan.ref_updates {
+                        eprintln!(
+                            "  {}: {} -> {}",
+                            update.name,
+                            update.from.as_deref().map_or("(none)", short_oid),
+                            update.to.as_deref().map_or("(none)", short_oid)
+                        );
+                    }
+                    if plan.affects_worktree && !cmd.yes {
+                        return Err(anyhow!(
+                            "this undo moves HEAD and updates the working tree; rerun with --yes to apply it"
+                        ));
+                    }
+                    service.apply_undo(&repo, &plan)?;
+                    println!("undone operation {}", plan.operation.id);
+                }
+            }
+        }
         Commands::Actions(cmd) => match cmd.subcommand {
             ActionsSubcommand::List => {
                 println!("{}", serde_json::to_string_pretty(service.actions())?);
@@ -429,18 +704,35 @@ fn run(cli: Cli) -> Result<()> {
                         "args": resolved.args,
                         "shell_script": resolved.shell_script,
                         "command_line": resolved.command_line,
+                        "risk": resolved.risk,
                     }))?
                 );
             }
-            ActionsSubcommand::Run(run) => {
-                let repo = resolve_repo(run.repo.clone())?;
-                let request = build_action_request(run)?;
+            ActionsSubcommand::Run { action, yes } => {
+                let repo = resolve_repo(action.repo.clone())?;
+                let request = build_action_request(action)?;
+                let resolved = service.resolve_action_preview(
+                    request.clone(),
+                    &state.default_remote_name,
+                    Some(&repo),
+                )?;
+                if let Some(reason) = resolved.risk.reason()
+                    && !yes
+                {
+                    return Err(anyhow!(
+                        "`git {}` is destructive ({reason}); rerun with --yes to run it",
+                        resolved.command_line
+                    ));
+                }
                 let result = service.execute_action(&repo, request, &state.default_remote_name)?;
                 eprintln!("executed: git {}", result.command_line);
                 println!("{}", result.output.stdout);
                 if !result.output.stderr.trim().is_empty() {
                     eprintln!("{}", result.output.stderr);
                 }
+                if let Some(warning) = &result.log_warning {
+                    eprintln!("warning: {warning}");
+                }
             }
         },
         Commands::State(cmd) => match cmd.subcommand {
@@ -468,6 +760,10 @@ fn run(cli: Cli) -> Result<()> {
                         skip: 0,
                         all_refs: true,
                         include_stash_ref: false,
+                        mode: GraphMode::Full,
+                        notes_refs: vec![],
+                        verify_signatures: false,
+                        include_uncommitted: false,
                         additional_args: vec![],
                     },
                 )
@@ -561,6 +857,7 @@ fn apply_context_placeholder(context: &mut ActionContext, key: &str, value: &str
                 .collect()
         }
         "COMMIT_BODY" => context.commit_body = Some(value.to_string()),
+        "COMMIT_NOTE" => context.commit_note = Some(value.to_string()),
         "STASH_NAME" => context.stash_name = Some(value.to_string()),
         "TAG_NAME" => context.tag_name = Some(value.to_string()),
         "REMOTE_NAME" => context.remote_name = Some(value.to_string()),
@@ -573,32 +870,41 @@ fn apply_context_placeholder(context: &mut ActionContext, key: &str, value: &str
     }
 }
 
-fn build_query_from_graph_args(
-    mut base: GraphQuery,
-    limit: Option<usize>,
-    skip: Option<usize>,
-    all: bool,
-    no_stash: bool,
-    additional_args: Vec<String>,
-) -> GraphQuery {
-    if let Some(limit) = limit {
+fn build_query_from_graph_args(mut base: GraphQuery, args: GraphQueryArgs) -> GraphQuery {
+    if let Some(limit) = args.limit {
         base.limit = limit;
     }
-    if let Some(skip) = skip {
+    if let Some(skip) = args.skip {
         base.skip = skip;
     }
-    if all {
+    if args.all {
         base.all_refs = true;
     }
-    if no_stash {
+    if args.no_stash {
         base.include_stash_ref = false;
     }
-    if !additional_args.is_empty() {
-        base.additional_args = additional_args;
+    if let Some(mode) = args.mode {
+        base.mode = mode.into();
+    }
+    if !args.notes_refs.is_empty() {
+        base.notes_refs = args.notes_refs;
+    }
+    if args.verify_signatures {
+        base.verify_signatures = true;
+    }
+    if args.uncommitted {
+        base.include_uncommitted = true;
+    }
+    if !args.arg.is_empty() {
+        base.additional_args = args.arg;
     }
     base
 }
 
+fn short_oid(oid: &str) -> &str {
+    oid.get(..7).unwrap_or(oid)
+}
+
 fn path_to_git_path(path: PathBuf) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
@@ -613,6 +919,7 @@ pub(crate) fn generate_commit_description_for_repo_with_git(
     git_binary: &str,
 ) -> Result<String> {
     let runner = GitRunner::new(git_binary.to_string());
+    let service = GitLgService::with_default_actions(runner.clone());
     let cfg = load_or_create_gitgraph_openai_config(repo)?;
     let defaults = CommitDescCmd {
         repo: None,
@@ -629,31 +936,11 @@ pub(crate) fn generate_commit_description_for_repo_with_git(
         max_diff_chars: None,
     };
     let settings = resolve_commit_desc_settings(&defaults, &cfg);
-    let changes = collect_uncommitted_changes(&runner, repo)?;
+    let changes = collect_uncommitted_changes(&service, &runner, repo)?;
     let prompt = build_commit_description_prompt(&changes, settings.max_diff_chars);
     generate_commit_description_with_openai(&settings, prompt)
 }
 
-pub(crate) fn auto_commit_with_message(
-    repo: &Path,
-    git_binary: &str,
-    message: &str,
-) -> Result<String> {
-    let runner = GitRunner::new(git_binary.to_string());
-    runner.validate_repo(repo)?;
-
-    let (subject, body) = split_commit_message(message)?;
-    runner.exec(repo, &["add".to_string(), "-A".to_string()], false)?;
-
-    let mut args = vec!["commit".to_string(), "-m".to_string(), subject];
-    if !body.is_empty() {
-        args.push("-m".to_string());
-        args.push(body);
-    }
-    let out = runner.exec(repo, &args, false)?;
-    Ok(summarize_git_output(&out))
-}
-
 pub(crate) fn auto_push_current_branch(repo: &Path, git_binary: &str) -> Result<String> {
     let runner = GitRunner::new(git_binary.to_string());
     runner.validate_repo(repo)?;
@@ -759,18 +1046,6 @@ fn resolve_commit_desc_settings(
     }
 }
 
-fn split_commit_message(message: &str) -> Result<(String, String)> {
-    let mut lines = message.lines().skip_while(|line| line.trim().is_empty());
-    let subject = lines
-        .next()
-        .map(str::trim)
-        .filter(|line| !line.is_empty())
-        .ok_or_else(|| anyhow!("generated commit description is empty"))?
-        .to_string();
-    let body = lines.collect::<Vec<_>>().join("\n").trim().to_string();
-    Ok((subject, body))
-}
-
 fn summarize_git_output(out: &GitOutput) -> String {
     let stdout = out.stdout.trim();
     if !stdout.is_empty() {
@@ -783,19 +1058,19 @@ fn summarize_git_output(out: &GitOutput) -> String {
     "ok".to_string()
 }
 
-fn collect_uncommitted_changes(runner: &GitRunner, repo: &Path) -> Result<WorkingTreeChanges> {
-    runner.validate_repo(repo)?;
-    let raw_status = run_git_stdout(
-        runner,
-        repo,
-        &[
-            "status".to_string(),
-            "--short".to_string(),
-            "--untracked-files=all".to_string(),
-        ],
-        false,
-    )?;
-    let status_short = filter_status_for_commit_desc(&raw_status);
+fn collect_uncommitted_changes(
+    service: &GitLgService,
+    runner: &GitRunner,
+    repo: &Path,
+) -> Result<WorkingTreeChanges> {
+    let status = service.working_tree_status(repo)?;
+    let status_short = status
+        .entries
+        .iter()
+        .filter(|entry| !is_local_config_status_entry(entry))
+        .map(StatusEntry::short_line)
+        .collect::<Vec<_>>()
+        .join("\n");
     if status_short.trim().is_empty() {
         return Err(anyhow!(
             "working tree is clean (or only .config.toml changed): no commit changes found"
@@ -831,20 +1106,9 @@ fn collect_uncommitted_changes(runner: &GitRunner, repo: &Path) -> Result<Workin
     })
 }
 
-fn filter_status_for_commit_desc(status_short: &str) -> String {
-    status_short
-        .lines()
-        .filter(|line| {
-            let trimmed = line.trim();
-            !trimmed.is_empty()
-                && trimmed != "?? .config.toml"
-                && trimmed != "?? ./.config.toml"
-                && trimmed != "A  .config.toml"
-                && trimmed != " M .config.toml"
-                && trimmed != "M  .config.toml"
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+// The generated `.config.toml` is not part of the change being described.
+fn is_local_config_status_entry(entry: &StatusEntry) -> bool {
+    entry.path.trim_start_matches("./") == ".config.toml"
 }
 
 fn run_git_stdout(
@@ -1026,16 +1290,12 @@ async fn generate_commit_description_streaming(
         let event = event.map_err(|e| anyhow!("OpenAI stream read failed: {e}"))?;
         match event {
             ResponseStreamEvent::ResponseOutputTextDelta(delta) => out.push_str(&delta.delta),
-            ResponseStreamEvent::ResponseOutputTextDone(done) => {
-                if out.trim().is_empty() {
-                    out.push_str(&done.text);
-                }
+            ResponseStreamEvent::ResponseOutputTextDone(done) if out.trim().is_empty() => {
+                out.push_str(&done.text);
             }
             ResponseStreamEvent::ResponseRefusalDelta(delta) => out.push_str(&delta.delta),
-            ResponseStreamEvent::ResponseRefusalDone(done) => {
-                if out.trim().is_empty() {
-                    out.push_str(&done.refusal);
-                }
+            ResponseStreamEvent::ResponseRefusalDone(done) if out.trim().is_empty() => {
+                out.push_str(&done.refusal);
             }
             ResponseStreamEvent::ResponseError(err) => {
                 return Err(anyhow!(
@@ -1079,17 +1339,16 @@ fn resolve_api_key(settings: &CommitDescSettings) -> Result<String> {
     {
         return Ok(value);
     }
-    if let Ok(value) = std::env::var(&settings.api_key_env) {
-        if let Some(value) = normalize_string_setting(Some(value)) {
-            return Ok(value);
-        }
+    if let Ok(value) = std::env::var(&settings.api_key_env)
+        && let Some(value) = normalize_string_setting(Some(value))
+    {
+        return Ok(value);
     }
-    if settings.api_key_env != DEFAULT_API_KEY_ENV {
-        if let Ok(value) = std::env::var(DEFAULT_API_KEY_ENV) {
-            if let Some(value) = normalize_string_setting(Some(value)) {
-                return Ok(value);
-            }
-        }
+    if settings.api_key_env != DEFAULT_API_KEY_ENV
+        && let Ok(value) = std::env::var(DEFAULT_API_KEY_ENV)
+        && let Some(value) = normalize_string_setting(Some(value))
+    {
+        return Ok(value);
     }
 
     Err(anyhow!(
@@ -1101,20 +1360,20 @@ To use codex-lb auth instead, set requires_openai_auth=true",
 }
 
 fn resolve_codex_auth_token(settings: &CommitDescSettings) -> Option<String> {
-    if let Ok(value) = std::env::var(&settings.codex_auth_token_env) {
-        if let Some(value) = normalize_string_setting(Some(value)) {
-            return Some(value);
-        }
+    if let Ok(value) = std::env::var(&settings.codex_auth_token_env)
+        && let Some(value) = normalize_string_setting(Some(value))
+    {
+        return Some(value);
     }
 
     for env_name in ["OPENAI_ACCESS_TOKEN", "CODEX_ACCESS_TOKEN"] {
         if env_name == settings.codex_auth_token_env {
             continue;
         }
-        if let Ok(value) = std::env::var(env_name) {
-            if let Some(value) = normalize_string_setting(Some(value)) {
-                return Some(value);
-            }
+        if let Ok(value) = std::env::var(env_name)
+            && let Some(value) = normalize_string_setting(Some(value))
+        {
+            return Some(value);
         }
     }
 
@@ -1139,10 +1398,10 @@ fn resolve_codex_auth_token(settings: &CommitDescSettings) -> Option<String> {
 }
 
 fn default_codex_auth_json_path() -> Option<PathBuf> {
-    if let Ok(codex_home) = std::env::var("CODEX_HOME") {
-        if let Some(base) = normalize_string_setting(Some(codex_home)) {
-            return Some(PathBuf::from(base).join("auth.json"));
-        }
+    if let Ok(codex_home) = std::env::var("CODEX_HOME")
+        && let Some(base) = normalize_string_setting(Some(codex_home))
+    {
+        return Some(PathBuf::from(base).join("auth.json"));
     }
     home_dir().map(|home| home.join(".codex").join("auth.json"))
 }
@@ -1,7 +1,7 @@
-use std::cmp::{max, min};
-use std::collections::HashMap;
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Stdout};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -14,7 +14,14 @@ use crossterm::execute;
 use crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
-use gitgraph_core::{CommitSearchQuery, FileChange, GitLgService, GraphData, GraphQuery, GraphRow};
+use gitgraph_core::{
+    ActionContext, ActionRequest, ActionRisk, BisectMark, BisectStatus, BranchInfo, CommitOptions,
+    CommitSearchQuery, FileChange, GitLgService, GitRefKind, GraphData, GraphQuery, GraphRow,
+    GraphStream, HUNK_CONTEXT_LINES, HunkAction, HunkSelection, RepoOperationControl,
+    RevisionComparison, SignatureStatus, TextGraphCellKind, TextGraphStyle, UNCOMMITTED_HASH,
+    UndoPlan, WorkingTreeStatus, WorktreeDiffSide, classify_git_args, graph_cells, hunk_apply_args,
+    operation_control_args, parse_hunk_header, signature_badge,
+};
 use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
@@ -28,6 +35,7 @@ pub struct TuiConfig {
     pub graph_style: GraphStyle,
     pub max_patch_lines: usize,
     pub git_binary: String,
+    pub default_remote_name: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -83,6 +91,63 @@ impl FocusPane {
     }
 }
 
+// Commits that touched one file, with the file's name at each commit. For a
+// line range, `hunks` holds the rendered `-L` hunks shown in the Patch pane.
+#[derive(Debug)]
+struct HistoryView {
+    path: String,
+    range: Option<(usize, usize)>,
+    paths: HashMap<String, String>,
+    hunks: HashMap<String, Vec<Line<'static>>>,
+}
+
+impl HistoryView {
+    fn title(&self) -> String {
+        match self.range {
+            Some((start, end)) => format!("{}:{start}-{end}", self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+// Start of a line selection in the Patch pane; the selection runs from here to
+// the top visible line and only applies while the same file is shown.
+#[derive(Debug)]
+struct DiffMark {
+    hash: String,
+    path: String,
+    line: usize,
+}
+
+// A step waiting for its key to be pressed again: a previewed merge or
+// rebase, an undo, or a destructive operation control or hunk discard.
+#[derive(Debug, Clone)]
+struct PendingAction {
+    key: char,
+    title: String,
+    run: PendingRun,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PendingRun {
+    Action(Box<ActionRequest>),
+    Undo(Box<UndoPlan>),
+    Control(RepoOperationControl),
+    Hunk {
+        action: HunkAction,
+        path: String,
+        selection: HunkSelection,
+    },
+}
+
+// How the popup's `c`, `s` and `a` keys commit the generated message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PopupCommit {
+    All,
+    StagedOnly,
+    Amend,
+}
+
 #[derive(Debug, Default)]
 struct CommitArtifactCache {
     files: Arc<Vec<FileChange>>,
@@ -121,10 +186,8 @@ pub fn run(service: &GitLgService, config: TuiConfig) -> Result<()> {
                         }
                         needs_redraw = true;
                     }
-                    Event::Mouse(mouse) => {
-                        if app.on_mouse(mouse) {
-                            needs_redraw = true;
-                        }
+                    Event::Mouse(mouse) if app.on_mouse(mouse) => {
+                        needs_redraw = true;
                     }
                     Event::Resize(_, _) => needs_redraw = true,
                     _ => {}
@@ -152,8 +215,10 @@ struct TuiApp<'a> {
     service: &'a GitLgService,
     repo: PathBuf,
     git_binary: String,
+    default_remote_name: String,
     base_query: GraphQuery,
     graph: GraphData,
+    graph_stream: Option<GraphStream>,
     filtered_rows: Vec<GraphRow>,
     list_state: ListState,
     status: String,
@@ -181,6 +246,17 @@ struct TuiApp<'a> {
     commit_desc_popup_scroll: usize,
     commit_desc_popup_area: Option<Rect>,
     last_generated_commit_desc: Option<String>,
+    marked_commit: Option<String>,
+    comparison: Option<RevisionComparison>,
+    history: Option<HistoryView>,
+    worktree_status: Option<WorkingTreeStatus>,
+    // `None` shows the combined diff against HEAD for the uncommitted row.
+    worktree_side: Option<WorktreeDiffSide>,
+    diff_mark: Option<DiffMark>,
+    pending_action: Option<PendingAction>,
+    bisect: Option<BisectStatus>,
+    // First end picked with `B` before a bisect is started.
+    bisect_mark: Option<String>,
 }
 
 const LANE_COLORS: [Color; 8] = [
@@ -193,6 +269,11 @@ const LANE_COLORS: [Color; 8] = [
     Color::LightCyan,
     Color::White,
 ];
+const GRAPH_PAGE_SIZE: usize = 500;
+const GRAPH_LOAD_AHEAD_ROWS: usize = 100;
+// Pages a search scans per step; a rare match scans further as the selection
+// nears the end of the list, or through the whole history with `G`.
+const SEARCH_SCAN_PAGES: usize = 20;
 const COMMIT_PREFETCH_DEBOUNCE: Duration = Duration::from_millis(120);
 const SEARCH_APPLY_DEBOUNCE: Duration = Duration::from_millis(180);
 const MOUSE_DIFF_SCROLL_LINES: i16 = 4;
@@ -200,9 +281,12 @@ const COMMIT_DESC_POPUP_SCROLL_LINES: i16 = 4;
 
 impl<'a> TuiApp<'a> {
     fn new(service: &'a GitLgService, config: TuiConfig) -> Result<Self> {
-        let graph = service
-            .graph(&config.repo, &config.query)
+        let (graph, graph_stream) = service
+            .graph_paged(&config.repo, &config.query, GRAPH_PAGE_SIZE)
             .with_context(|| format!("failed to load graph for {}", config.repo.display()))?;
+        // Status is informational; a failure here must not block the graph.
+        let worktree_status = service.working_tree_status(&config.repo).ok();
+        let bisect = service.bisect_status(&config.repo).ok().flatten();
         let filtered_rows = graph.commits.clone();
         let mut list_state = ListState::default();
         if !filtered_rows.is_empty() {
@@ -213,8 +297,10 @@ impl<'a> TuiApp<'a> {
             service,
             repo: config.repo,
             git_binary: config.git_binary,
+            default_remote_name: config.default_remote_name,
             base_query: config.query,
             graph,
+            graph_stream: (!graph_stream.is_finished()).then_some(graph_stream),
             filtered_rows,
             list_state,
             status: "Ready".to_string(),
@@ -242,6 +328,15 @@ impl<'a> TuiApp<'a> {
             commit_desc_popup_scroll: 0,
             commit_desc_popup_area: None,
             last_generated_commit_desc: None,
+            marked_commit: None,
+            comparison: None,
+            history: None,
+            worktree_status,
+            worktree_side: None,
+            diff_mark: None,
+            pending_action: None,
+            bisect,
+            bisect_mark: None,
         };
         app.rebuild_list_cache();
         app.sync_selected_commit_from_cache();
@@ -287,12 +382,12 @@ impl<'a> TuiApp<'a> {
             KeyCode::Down | KeyCode::Char('j') => match self.focus {
                 FocusPane::Commits => self.next_commit()?,
                 FocusPane::Files => self.next_file()?,
-                FocusPane::Diff => self.scroll_diff(3),
+                FocusPane::Diff => self.scroll_diff(self.diff_step()),
             },
             KeyCode::Up | KeyCode::Char('k') => match self.focus {
                 FocusPane::Commits => self.prev_commit()?,
                 FocusPane::Files => self.prev_file()?,
-                FocusPane::Diff => self.scroll_diff(-3),
+                FocusPane::Diff => self.scroll_diff(-self.diff_step()),
             },
             KeyCode::PageDown => self.scroll_diff(14),
             KeyCode::PageUp => self.scroll_diff(-14),
@@ -307,26 +402,88 @@ impl<'a> TuiApp<'a> {
                 FocusPane::Diff => self.diff_scroll = self.max_diff_scroll(),
             },
             KeyCode::Char('r') => self.refresh()?,
+            KeyCode::Char('v') => self.cycle_graph_mode()?,
+            KeyCode::Char('x') => {
+                if let Err(err) = self.toggle_compare_mark() {
+                    self.marked_commit = None;
+                    self.status = format!("compare failed: {err:#}");
+                }
+            }
             KeyCode::Char('m') => {
                 if let Err(err) = self.generate_commit_description_popup() {
                     self.show_commit_desc_error(&err);
                 }
             }
-            KeyCode::Enter => {
-                if self.focus == FocusPane::Files {
-                    self.ensure_selected_file_patch_loaded()?;
+            KeyCode::Char('L') if self.focus == FocusPane::Diff => {
+                if let Err(err) = self.show_hunk_line_history() {
+                    self.status = format!("line history failed: {err:#}");
+                }
+            }
+            KeyCode::Char('t') if self.focus == FocusPane::Diff => {
+                if let Err(err) = self.cycle_worktree_side() {
+                    self.status = format!("diff side switch failed: {err:#}");
+                }
+            }
+            KeyCode::Char(' ') if self.focus == FocusPane::Diff => self.toggle_diff_mark(),
+            KeyCode::Char('s') if self.focus == FocusPane::Diff => {
+                self.run_hunk_action(HunkAction::Stage);
+            }
+            KeyCode::Char('u') if self.focus == FocusPane::Diff => {
+                self.run_hunk_action(HunkAction::Unstage);
+            }
+            KeyCode::Char('D') if self.focus == FocusPane::Diff => {
+                self.run_hunk_action(HunkAction::Discard);
+            }
+            KeyCode::Char(ch @ ('C' | 'A' | 'S')) if self.graph.operation.is_some() => {
+                let control = match ch {
+                    'C' => RepoOperationControl::Continue,
+                    'A' => RepoOperationControl::Abort,
+                    _ => RepoOperationControl::Skip,
+                };
+                if let Err(err) = self.control_operation(ch, control) {
+                    self.status = format!("{} failed: {err:#}", control.label());
+                }
+            }
+            KeyCode::Char('U') => {
+                if let Err(err) = self.undo_last_operation() {
+                    self.status = format!("undo failed: {err:#}");
+                }
+            }
+            KeyCode::Char('B') if self.focus == FocusPane::Commits => {
+                self.run_bisect_mark(BisectMark::Bad);
+            }
+            KeyCode::Char('W') if self.focus == FocusPane::Commits && self.bisect.is_some() => {
+                self.run_bisect_mark(BisectMark::Good);
+            }
+            KeyCode::Char(ch @ ('M' | 'R')) if self.focus == FocusPane::Commits => {
+                if let Err(err) = self.merge_or_rebase_selected(ch) {
+                    self.status = format!("{} failed: {err:#}", merge_key_verb(ch));
+                }
+            }
+            KeyCode::Enter if self.focus == FocusPane::Files => {
+                self.ensure_selected_file_patch_loaded()?;
+            }
+            KeyCode::Char('h') if self.focus == FocusPane::Files => {
+                if let Err(err) = self.show_selected_file_history() {
+                    self.status = format!("file history failed: {err:#}");
                 }
             }
             KeyCode::Char('/') => {
                 self.input_mode = InputMode::Search;
                 self.status = "Search: type text, Enter apply, Esc cancel".to_string();
             }
-            KeyCode::Esc => {
-                if !self.search_input.is_empty() {
-                    self.search_input.clear();
-                    self.apply_search(true)?;
+            KeyCode::Esc if self.pending_action.is_some() => {
+                if let Some(pending) = self.pending_action.take() {
+                    self.status = format!("{} canceled", pending.title);
                 }
             }
+            KeyCode::Esc if self.history.is_some() => {
+                self.apply_search(true)?;
+            }
+            KeyCode::Esc if !self.search_input.is_empty() => {
+                self.search_input.clear();
+                self.apply_search(true)?;
+            }
             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 return Ok(false);
             }
@@ -362,13 +519,12 @@ impl<'a> TuiApp<'a> {
                 self.search_input.pop();
                 self.queue_search_apply();
             }
-            KeyCode::Char(ch) => {
+            KeyCode::Char(ch)
                 if !key.modifiers.contains(KeyModifiers::CONTROL)
-                    && !key.modifiers.contains(KeyModifiers::ALT)
-                {
-                    self.search_input.push(ch);
-                    self.queue_search_apply();
-                }
+                    && !key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                self.search_input.push(ch);
+                self.queue_search_apply();
             }
             _ => {}
         }
@@ -384,11 +540,16 @@ impl<'a> TuiApp<'a> {
                 self.commit_desc_popup_area = None;
                 self.status = "Commit description closed".to_string();
             }
-            KeyCode::Char('c')
+            KeyCode::Char(ch @ ('c' | 's' | 'a'))
                 if !key.modifiers.contains(KeyModifiers::CONTROL)
                     && self.commit_desc_popup_mode == Some(CommitDescPopupMode::Generated) =>
             {
-                if let Err(err) = self.auto_commit_from_popup() {
+                let mode = match ch {
+                    'c' => PopupCommit::All,
+                    's' => PopupCommit::StagedOnly,
+                    _ => PopupCommit::Amend,
+                };
+                if let Err(err) = self.auto_commit_from_popup(mode) {
                     self.show_commit_desc_error(&err);
                 }
             }
@@ -433,12 +594,11 @@ impl<'a> TuiApp<'a> {
 
         if let Some(area) = self.search_area
             && point_in_rect(area, mouse.column, mouse.row)
+            && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
         {
-            if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
-                self.input_mode = InputMode::Search;
-                self.status = "Search: type text, Enter apply, Esc cancel".to_string();
-                return true;
-            }
+            self.input_mode = InputMode::Search;
+            self.status = "Search: type text, Enter apply, Esc cancel".to_string();
+            return true;
         }
 
         if self.input_mode == InputMode::Search {
@@ -551,36 +711,124 @@ impl<'a> TuiApp<'a> {
     }
 
     fn refresh(&mut self) -> Result<()> {
-        self.graph = self
+        let (graph, graph_stream) = self
             .service
-            .graph(&self.repo, &self.base_query)
+            .graph_paged(&self.repo, &self.base_query, GRAPH_PAGE_SIZE)
             .with_context(|| format!("failed to refresh graph for {}", self.repo.display()))?;
+        self.graph = graph;
+        self.graph_stream = (!graph_stream.is_finished()).then_some(graph_stream);
+        self.worktree_status = self.service.working_tree_status(&self.repo).ok();
+        self.bisect = self.service.bisect_status(&self.repo).ok().flatten();
+        // The working tree may have changed since its files were cached.
+        self.commit_cache.remove(UNCOMMITTED_HASH);
         self.apply_search(true)?;
         self.status = format!("Refreshed {} commit(s)", self.graph.commits.len());
         Ok(())
     }
 
-    fn apply_search(&mut self, prefetch_artifacts: bool) -> Result<()> {
-        self.pending_search_apply = false;
-        let query = CommitSearchQuery {
+    fn cycle_graph_mode(&mut self) -> Result<()> {
+        self.base_query.mode = self.base_query.mode.next();
+        self.refresh()?;
+        self.status = format!(
+            "Graph mode: {} ({} commit(s))",
+            self.base_query.mode.label(),
+            self.graph.commits.len()
+        );
+        Ok(())
+    }
+
+    fn has_more_commits(&self) -> bool {
+        self.graph_stream.is_some()
+    }
+
+    fn load_more_commits(&mut self, load_all: bool) -> Result<usize> {
+        // The file history list is complete; paging only applies to the graph.
+        if self.history.is_some() {
+            return Ok(0);
+        }
+        let Some(stream) = self.graph_stream.as_mut() else {
+            return Ok(0);
+        };
+        let rows = if load_all {
+            stream.collect_remaining()
+        } else {
+            stream.next_batch(GRAPH_PAGE_SIZE)
+        }
+        .with_context(|| format!("failed to load more commits for {}", self.repo.display()))?;
+        if stream.is_finished() {
+            self.graph_stream = None;
+        }
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let loaded = rows.len();
+        let matched = if self.search_input.trim().is_empty() {
+            rows.clone()
+        } else {
+            gitgraph_core::filter_commits(&rows, &self.search_query())
+                .map_err(|e| anyhow!("search failed: {e}"))?
+        };
+        self.list_cache.extend(
+            matched
+                .iter()
+                .map(|row| build_commit_line(row, self.graph_style)),
+        );
+        self.filtered_rows.extend(matched);
+        self.graph.commits.extend(rows);
+        Ok(loaded)
+    }
+
+    fn ensure_commits_loaded_through(&mut self, index: usize) -> Result<()> {
+        for _ in 0..SEARCH_SCAN_PAGES {
+            if !self.has_more_commits()
+                || index.saturating_add(GRAPH_LOAD_AHEAD_ROWS) < self.filtered_rows.len()
+            {
+                break;
+            }
+            if self.load_more_commits(false)? == 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn search_query(&self) -> CommitSearchQuery {
+        CommitSearchQuery {
             text: self.search_input.clone(),
             ..CommitSearchQuery::default()
-        };
+        }
+    }
+
+    fn apply_search(&mut self, prefetch_artifacts: bool) -> Result<()> {
+        self.pending_search_apply = false;
+        self.history = None;
+        let query = self.search_query();
         self.filtered_rows = gitgraph_core::filter_commits(&self.graph.commits, &query)
             .map_err(|e| anyhow!("search failed: {e}"))?;
+        let selected = self.list_state.selected().unwrap_or(0);
+        self.ensure_commits_loaded_through(selected)?;
+        // Only the loaded pages were searched while more history remains.
+        let scanned = if self.has_more_commits() {
+            format!(
+                " in the first {} commit(s); scroll or press G to search further",
+                self.graph.commits.len()
+            )
+        } else {
+            String::new()
+        };
         if self.filtered_rows.is_empty() {
             self.list_state.select(None);
             self.clear_current_commit_view();
             self.status = if self.search_input.trim().is_empty() {
                 "No commits loaded".to_string()
             } else {
-                format!("No matches for {:?}", self.search_input)
+                format!("No matches for {:?}{scanned}", self.search_input)
             };
             self.rebuild_list_cache();
             return Ok(());
         }
 
-        let selected = self.list_state.selected().unwrap_or(0);
         let bounded = min(selected, self.filtered_rows.len().saturating_sub(1));
         self.list_state.select(Some(bounded));
         self.rebuild_list_cache();
@@ -593,7 +841,7 @@ impl<'a> TuiApp<'a> {
         self.status = if self.search_input.trim().is_empty() {
             format!("Loaded {} commit(s)", self.filtered_rows.len())
         } else {
-            format!("Matched {} commit(s)", self.filtered_rows.len())
+            format!("Matched {} commit(s){scanned}", self.filtered_rows.len())
         };
         Ok(())
     }
@@ -622,13 +870,18 @@ impl<'a> TuiApp<'a> {
     }
 
     fn goto_bottom_commit(&mut self) -> Result<()> {
+        self.load_more_commits(true)?;
         if self.filtered_rows.is_empty() {
+            if !self.search_input.trim().is_empty() {
+                self.status = format!("No matches for {:?}", self.search_input);
+            }
             return Ok(());
         }
         self.set_commit_index(self.filtered_rows.len().saturating_sub(1))
     }
 
     fn set_commit_index(&mut self, index: usize) -> Result<()> {
+        self.ensure_commits_loaded_through(index)?;
         if self.filtered_rows.is_empty() {
             self.list_state.select(None);
             self.clear_current_commit_view();
@@ -652,6 +905,9 @@ impl<'a> TuiApp<'a> {
         }
 
         self.current_commit_hash = Some(hash.clone());
+        if self.comparison.take().is_some() {
+            self.rebuild_list_cache();
+        }
         self.file_list_state = ListState::default();
         self.current_files = self
             .commit_cache
@@ -666,6 +922,172 @@ impl<'a> TuiApp<'a> {
         }
     }
 
+    // First press marks the selected commit, the second shows the combined
+    // diff from the marked commit to the selected one. Pressing it again on
+    // the marked commit, or while comparing, clears the mark.
+    fn toggle_compare_mark(&mut self) -> Result<()> {
+        let Some(hash) = self.selected_row().map(|row| row.hash.clone()) else {
+            return Ok(());
+        };
+        if self.comparison.is_some() || self.marked_commit.as_deref() == Some(hash.as_str()) {
+            self.marked_commit = None;
+            self.current_commit_hash = None;
+            self.sync_selected_commit_from_cache();
+            self.rebuild_list_cache();
+            self.status = "Compare mark cleared".to_string();
+            return Ok(());
+        }
+        if hash == UNCOMMITTED_HASH {
+            self.status = "Select a commit to compare".to_string();
+            return Ok(());
+        }
+        let Some(marked) = self.marked_commit.take() else {
+            self.status = format!(
+                "Marked {}; select another commit and press x to compare",
+                short_hash(&hash)
+            );
+            self.marked_commit = Some(hash);
+            self.rebuild_list_cache();
+            return Ok(());
+        };
+
+        let comparison = self
+            .service
+            .compare_revisions(&self.repo, &marked, &hash)
+            .with_context(|| format!("failed to compare {marked} and {hash}"))?;
+        let key = compare_cache_key(&comparison.left_hash, &comparison.right_hash);
+        self.commit_cache.insert(
+            key.clone(),
+            CommitArtifactCache {
+                files: Arc::new(comparison.files.clone()),
+                patches: HashMap::new(),
+            },
+        );
+        self.status = format!(
+            "Comparing {}..{}: {} file(s), x to exit",
+            short_hash(&marked),
+            short_hash(&hash),
+            comparison.files.len()
+        );
+        self.current_commit_hash = Some(key);
+        self.current_files = Arc::new(comparison.files.clone());
+        self.comparison = Some(comparison);
+        self.file_list_state = ListState::default();
+        self.file_list_state
+            .select((!self.current_files.is_empty()).then_some(0));
+        self.diff_scroll = 0;
+        self.rebuild_list_cache();
+        self.queue_selected_commit_prefetch();
+        Ok(())
+    }
+
+    fn show_selected_file_history(&mut self) -> Result<()> {
+        let Some(path) = self.selected_file_path() else {
+            return Ok(());
+        };
+        let entries = self
+            .service
+            .file_history(&self.repo, Path::new(&path))
+            .with_context(|| format!("failed to load history of {path}"))?;
+        let Some(first) = entries.first() else {
+            self.status = format!("No history for {path}");
+            return Ok(());
+        };
+        let view = HistoryView {
+            path: first.path.clone(),
+            range: None,
+            paths: entries
+                .iter()
+                .map(|entry| (entry.row.hash.clone(), entry.path.clone()))
+                .collect(),
+            hunks: HashMap::new(),
+        };
+        self.enter_history_view(view, entries.into_iter().map(|entry| entry.row).collect());
+        Ok(())
+    }
+
+    // Traces the new-side range of the hunk at the top of the Patch pane,
+    // numbered as in the commit being viewed.
+    fn show_hunk_line_history(&mut self) -> Result<()> {
+        let (Some(hash), Some(path)) =
+            (self.current_commit_hash.clone(), self.selected_file_path())
+        else {
+            return Ok(());
+        };
+        let Some(lines) = self.current_patch_lines() else {
+            return Ok(());
+        };
+        let texts = lines.iter().map(line_text).collect::<Vec<_>>();
+        let top = min(self.diff_scroll, texts.len().saturating_sub(1));
+        let Some(hunk) = texts[..=top]
+            .iter()
+            .rev()
+            .chain(texts.get(top + 1..).unwrap_or_default())
+            .find_map(|text| parse_hunk_header(text))
+        else {
+            self.status = "No hunk to trace in this patch".to_string();
+            return Ok(());
+        };
+        let start = hunk.new_start.max(1);
+        let end = start + hunk.new_lines.saturating_sub(1);
+        let revision = hash
+            .rsplit("..")
+            .next()
+            .unwrap_or(hash.as_str())
+            .to_string();
+        let entries = self
+            .service
+            .line_history_at(&self.repo, &revision, Path::new(&path), start, end)
+            .with_context(|| format!("failed to load history of {path}:{start}-{end}"))?;
+        if entries.is_empty() {
+            self.status = format!("No history for {path}:{start}-{end}");
+            return Ok(());
+        }
+        let mut view = HistoryView {
+            path: entries[0].path.clone(),
+            range: Some((start, end)),
+            paths: HashMap::new(),
+            hunks: HashMap::new(),
+        };
+        let mut rows = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let patch = entry
+                .hunks
+                .iter()
+                .flat_map(|hunk| std::iter::once(&hunk.header).chain(&hunk.lines))
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let hash = entry.row.hash.clone();
+            view.hunks.insert(
+                hash.clone(),
+                render_patch_lines(&patch, self.max_patch_lines),
+            );
+            view.paths.insert(hash, entry.path);
+            rows.push(entry.row);
+        }
+        self.enter_history_view(view, rows);
+        Ok(())
+    }
+
+    fn enter_history_view(&mut self, view: HistoryView, rows: Vec<GraphRow>) {
+        self.status = format!(
+            "History of {}: {} commit(s), Esc to return",
+            view.title(),
+            rows.len()
+        );
+        self.history = Some(view);
+        self.marked_commit = None;
+        self.comparison = None;
+        self.current_commit_hash = None;
+        self.filtered_rows = rows;
+        self.list_state.select(Some(0));
+        self.focus = FocusPane::Commits;
+        self.rebuild_list_cache();
+        self.sync_selected_commit_from_cache();
+        self.queue_selected_commit_prefetch();
+    }
+
     fn ensure_current_commit_files_loaded(&mut self) -> Result<()> {
         if self.current_commit_hash.is_none() {
             self.sync_selected_commit_from_cache();
@@ -753,7 +1175,7 @@ impl<'a> TuiApp<'a> {
             .collect::<Vec<_>>();
         lines.push(Line::from(Span::raw("")));
         lines.push(Line::from(Span::styled(
-            "[c] auto-commit  [Esc/q] close  [PgUp/PgDn/j/k] scroll",
+            "[c] commit all  [s] commit staged  [a] amend HEAD  [Esc/q] close  [PgUp/PgDn/j/k] scroll",
             Style::default().fg(Color::DarkGray),
         )));
         self.commit_desc_popup_lines = Some(lines);
@@ -761,32 +1183,48 @@ impl<'a> TuiApp<'a> {
         self.commit_desc_popup_scroll = 0;
         self.commit_desc_popup_area = None;
         self.last_generated_commit_desc = Some(text);
-        self.status = "Commit description generated (press c to auto-commit)".to_string();
+        self.status =
+            "Commit description generated (c commit all, s commit staged, a amend)".to_string();
         Ok(())
     }
 
-    fn auto_commit_from_popup(&mut self) -> Result<()> {
+    fn auto_commit_from_popup(&mut self, mode: PopupCommit) -> Result<()> {
         let message = self
             .last_generated_commit_desc
-            .as_deref()
+            .clone()
             .ok_or_else(|| anyhow!("no generated commit description available"))?;
-        let result = super::auto_commit_with_message(&self.repo, &self.git_binary, message)
+        let options = CommitOptions {
+            message,
+            stage_all: mode == PopupCommit::All,
+            amend: mode == PopupCommit::Amend,
+            ..CommitOptions::default()
+        };
+        let row = self
+            .service
+            .commit(&self.repo, &options)
             .with_context(|| format!("failed to auto-commit in {}", self.repo.display()))?;
+        let title = match mode {
+            PopupCommit::All => "Auto-commit completed",
+            PopupCommit::StagedOnly => "Committed staged changes",
+            PopupCommit::Amend => "Amended HEAD",
+        };
         let mut lines = vec![
             Line::from(Span::styled(
-                "Auto-commit completed",
+                title,
                 Style::default()
                     .fg(Color::LightGreen)
                     .add_modifier(Modifier::BOLD),
             )),
             Line::from(Span::raw("")),
-        ];
-        lines.extend(result.lines().map(|line| {
             Line::from(Span::styled(
-                sanitize_terminal_text(line),
+                format!(
+                    "{} {}",
+                    row.short_hash,
+                    sanitize_terminal_text(&row.subject)
+                ),
                 Style::default().fg(Color::White),
-            ))
-        }));
+            )),
+        ];
         lines.push(Line::from(Span::raw("")));
         lines.push(Line::from(Span::styled(
             "[p] auto-push  [Esc/q] close",
@@ -799,7 +1237,7 @@ impl<'a> TuiApp<'a> {
         if let Err(err) = self.refresh() {
             self.status = format!("auto-commit done, refresh failed: {err}");
         } else {
-            self.status = "Auto-commit completed (press p to auto-push)".to_string();
+            self.status = format!("{title} (press p to auto-push)");
         }
         Ok(())
     }
@@ -965,10 +1403,24 @@ impl<'a> TuiApp<'a> {
             return Ok(());
         }
 
-        let patch = self
-            .service
-            .commit_file_patch(&self.repo, &hash, &file_path, 3)
-            .with_context(|| format!("failed to load patch for {file_path} in {hash}"))?;
+        let patch = match hash.split_once("..") {
+            Some((left, right)) => self
+                .service
+                .compare_file_patch(&self.repo, left, right, &file_path, 3)
+                .with_context(|| format!("failed to load patch for {file_path} in {hash}"))?,
+            None => match self.worktree_side.filter(|_| hash == UNCOMMITTED_HASH) {
+                Some(side) => self
+                    .service
+                    .working_tree_file_patch(&self.repo, &file_path, side, HUNK_CONTEXT_LINES)
+                    .with_context(|| {
+                        format!("failed to load {} patch for {file_path}", side.label())
+                    })?,
+                None => self
+                    .service
+                    .commit_file_patch(&self.repo, &hash, &file_path, 3)
+                    .with_context(|| format!("failed to load patch for {file_path} in {hash}"))?,
+            },
+        };
         let rendered = render_patch_lines(&patch, self.max_patch_lines);
         if let Some(entry) = self.commit_cache.get_mut(&hash) {
             entry.patches.insert(file_path, rendered);
@@ -976,6 +1428,386 @@ impl<'a> TuiApp<'a> {
         Ok(())
     }
 
+    fn is_uncommitted_selected(&self) -> bool {
+        self.current_commit_hash.as_deref() == Some(UNCOMMITTED_HASH)
+    }
+
+    // Line-by-line scrolling while selecting lines, so the selection can end anywhere.
+    fn diff_step(&self) -> i16 {
+        if self.active_diff_mark().is_some() {
+            1
+        } else {
+            3
+        }
+    }
+
+    fn active_diff_mark(&self) -> Option<usize> {
+        let mark = self.diff_mark.as_ref()?;
+        (self.current_commit_hash.as_ref() == Some(&mark.hash)
+            && self.selected_file_path().as_ref() == Some(&mark.path))
+        .then_some(mark.line)
+    }
+
+    fn toggle_diff_mark(&mut self) {
+        if self.active_diff_mark().is_some() {
+            self.diff_mark = None;
+            self.status = "Line selection cleared".to_string();
+            return;
+        }
+        let (Some(hash), Some(path)) =
+            (self.current_commit_hash.clone(), self.selected_file_path())
+        else {
+            return;
+        };
+        if self.current_patch_len() == 0 {
+            return;
+        }
+        self.diff_mark = Some(DiffMark {
+            hash,
+            path,
+            line: self.diff_scroll,
+        });
+        self.status =
+            "Selecting lines: scroll to extend, s/u/D to apply, Space to cancel".to_string();
+    }
+
+    fn set_worktree_side(&mut self, side: Option<WorktreeDiffSide>) -> Result<()> {
+        self.worktree_side = side;
+        self.diff_mark = None;
+        self.diff_scroll = 0;
+        if let Some(entry) = self.commit_cache.get_mut(UNCOMMITTED_HASH) {
+            entry.patches.clear();
+        }
+        self.ensure_selected_file_patch_loaded()
+    }
+
+    fn cycle_worktree_side(&mut self) -> Result<()> {
+        if !self.is_uncommitted_selected() {
+            self.status = "Only the uncommitted changes row has staged/unstaged diffs".to_string();
+            return Ok(());
+        }
+        let next = match self.worktree_side {
+            None => Some(WorktreeDiffSide::Unstaged),
+            Some(WorktreeDiffSide::Unstaged) => Some(WorktreeDiffSide::Staged),
+            Some(WorktreeDiffSide::Staged) => None,
+        };
+        self.set_worktree_side(next)?;
+        self.status = format!("Showing {}", worktree_side_label(next));
+        Ok(())
+    }
+
+    // `M` merges the selected commit into HEAD and `R` rebases HEAD onto it.
+    // Both are previewed with `git merge-tree` first; a rebase replays each
+    // commit, so the preview only approximates its conflicts.
+    fn merge_or_rebase_selected(&mut self, key: char) -> Result<()> {
+        let Some(hash) = self.selected_row().map(|row| row.hash.clone()) else {
+            return Ok(());
+        };
+        if hash == UNCOMMITTED_HASH {
+            self.status = "Select a commit to merge or rebase onto".to_string();
+            return Ok(());
+        }
+        let template_id = if key == 'M' {
+            "commit:8:merge"
+        } else {
+            "commit:6:rebase"
+        };
+        let request = ActionRequest {
+            template_id: template_id.to_string(),
+            params: HashMap::new(),
+            enabled_options: HashSet::new(),
+            context: ActionContext {
+                commit_hash: Some(hash.clone()),
+                ..ActionContext::default()
+            },
+        };
+        if let Some(operation) = &self.graph.operation {
+            self.status = format!(
+                "A {} is in progress; continue (C) or abort (A) it first",
+                operation.kind.label()
+            );
+            return Ok(());
+        }
+        if let Some(pending) = self.pending_action.take()
+            && pending.key == key
+            && pending.run == PendingRun::Action(Box::new(request.clone()))
+        {
+            return self.run_pending_action(pending);
+        }
+
+        // The first press only previews; `git merge-tree --write-tree` needs
+        // git 2.38, so older versions confirm without a preview.
+        let title = format!("{} {}", merge_key_verb(key), short_hash(&hash));
+        let outcome = match self.service.preview_merge(&self.repo, "HEAD", &hash) {
+            Ok(preview) if preview.clean => "merges cleanly".to_string(),
+            Ok(preview) => format!("will conflict: {}", preview.summary()),
+            Err(err) => format!("could not be previewed ({err})"),
+        };
+        self.status = format!("{title} {outcome}; press {key} again to continue, Esc to cancel");
+        self.pending_action = Some(PendingAction {
+            key,
+            title,
+            run: PendingRun::Action(Box::new(request)),
+        });
+        Ok(())
+    }
+
+    fn run_bisect_mark(&mut self, mark: BisectMark) {
+        if let Err(err) = self.bisect_selected(mark) {
+            self.bisect_mark = None;
+            self.rebuild_list_cache();
+            self.status = format!("bisect failed: {err:#}");
+        }
+    }
+
+    // Without a bisect, `B` picks the two ends and starts one; during a
+    // bisect `B` / `W` mark the selected commit bad / good. The selection
+    // then follows the commit git checked out for testing.
+    fn bisect_selected(&mut self, mark: BisectMark) -> Result<()> {
+        let Some(row) = self.selected_row().cloned() else {
+            return Ok(());
+        };
+        let result = if self.bisect.is_some() {
+            self.service
+                .mark_bisect(&self.repo, mark, Some(row.hash.as_str()))
+        } else {
+            let Some(marked) = self.bisect_mark.take() else {
+                self.status = format!(
+                    "Bisect end {}; select the other end and press B to start",
+                    short_hash(&row.hash)
+                );
+                self.bisect_mark = Some(row.hash);
+                self.rebuild_list_cache();
+                return Ok(());
+            };
+            if marked == row.hash {
+                self.status = "Bisect mark cleared".to_string();
+                self.rebuild_list_cache();
+                return Ok(());
+            }
+            let first = self
+                .graph
+                .commits
+                .iter()
+                .find(|candidate| candidate.hash == marked)
+                .cloned()
+                .ok_or_else(|| anyhow!("commit {} is no longer loaded", short_hash(&marked)))?;
+            self.service.start_bisect(&self.repo, &first, &row)
+        };
+        self.refresh()?;
+        let status = result?;
+        if let Some(idx) = status.current.as_ref().and_then(|current| {
+            self.filtered_rows
+                .iter()
+                .position(|row| row.hash == *current)
+        }) {
+            self.list_state.select(Some(idx));
+            self.sync_selected_commit_from_cache();
+            self.queue_selected_commit_prefetch();
+        }
+        self.status = match &status.first_bad {
+            Some(_) => format!("Bisect done, {}; press A to reset", status.summary()),
+            None => format!("Bisect: {}; B bad, W good, S skip", status.summary()),
+        };
+        Ok(())
+    }
+
+    // Aborting or skipping throws away conflict resolutions and the commit
+    // being applied, so both are classified as destructive; bisect steps
+    // only move `HEAD`.
+    fn control_operation(&mut self, key: char, control: RepoOperationControl) -> Result<()> {
+        if let Some(pending) = self.pending_action.take()
+            && pending.run == PendingRun::Control(control)
+        {
+            return self.run_pending_action(pending);
+        }
+        let Some(kind) = self
+            .graph
+            .operation
+            .as_ref()
+            .map(|operation| operation.kind)
+        else {
+            return Ok(());
+        };
+        if !kind.supports(control) {
+            self.status = format!("A {} cannot {}", kind.label(), control.label());
+            return Ok(());
+        }
+        let pending = PendingAction {
+            key,
+            title: format!("{} {}", kind.label(), control.label()),
+            run: PendingRun::Control(control),
+        };
+        let risk = classify_git_args(&operation_control_args(kind, control)?);
+        self.run_or_confirm(pending, risk)
+    }
+
+    // The first press shows what undoing the last logged action restores;
+    // the second applies it.
+    fn undo_last_operation(&mut self) -> Result<()> {
+        if let Some(pending) = self.pending_action.take()
+            && matches!(pending.run, PendingRun::Undo(_))
+        {
+            return self.run_pending_action(pending);
+        }
+        let Some(plan) = self.service.plan_undo_last(&self.repo)? else {
+            self.status = "Nothing to undo".to_string();
+            return Ok(());
+        };
+        self.status = format!(
+            "{}{}; press U again to undo, Esc to cancel",
+            plan.summary(),
+            if plan.affects_worktree {
+                " (moves the working tree)"
+            } else {
+                ""
+            }
+        );
+        self.pending_action = Some(PendingAction {
+            key: 'U',
+            title: format!("Undo {}", plan.operation.action_id),
+            run: PendingRun::Undo(Box::new(plan)),
+        });
+        Ok(())
+    }
+
+    // Destructive steps wait for their key to be pressed again.
+    fn run_or_confirm(&mut self, pending: PendingAction, risk: ActionRisk) -> Result<()> {
+        let Some(reason) = risk.reason() else {
+            return self.run_pending_action(pending);
+        };
+        self.status = format!(
+            "{} is destructive ({reason}); press {} again to run it, Esc to cancel",
+            pending.title, pending.key
+        );
+        self.pending_action = Some(pending);
+        Ok(())
+    }
+
+    fn run_pending_action(&mut self, pending: PendingAction) -> Result<()> {
+        let result = match pending.run {
+            PendingRun::Hunk {
+                action,
+                path,
+                selection,
+            } => return self.run_hunk(action, &path, selection),
+            PendingRun::Action(request) => self
+                .service
+                .execute_action(&self.repo, *request, &self.default_remote_name)
+                .map(|result| match result.log_warning {
+                    Some(warning) => format!("git {} ({warning})", result.command_line),
+                    None => format!("git {}", result.command_line),
+                }),
+            PendingRun::Undo(plan) => self
+                .service
+                .apply_undo(&self.repo, &plan)
+                .map(|()| "done".to_string()),
+            PendingRun::Control(control) => self
+                .service
+                .control_operation(&self.repo, control)
+                .map(|after| match after {
+                    Some(operation) => format!("{} still in progress", operation.summary()),
+                    None => "done".to_string(),
+                }),
+        };
+        // A merge or rebase that stops on conflicts still moves refs.
+        self.refresh()?;
+        self.status = format!("{}: {}", pending.title, result?);
+        Ok(())
+    }
+
+    fn run_hunk_action(&mut self, action: HunkAction) {
+        if let Err(err) = self.apply_hunk_action(action) {
+            self.status = format!("{} failed: {err:#}", action.label());
+        }
+    }
+
+    fn apply_hunk_action(&mut self, action: HunkAction) -> Result<()> {
+        if !self.is_uncommitted_selected() {
+            self.status = "Hunks can only be staged from the uncommitted changes row".to_string();
+            return Ok(());
+        }
+        let Some(path) = self.selected_file_path() else {
+            return Ok(());
+        };
+        // Hunk indexes only make sense against the diff they were taken from.
+        let side = action.side();
+        if self.worktree_side != Some(side) {
+            self.set_worktree_side(Some(side))?;
+            self.status = format!(
+                "Showing {}; press again to {} the hunk at the top",
+                worktree_side_label(Some(side)),
+                action.label()
+            );
+            return Ok(());
+        }
+        let Some(lines) = self.current_patch_lines() else {
+            return Ok(());
+        };
+        let texts = lines.iter().map(line_text).collect::<Vec<_>>();
+        let cursor = min(self.diff_scroll, texts.len().saturating_sub(1));
+        let selection = match self.active_diff_mark() {
+            Some(mark) => match (hunk_position(&texts, mark), hunk_position(&texts, cursor)) {
+                (Some((hunk, start)), Some((end_hunk, end))) if hunk == end_hunk => HunkSelection {
+                    hunk,
+                    lines: Some((start.unwrap_or(0), end.unwrap_or(0))),
+                },
+                _ => {
+                    self.status = "The line selection must stay within one hunk".to_string();
+                    return Ok(());
+                }
+            },
+            None => match hunk_position(&texts, cursor) {
+                Some((hunk, _)) => HunkSelection { hunk, lines: None },
+                None => {
+                    self.status = format!("No {} hunk to {}", side.label(), action.label());
+                    return Ok(());
+                }
+            },
+        };
+        let pending = PendingAction {
+            key: hunk_action_key(action),
+            title: format!("{} {path}", action.label()),
+            run: PendingRun::Hunk {
+                action,
+                path,
+                selection,
+            },
+        };
+        if let Some(previous) = self.pending_action.take()
+            && previous.run == pending.run
+        {
+            return self.run_pending_action(pending);
+        }
+        self.run_or_confirm(pending, classify_git_args(&hunk_apply_args(action)))
+    }
+
+    fn run_hunk(&mut self, action: HunkAction, path: &str, selection: HunkSelection) -> Result<()> {
+        self.service
+            .apply_hunk(&self.repo, path, action, &selection)
+            .with_context(|| format!("failed to {} hunk of {path}", action.label()))?;
+        self.reload_worktree_changes()?;
+        let what = if selection.lines.is_some() {
+            "selected lines"
+        } else {
+            "hunk"
+        };
+        self.status = match action {
+            HunkAction::Stage => format!("Staged {what} of {path}"),
+            HunkAction::Unstage => format!("Unstaged {what} of {path}"),
+            HunkAction::Discard => format!("Discarded {what} of {path}"),
+        };
+        Ok(())
+    }
+
+    fn reload_worktree_changes(&mut self) -> Result<()> {
+        self.worktree_status = self.service.working_tree_status(&self.repo).ok();
+        self.commit_cache.remove(UNCOMMITTED_HASH);
+        self.diff_mark = None;
+        self.ensure_current_commit_files_loaded()?;
+        self.ensure_selected_file_patch_loaded()
+    }
+
     fn scroll_diff(&mut self, delta: i16) {
         let max_scroll = self.max_diff_scroll();
         if delta > 0 {
@@ -1016,6 +1848,9 @@ impl<'a> TuiApp<'a> {
 
     fn current_patch_lines(&self) -> Option<&Vec<Line<'static>>> {
         let hash = self.current_commit_hash.as_ref()?;
+        if let Some(hunks) = self.history.as_ref().and_then(|view| view.hunks.get(hash)) {
+            return Some(hunks);
+        }
         let file_path = self.selected_file_path()?;
         self.commit_cache.get(hash)?.patches.get(&file_path)
     }
@@ -1033,13 +1868,61 @@ impl<'a> TuiApp<'a> {
     }
 
     fn rebuild_list_cache(&mut self) {
+        let compared = self.comparison.as_ref().map(|comparison| {
+            [
+                comparison.left_hash.as_str(),
+                comparison.right_hash.as_str(),
+            ]
+        });
         self.list_cache = self
             .filtered_rows
             .iter()
-            .map(|row| build_commit_line(row, self.graph_style))
+            .map(|row| {
+                let mut line = build_commit_line(row, self.graph_style);
+                if let Some(path) = self.history.as_ref().and_then(|history| {
+                    history
+                        .paths
+                        .get(&row.hash)
+                        .filter(|path| **path != history.path)
+                }) {
+                    line.push_span(Span::styled(
+                        format!("  [{path}]"),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                if let Some((tag, color)) = self.bisect_tag(&row.hash) {
+                    line.push_span(Span::styled(
+                        format!("  [{tag}]"),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                let marked = self.marked_commit.as_deref() == Some(row.hash.as_str())
+                    || self.bisect_mark.as_deref() == Some(row.hash.as_str())
+                    || compared.is_some_and(|hashes| hashes.contains(&row.hash.as_str()));
+                if marked {
+                    line.style(Style::default().bg(Color::Rgb(70, 50, 0)))
+                } else {
+                    line
+                }
+            })
             .collect::<Vec<_>>();
     }
 
+    fn bisect_tag(&self, hash: &str) -> Option<(&'static str, Color)> {
+        let bisect = self.bisect.as_ref()?;
+        if bisect.first_bad.as_deref() == Some(hash) {
+            return Some(("first bad", Color::LightRed));
+        }
+        match bisect.mark_of(hash) {
+            Some(BisectMark::Bad) => Some(("bad", Color::Red)),
+            Some(BisectMark::Good) => Some(("good", Color::LightGreen)),
+            Some(BisectMark::Skip) => Some(("skip", Color::DarkGray)),
+            None if bisect.current.as_deref() == Some(hash) => Some(("testing", Color::Yellow)),
+            None if bisect.is_candidate(hash) => Some(("untested", Color::Yellow)),
+            None => None,
+        }
+    }
+
     fn draw(&mut self, frame: &mut Frame) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -1067,7 +1950,11 @@ impl<'a> TuiApp<'a> {
             ]),
             Line::from(vec![
                 Span::styled(
-                    format!("commits: {}", self.filtered_rows.len()),
+                    format!(
+                        "commits: {}{}",
+                        self.filtered_rows.len(),
+                        if self.has_more_commits() { "+" } else { "" }
+                    ),
                     Style::default().fg(Color::White),
                 ),
                 Span::raw("  "),
@@ -1075,6 +1962,15 @@ impl<'a> TuiApp<'a> {
                     format!("focus: {}", self.focus.as_str()),
                     Style::default().fg(Color::Yellow),
                 ),
+                Span::raw("  "),
+                Span::styled(
+                    format!("mode: {}", self.base_query.mode.label()),
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::raw("  "),
+                Span::styled(self.branch_sync_summary(), Style::default().fg(Color::Cyan)),
+                Span::raw("  "),
+                self.worktree_status_span(),
             ]),
         ]);
         frame.render_widget(header, chunks[0]);
@@ -1090,7 +1986,7 @@ impl<'a> TuiApp<'a> {
         let right = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(10),
+                Constraint::Length(11),
                 Constraint::Length(8),
                 Constraint::Min(8),
             ])
@@ -1110,11 +2006,35 @@ impl<'a> TuiApp<'a> {
         self.search_area = Some(chunks[2]);
         frame.render_widget(search, chunks[2]);
 
-        let footer = Paragraph::new(format!(
-            "{} | q quit | tab switch pane | j/k move | g/G top/bottom | PgUp/PgDn diff | r refresh | m commit-desc | mouse: wheel/click",
+        let mut footer_spans = Vec::new();
+        if let Some(operation) = &self.graph.operation {
+            let mut controls = [
+                ('C', RepoOperationControl::Continue),
+                ('A', RepoOperationControl::Abort),
+                ('S', RepoOperationControl::Skip),
+            ]
+            .into_iter()
+            .filter(|(_, control)| operation.kind.supports(*control))
+            .map(|(key, control)| format!("{key} {}", control.label()))
+            .collect::<Vec<_>>();
+            let mut summary = operation.summary();
+            if let Some(bisect) = &self.bisect {
+                summary.push_str(&format!(": {}", bisect.summary()));
+                controls.splice(0..0, ["B bad".to_string(), "W good".to_string()]);
+            }
+            footer_spans.push(Span::styled(
+                format!("{summary} in progress ({}) | ", controls.join(", ")),
+                Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        footer_spans.push(Span::raw(format!(
+            "{} | q quit | tab switch pane | j/k move | g/G top/bottom | PgUp/PgDn diff | r refresh | v graph mode | x mark/compare | m commit-desc | mouse: wheel/click",
             self.status
-        ))
-        .style(Style::default().fg(Color::DarkGray));
+        )));
+        let footer =
+            Paragraph::new(Line::from(footer_spans)).style(Style::default().fg(Color::DarkGray));
         frame.render_widget(footer, chunks[3]);
 
         self.draw_commit_desc_popup(frame);
@@ -1192,7 +2112,10 @@ impl<'a> TuiApp<'a> {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Commit Graph")
+                    .title(match &self.history {
+                        Some(history) => format!("History of {} (Esc to return)", history.title()),
+                        None => "Commit Graph".to_string(),
+                    })
                     .border_style(border_style),
             )
             .highlight_style(
@@ -1207,14 +2130,68 @@ impl<'a> TuiApp<'a> {
         frame.render_stateful_widget(list, area, &mut local_state);
     }
 
+    fn worktree_status_span(&self) -> Span<'static> {
+        let Some(status) = self.worktree_status.as_ref() else {
+            return Span::styled("worktree: ?", Style::default().fg(Color::DarkGray));
+        };
+        let color = if status.conflicted().next().is_some() {
+            Color::LightRed
+        } else if status.is_clean() {
+            Color::LightGreen
+        } else {
+            Color::Yellow
+        };
+        Span::styled(
+            format!("worktree: {}", status.summary()),
+            Style::default().fg(color),
+        )
+    }
+
+    fn branch_sync_summary(&self) -> String {
+        let local = self.graph.branches.iter().filter(|b| !b.is_remote);
+        let (to_push, to_pull, gone) = local.fold((0, 0, 0), |(push, pull, gone), branch| {
+            (
+                push + usize::from(branch.ahead > 0),
+                pull + usize::from(branch.behind > 0),
+                gone + usize::from(branch.upstream_gone),
+            )
+        });
+        let mut summary = format!("branches: {to_push} to push, {to_pull} to pull");
+        if gone > 0 {
+            summary.push_str(&format!(", {gone} gone"));
+        }
+        summary
+    }
+
+    fn describe_ref(&self, git_ref: &gitgraph_core::GitRef) -> String {
+        let branch_name = match git_ref.kind {
+            GitRefKind::LocalBranch => Some(git_ref.name.as_str()),
+            GitRefKind::Head => git_ref.target.as_deref(),
+            _ => None,
+        };
+        let tracking = branch_name.and_then(|name| {
+            self.graph
+                .branches
+                .iter()
+                .find(|branch| !branch.is_remote && branch.name == name)
+                .and_then(branch_tracking_label)
+        });
+        match tracking {
+            Some(tracking) => format!("{} [{}]", git_ref.name, tracking),
+            None => git_ref.name.clone(),
+        }
+    }
+
     fn draw_details(&self, frame: &mut Frame, area: Rect) {
-        let text = if let Some(row) = self.selected_row() {
+        let text = if let Some(comparison) = self.comparison.as_ref() {
+            describe_comparison(comparison, &self.current_files)
+        } else if let Some(row) = self.selected_row() {
             let refs = if row.refs.is_empty() {
                 "(none)".to_string()
             } else {
                 row.refs
                     .iter()
-                    .map(|r| r.name.clone())
+                    .map(|r| self.describe_ref(r))
                     .collect::<Vec<_>>()
                     .join(", ")
             };
@@ -1255,12 +2232,26 @@ impl<'a> TuiApp<'a> {
             } else {
                 "(loading)".to_string()
             };
+            let committer = if row.committer_name.is_empty() {
+                format_git_time(row.committed_unix, row.committer_tz_offset_minutes)
+            } else {
+                format!(
+                    "{} <{}> {}",
+                    row.committer_name,
+                    row.committer_email,
+                    format_git_time(row.committed_unix, row.committer_tz_offset_minutes)
+                )
+            };
+            let annotation_lines = describe_commit_annotations(row);
             format!(
-                "hash: {}\nshort: {}\nauthor: {} <{}>\nrefs: {}\nparents: {}\nfiles changed: {}\nchange totals: {}\nselected file: {}\nsubject: {}",
+                "hash: {}\nshort: {}\nauthor: {} <{}> {}\ncommitter: {}\n{}refs: {}\nparents: {}\nfiles changed: {}\nchange totals: {}\nselected file: {}\nsubject: {}",
                 sanitize_terminal_text(&row.hash),
                 sanitize_terminal_text(&row.short_hash),
                 sanitize_terminal_text(&row.author_name),
                 sanitize_terminal_text(&row.author_email),
+                format_git_time(row.authored_unix, row.author_tz_offset_minutes),
+                sanitize_terminal_text(&committer),
+                annotation_lines,
                 sanitize_terminal_text(&refs),
                 sanitize_terminal_text(&parents),
                 files_changed,
@@ -1368,18 +2359,43 @@ impl<'a> TuiApp<'a> {
         let max_scroll = self.max_diff_scroll();
         self.diff_scroll = min(self.diff_scroll, max_scroll);
 
+        let title = if self.is_uncommitted_selected() {
+            format!(
+                "Patch [{}] (t switch, s/u/D stage/unstage/discard, Space select lines)",
+                worktree_side_label(self.worktree_side)
+            )
+        } else {
+            "Patch".to_string()
+        };
         let paragraph = if let Some(lines) = self.current_patch_lines() {
             let visible_height = inner_block_area(area)
                 .map(|inner| inner.height as usize)
                 .unwrap_or(0);
             let start = self.diff_scroll;
             let end = min(start.saturating_add(visible_height), lines.len());
-            let visible_lines = lines[start..end].to_vec();
+            let selected = self
+                .active_diff_mark()
+                .map(|mark| min(mark, start)..=mark.max(start));
+            let visible_lines = lines[start..end]
+                .iter()
+                .enumerate()
+                .map(|(offset, line)| {
+                    if selected
+                        .as_ref()
+                        .is_some_and(|range| range.contains(&(start + offset)))
+                    {
+                        line.clone()
+                            .patch_style(Style::default().add_modifier(Modifier::REVERSED))
+                    } else {
+                        line.clone()
+                    }
+                })
+                .collect::<Vec<_>>();
             Paragraph::new(Text::from(visible_lines))
                 .block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title("Patch")
+                        .title(title)
                         .border_style(border_style),
                 )
                 .wrap(Wrap { trim: false })
@@ -1406,6 +2422,201 @@ impl<'a> TuiApp<'a> {
     }
 }
 
+fn hunk_action_key(action: HunkAction) -> char {
+    match action {
+        HunkAction::Stage => 's',
+        HunkAction::Unstage => 'u',
+        HunkAction::Discard => 'D',
+    }
+}
+
+fn worktree_side_label(side: Option<WorktreeDiffSide>) -> &'static str {
+    match side {
+        None => "all changes",
+        Some(side) => side.label(),
+    }
+}
+
+fn line_text(line: &Line<'_>) -> String {
+    line.spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+// The hunk containing patch line `line` (or the first hunk when `line` is in the
+// file header) and the line's index within that hunk's body, `None` on the
+// `@@` header itself.
+fn hunk_position(texts: &[String], line: usize) -> Option<(usize, Option<usize>)> {
+    let mut position = None;
+    let mut hunk_count = 0;
+    for text in texts.iter().take(line + 1) {
+        if parse_hunk_header(text).is_some() {
+            position = Some((hunk_count, None));
+            hunk_count += 1;
+        } else if let Some((_, body)) = position.as_mut() {
+            *body = Some(body.map_or(0, |line: usize| line + 1));
+        }
+    }
+    position.or_else(|| {
+        texts
+            .iter()
+            .any(|text| parse_hunk_header(text).is_some())
+            .then_some((0, None))
+    })
+}
+
+// Renders a timestamp in the committer's own timezone, like `git log --date=iso`.
+fn compare_cache_key(left_hash: &str, right_hash: &str) -> String {
+    format!("{left_hash}..{right_hash}")
+}
+
+fn merge_key_verb(key: char) -> &'static str {
+    if key == 'M' { "Merge" } else { "Rebase onto" }
+}
+
+fn short_hash(hash: &str) -> &str {
+    hash.get(..7).unwrap_or(hash)
+}
+
+fn describe_comparison(comparison: &RevisionComparison, files: &[FileChange]) -> String {
+    let (added, removed) = files.iter().fold((0u64, 0u64), |(added, removed), file| {
+        (
+            added + u64::from(file.added.unwrap_or(0)),
+            removed + u64::from(file.removed.unwrap_or(0)),
+        )
+    });
+    let summarize = |rows: &[GraphRow]| {
+        rows.iter()
+            .take(3)
+            .map(|row| {
+                format!(
+                    "{} {}",
+                    row.short_hash,
+                    sanitize_terminal_text(&row.subject)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+    format!(
+        "compare: {} -> {}\nleft: {}\nright: {}\nmerge base: {}\nonly in left: {} {}\nonly in right: {} {}\nfiles changed: {}\nchange totals: +{} / -{}",
+        short_hash(&comparison.left_hash),
+        short_hash(&comparison.right_hash),
+        sanitize_terminal_text(&comparison.left_hash),
+        sanitize_terminal_text(&comparison.right_hash),
+        comparison
+            .merge_base
+            .as_deref()
+            .map(short_hash)
+            .unwrap_or("(none)"),
+        comparison.only_in_left.len(),
+        summarize(&comparison.only_in_left),
+        comparison.only_in_right.len(),
+        summarize(&comparison.only_in_right),
+        files.len(),
+        added,
+        removed
+    )
+}
+
+fn format_git_time(unix: i64, offset_minutes: i32) -> String {
+    let local = unix + i64::from(offset_minutes) * 60;
+    let days = local.div_euclid(86_400);
+    let secs = local.rem_euclid(86_400);
+    // Civil-from-days conversion (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let sign = if offset_minutes < 0 { '-' } else { '+' };
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} {sign}{:02}{:02}",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        offset_minutes.abs() / 60,
+        offset_minutes.abs() % 60
+    )
+}
+
+fn describe_commit_annotations(row: &GraphRow) -> String {
+    let join = |trailers: Vec<&gitgraph_core::CommitTrailer>| {
+        trailers
+            .iter()
+            .map(|t| match &t.identity {
+                Some(identity) => sanitize_terminal_text(&identity.name),
+                None => sanitize_terminal_text(&t.value),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let (co_authors, rest): (Vec<_>, Vec<_>) = row.trailers.iter().partition(|t| t.is_co_author());
+    let (sign_offs, others): (Vec<_>, Vec<_>) = rest.into_iter().partition(|t| t.is_sign_off());
+    let mut lines = String::new();
+    if let Some(signature) = row.signature.as_ref().filter(|sig| sig.status.is_signed()) {
+        let mut line = format!("signature: {}", signature.status.label());
+        if !signature.signer.is_empty() {
+            line.push_str(&format!(
+                " by {}",
+                sanitize_terminal_text(&signature.signer)
+            ));
+        }
+        if !signature.key_id.is_empty() {
+            line.push_str(&format!(
+                " (key {})",
+                sanitize_terminal_text(&signature.key_id)
+            ));
+        }
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    if !co_authors.is_empty() {
+        lines.push_str(&format!("co-authors: {}\n", join(co_authors)));
+    }
+    if !sign_offs.is_empty() {
+        lines.push_str(&format!("signed-off-by: {}\n", join(sign_offs)));
+    }
+    for note in row.notes.lines().filter(|line| !line.trim().is_empty()) {
+        lines.push_str(&format!("note: {}\n", sanitize_terminal_text(note)));
+    }
+    if !others.is_empty() {
+        let others = others
+            .iter()
+            .map(|t| sanitize_terminal_text(&format!("{}: {}", t.key, t.value)))
+            .collect::<Vec<_>>()
+            .join("; ");
+        lines.push_str(&format!("trailers: {}\n", others));
+    }
+    lines
+}
+
+fn branch_tracking_label(branch: &BranchInfo) -> Option<String> {
+    let upstream = branch.upstream.as_deref()?;
+    let upstream = upstream.strip_prefix("refs/remotes/").unwrap_or(upstream);
+    let status = if branch.upstream_gone {
+        "gone".to_string()
+    } else if branch.ahead == 0 && branch.behind == 0 {
+        "up to date".to_string()
+    } else {
+        format!("↑{} ↓{}", branch.ahead, branch.behind)
+    };
+    Some(format!("{upstream} {status}"))
+}
+
+fn signature_badge_color(status: SignatureStatus) -> Color {
+    match status {
+        SignatureStatus::Good => Color::LightGreen,
+        SignatureStatus::Bad => Color::LightRed,
+        SignatureStatus::Unknown => Color::LightYellow,
+        SignatureStatus::Expired | SignatureStatus::None => Color::Yellow,
+    }
+}
+
 fn build_commit_line(row: &GraphRow, style: GraphStyle) -> Line<'static> {
     let mut spans = build_graph_spans(row, style);
     let graph_gap = match style {
@@ -1442,6 +2653,16 @@ fn build_commit_line(row: &GraphRow, style: GraphStyle) -> Line<'static> {
             .add_modifier(Modifier::BOLD),
     ));
     spans.push(Span::raw("  "));
+    if let Some(status) = row.signature.as_ref().map(|signature| signature.status)
+        && let Some(badge) = signature_badge(status, style.into())
+    {
+        spans.push(Span::styled(
+            format!("{badge} "),
+            Style::default()
+                .fg(signature_badge_color(status))
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
     if is_merge {
         spans.push(Span::styled(
             "merge ",
@@ -1450,137 +2671,71 @@ fn build_commit_line(row: &GraphRow, style: GraphStyle) -> Line<'static> {
                 .add_modifier(Modifier::BOLD),
         ));
     }
-    spans.push(Span::styled(
-        subject,
+    let subject_style = if row.is_uncommitted() {
+        Style::default()
+            .fg(Color::LightYellow)
+            .add_modifier(Modifier::ITALIC)
+    } else {
         Style::default().fg(Color::White).add_modifier(if is_merge {
             Modifier::BOLD
         } else {
             Modifier::empty()
-        }),
-    ));
+        })
+    };
+    spans.push(Span::styled(subject, subject_style));
     spans.push(Span::styled(
         refs,
         Style::default()
             .fg(Color::LightGreen)
             .add_modifier(Modifier::BOLD),
     ));
-    spans.push(Span::styled(
-        format!("  · {}", author),
-        Style::default().fg(Color::DarkGray),
-    ));
-
-    Line::from(spans)
-}
-
-fn build_graph_spans(row: &GraphRow, style: GraphStyle) -> Vec<Span<'static>> {
-    let max_edge_lane = row
-        .edges
-        .iter()
-        .map(|e| e.to_lane)
-        .max()
-        .unwrap_or(row.lane);
-    let lane_count = max(max(row.active_lane_count, row.lane + 1), max_edge_lane + 1);
-    match style {
-        GraphStyle::Unicode => build_graph_spans_unicode(row, lane_count),
-        GraphStyle::Ascii => build_graph_spans_ascii(row, lane_count),
+    if row.collapsed_commits > 0 {
+        spans.push(Span::styled(
+            format!("  (+{} collapsed)", row.collapsed_commits),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
-}
-
-fn build_graph_spans_unicode(row: &GraphRow, lane_count: usize) -> Vec<Span<'static>> {
-    let mut symbols = vec![' '; lane_count];
-    let mut styles = vec![Style::default().fg(Color::DarkGray); lane_count];
-
-    for lane in 0..lane_count {
-        if lane < row.active_lane_count {
-            symbols[lane] = '│';
-            styles[lane] = Style::default()
-                .fg(lane_color(lane))
-                .add_modifier(Modifier::DIM);
-        }
-    }
-
-    for edge in row.edges.iter().filter(|e| e.to_lane != row.lane) {
-        let from = row.lane;
-        let to = edge.to_lane;
-        if to < from {
-            symbols[to] = '╭';
-            styles[to] = Style::default()
-                .fg(lane_color(to))
-                .add_modifier(Modifier::BOLD);
-            for lane in (to + 1)..from {
-                overlay_horizontal_lane(&mut symbols, &mut styles, lane, lane_color(from));
-            }
-        } else if to > from {
-            symbols[to] = '╮';
-            styles[to] = Style::default()
-                .fg(lane_color(to))
-                .add_modifier(Modifier::BOLD);
-            for lane in (from + 1)..to {
-                overlay_horizontal_lane(&mut symbols, &mut styles, lane, lane_color(from));
-            }
-        }
+    if !author.is_empty() {
+        spans.push(Span::styled(
+            format!("  · {}", author),
+            Style::default().fg(Color::DarkGray),
+        ));
     }
 
-    symbols[row.lane] = '●';
-    styles[row.lane] = Style::default()
-        .fg(lane_color(row.lane))
-        .add_modifier(Modifier::BOLD);
-
-    let mut out = Vec::with_capacity(lane_count.saturating_mul(2).saturating_add(2));
-    for lane in 0..lane_count {
-        if lane > 0 {
-            out.push(Span::styled("  ", Style::default().fg(Color::DarkGray)));
-        }
-        out.push(Span::styled(symbols[lane].to_string(), styles[lane]));
-    }
-    out
+    Line::from(spans)
 }
 
-fn build_graph_spans_ascii(row: &GraphRow, lane_count: usize) -> Vec<Span<'static>> {
-    let mut out = Vec::with_capacity(lane_count.saturating_mul(2).saturating_add(2));
-    for lane in 0..lane_count {
-        let color = lane_color(lane);
-        let (ch, style) = if lane == row.lane {
-            ('o', Style::default().fg(color).add_modifier(Modifier::BOLD))
-        } else if row
-            .edges
-            .iter()
-            .any(|e| e.to_lane == lane && e.to_lane != row.lane)
-        {
-            if lane < row.lane {
-                ('/', Style::default().fg(color))
-            } else {
-                ('\\', Style::default().fg(color))
-            }
-        } else if lane < row.active_lane_count {
-            ('|', Style::default().fg(color).add_modifier(Modifier::DIM))
-        } else {
-            (' ', Style::default().fg(Color::DarkGray))
-        };
-        if lane > 0 {
-            out.push(Span::styled(" ", Style::default().fg(Color::DarkGray)));
+impl From<GraphStyle> for TextGraphStyle {
+    fn from(value: GraphStyle) -> Self {
+        match value {
+            GraphStyle::Unicode => TextGraphStyle::Unicode,
+            GraphStyle::Ascii => TextGraphStyle::Ascii,
         }
-        out.push(Span::styled(ch.to_string(), style));
     }
-    if !row.parents.is_empty() {
-        out.push(Span::styled("|", Style::default().fg(Color::DarkGray)));
-    }
-    out
 }
 
-fn overlay_horizontal_lane(symbols: &mut [char], styles: &mut [Style], lane: usize, color: Color) {
-    let next = match symbols[lane] {
-        '│' => '┼',
-        '╭' | '╮' => '┬',
-        '┼' | '┬' | '─' => symbols[lane],
-        _ => '─',
-    };
-    symbols[lane] = next;
-    styles[lane] = Style::default().fg(color).add_modifier(Modifier::DIM);
+fn build_graph_spans(row: &GraphRow, style: GraphStyle) -> Vec<Span<'static>> {
+    graph_cells(row, style.into())
+        .into_iter()
+        .map(|cell| {
+            let style = match (cell.kind, cell.color_group) {
+                (TextGraphCellKind::Blank, _) | (_, None) => Style::default().fg(Color::DarkGray),
+                (TextGraphCellKind::Node | TextGraphCellKind::Junction, Some(color)) => {
+                    Style::default()
+                        .fg(lane_color(color))
+                        .add_modifier(Modifier::BOLD)
+                }
+                (TextGraphCellKind::Line, Some(color)) => Style::default()
+                    .fg(lane_color(color))
+                    .add_modifier(Modifier::DIM),
+            };
+            Span::styled(cell.glyph, style)
+        })
+        .collect()
 }
 
-fn lane_color(lane: usize) -> Color {
-    LANE_COLORS[lane % LANE_COLORS.len()]
+fn lane_color(color_group: u32) -> Color {
+    LANE_COLORS[color_group as usize % LANE_COLORS.len()]
 }
 
 fn render_patch_lines(patch: &str, max_lines: usize) -> Vec<Line<'static>> {
@@ -1630,11 +2785,11 @@ fn render_patch_lines(patch: &str, max_lines: usize) -> Vec<Line<'static>> {
 
 fn sanitize_terminal_text(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
-    let mut chars = input.chars();
+    let chars = input.chars();
     let mut in_escape = false;
     let mut in_csi = false;
 
-    while let Some(ch) = chars.next() {
+    for ch in chars {
         if in_escape {
             if ch == '[' {
                 in_csi = true;
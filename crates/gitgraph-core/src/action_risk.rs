@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+
+// Destructive actions throw away uncommitted work, stashes or tags, or
+// rewrite refs on a remote; frontends ask before running them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "level", rename_all = "kebab-case")]
+pub enum ActionRisk {
+    #[default]
+    Safe,
+    Destructive {
+        reason: String,
+    },
+}
+
+impl ActionRisk {
+    pub fn destructive(reason: &str) -> Self {
+        Self::Destructive {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Self::Destructive { .. })
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            Self::Safe => None,
+            Self::Destructive { reason } => Some(reason),
+        }
+    }
+}
+
+// Classifies expanded git arguments, with or without a leading `git`.
+pub fn classify_git_args(args: &[String]) -> ActionRisk {
+    let Some((command, rest)) = git_command(args).split_first() else {
+        return ActionRisk::Safe;
+    };
+    let has = |short: Option<char>, long: &[&str]| has_flag(rest, short, long);
+    let operand = first_operand(rest);
+    let reason = match command.as_str() {
+        "reset" if has(None, &["--hard"]) => "reset --hard discards uncommitted changes",
+        "push"
+            if has(Some('f'), &["--force", "--force-with-lease", "--mirror"])
+                || rest.iter().any(|arg| arg.starts_with('+')) =>
+        {
+            "push --force overwrites remote history"
+        }
+        "push"
+            if has(Some('d'), &["--delete", "--prune"])
+                || rest.iter().any(|arg| arg.len() > 1 && arg.starts_with(':')) =>
+        {
+            "push --delete removes remote refs"
+        }
+        "branch"
+            if has(Some('D'), &[])
+                || has(Some('M'), &[])
+                || has(Some('C'), &[])
+                || has(Some('f'), &["--force"]) =>
+        {
+            "branch --force deletes or overwrites unmerged branches"
+        }
+        "tag" if has(Some('d'), &["--delete"]) => "tag --delete removes tags",
+        "tag" if has(Some('f'), &["--force"]) => "tag --force moves existing tags",
+        "stash" if matches!(operand, Some("drop" | "clear")) => {
+            "stash drop discards stashed changes"
+        }
+        "clean" if !has(Some('n'), &["--dry-run"]) => "clean deletes untracked files",
+        "checkout"
+            if has(Some('f'), &["--force"])
+                || has(Some('B'), &[])
+                || rest.iter().any(|arg| arg == "--") =>
+        {
+            "checkout --force discards local changes"
+        }
+        "switch"
+            if has(Some('f'), &["--force", "--discard-changes"])
+                || has(Some('C'), &["--force-create"]) =>
+        {
+            "switch --discard-changes discards local changes"
+        }
+        "restore" if !has(Some('S'), &["--staged"]) || has(Some('W'), &["--worktree"]) => {
+            "restore discards working tree changes"
+        }
+        "apply" if has(Some('R'), &["--reverse"]) && !has(None, &["--cached", "--index"]) => {
+            "apply --reverse discards working tree changes"
+        }
+        "merge" | "rebase" | "cherry-pick" | "revert" | "am" if has(None, &["--abort"]) => {
+            "--abort discards resolved conflicts and the steps applied so far"
+        }
+        "rebase" | "cherry-pick" | "revert" | "am" if has(None, &["--skip"]) => {
+            "--skip drops the commit being applied"
+        }
+        "update-ref" if has(Some('d'), &[]) => "update-ref -d deletes refs",
+        "reflog" if matches!(operand, Some("expire" | "delete")) => {
+            "reflog expire drops the history lost commits are recovered from"
+        }
+        "filter-branch" | "filter-repo" => "rewrites history",
+        "worktree" if operand == Some("remove") && has(Some('f'), &["--force"]) => {
+            "worktree remove --force deletes uncommitted changes"
+        }
+        "remote" if matches!(operand, Some("remove" | "rm" | "prune")) => {
+            "remote remove deletes remote-tracking branches"
+        }
+        _ => return ActionRisk::Safe,
+    };
+    ActionRisk::destructive(reason)
+}
+
+// Classifies each command of a script chained with `&&`, `||`, `;` or `|`;
+// the first destructive one wins. Commands other than git are not inspected.
+pub fn classify_shell_script(script: &str) -> ActionRisk {
+    for (index, tokens) in shell_commands(script).iter().enumerate() {
+        // The first command runs with the git binary prepended.
+        if index > 0 && tokens.first().is_none_or(|token| token != "git") {
+            continue;
+        }
+        let risk = classify_git_args(tokens);
+        if risk.is_destructive() {
+            return risk;
+        }
+    }
+    ActionRisk::Safe
+}
+
+// Splits a script into the words of each command. Separators only count
+// outside quotes, so `commit -m "a; b"` stays one command.
+fn shell_commands(script: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = script.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\'' => {
+                in_word = true;
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if matches!(chars.peek(), Some('"' | '\\' | '$' | '`')) => {
+                            word.extend(chars.next());
+                        }
+                        _ => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next().filter(|&c| c != '\n'));
+            }
+            ';' | '&' | '|' | '\n' => {
+                if matches!((ch, chars.peek()), ('&', Some('&')) | ('|', Some('|'))) {
+                    chars.next();
+                }
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                commands.push(std::mem::take(&mut words));
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    commands.push(words);
+    commands
+}
+
+// Skips `git` and the global options before the subcommand.
+fn git_command(args: &[String]) -> &[String] {
+    let mut args = match args.first() {
+        Some(first) if first == "git" => &args[1..],
+        _ => args,
+    };
+    while let Some(first) = args.first() {
+        match first.as_str() {
+            "-c" | "-C" => args = args.get(2..).unwrap_or_default(),
+            arg if arg.starts_with('-') => args = &args[1..],
+            _ => break,
+        }
+    }
+    args
+}
+
+// Matches `--long`, `--long=value` and `-s` inside short flag clusters such
+// as `-df`, up to a `--` separator.
+fn has_flag(args: &[String], short: Option<char>, long: &[&str]) -> bool {
+    args.iter().take_while(|arg| *arg != "--").any(|arg| {
+        if let Some(name) = arg.strip_prefix("--") {
+            return long.iter().any(|flag| {
+                let flag = &flag[2..];
+                name == flag || name.strip_prefix(flag).is_some_and(|v| v.starts_with('='))
+            });
+        }
+        match (short, arg.strip_prefix('-')) {
+            (Some(short), Some(cluster)) => cluster.contains(short),
+            _ => false,
+        }
+    })
+}
+
+fn first_operand(args: &[String]) -> Option<&str> {
+    args.iter()
+        .map(String::as_str)
+        .find(|arg| !arg.starts_with('-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionRisk, classify_git_args, classify_shell_script};
+
+    fn classify(command: &str) -> Option<String> {
+        let args = shlex::split(command).expect("split");
+        classify_git_args(&args).reason().map(ToString::to_string)
+    }
+
+    #[test]
+    fn flags_destructive_git_commands() {
+        for command in [
+            "reset --hard HEAD~1",
+            "push origin main --force",
+            "push --force-with-lease=main origin main",
+            "push origin +main",
+            "push origin --delete topic",
+            "push origin :topic",
+            "branch -D topic",
+            "branch --delete --force topic",
+            "branch -df topic",
+            "tag -d v1",
+            "stash drop stash@{0}",
+            "clean -fd",
+            "checkout -- src/lib.rs",
+            "restore src/lib.rs",
+            "git -c core.quotepath=off reset --hard",
+            "apply --whitespace=nowarn --reverse -",
+            "rebase --abort",
+            "cherry-pick --skip",
+        ] {
+            assert!(
+                classify(command).is_some(),
+                "{command} should be destructive"
+            );
+        }
+        for command in [
+            "reset --soft HEAD~1",
+            "push origin main",
+            "branch --delete topic",
+            "branch -m old new",
+            "tag v1 HEAD --message 'release -d'",
+            "stash pop",
+            "clean -n",
+            "checkout main",
+            "restore --staged src/lib.rs",
+            "commit -m '-D'",
+            "apply --cached --reverse -",
+            "rebase --continue",
+            "bisect reset",
+            "bisect skip",
+            "",
+        ] {
+            assert_eq!(classify(command), None, "{command} should be safe");
+        }
+        assert_eq!(
+            classify("reset --hard").as_deref(),
+            Some("reset --hard discards uncommitted changes")
+        );
+    }
+
+    #[test]
+    fn inspects_every_git_command_of_a_script() {
+        assert_eq!(
+            classify_shell_script("checkout \"main\" && git reset \"abc\" --hard"),
+            ActionRisk::destructive("reset --hard discards uncommitted changes")
+        );
+        assert!(
+            classify_shell_script("push origin --delete topic || git branch -d topic")
+                .is_destructive()
+        );
+        assert_eq!(
+            classify_shell_script("checkout main && git merge topic; echo reset --hard"),
+            ActionRisk::Safe
+        );
+        assert_eq!(
+            classify_shell_script("commit -m \"fix; git clean -fd\" && git log -1"),
+            ActionRisk::Safe
+        );
+        assert_eq!(
+            classify_shell_script("commit -m 'a | b' -m \"it's \\\"done\\\"\" | git clean -fd"),
+            ActionRisk::destructive("clean deletes untracked files")
+        );
+    }
+}
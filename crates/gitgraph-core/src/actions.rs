@@ -3,6 +3,7 @@ use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
 
+use crate::action_risk::{ActionRisk, classify_git_args, classify_shell_script};
 use crate::error::{GitLgError, Result};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -55,6 +56,9 @@ pub struct ActionOption {
     pub default_active: bool,
     #[serde(default)]
     pub info: Option<String>,
+    // Like `ActionTemplate::destructive`, but only while the option is on.
+    #[serde(default)]
+    pub destructive: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -101,6 +105,9 @@ pub struct ActionTemplate {
     pub ignore_errors: bool,
     #[serde(default)]
     pub allow_non_zero_exit: bool,
+    // Forces confirmation even when the expanded args look harmless.
+    #[serde(default)]
+    pub destructive: bool,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -124,6 +131,8 @@ pub struct ActionContext {
     #[serde(default)]
     pub commit_body: Option<String>,
     #[serde(default)]
+    pub commit_note: Option<String>,
+    #[serde(default)]
     pub stash_name: Option<String>,
     #[serde(default)]
     pub tag_name: Option<String>,
@@ -165,6 +174,9 @@ impl ActionContext {
         if let Some(v) = &self.commit_body {
             out.insert("COMMIT_BODY".to_string(), v.clone());
         }
+        if let Some(v) = &self.commit_note {
+            out.insert("COMMIT_NOTE".to_string(), v.clone());
+        }
         if let Some(v) = &self.stash_name {
             out.insert("STASH_NAME".to_string(), v.clone());
         }
@@ -202,6 +214,7 @@ pub struct ResolvedAction {
     pub command_line: String,
     pub allow_non_zero_exit: bool,
     pub ignore_errors: bool,
+    pub risk: ActionRisk,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -302,11 +315,15 @@ impl ActionCatalog {
         for token in &template.args {
             args.push(expand_placeholders(token, &placeholders, &lookup)?);
         }
+        let mut destructive_option = None;
         for option in &template.options {
             if request.enabled_options.contains(&option.id)
                 || request.enabled_options.contains(&option.flag)
                 || option.default_active
             {
+                if option.destructive {
+                    destructive_option.get_or_insert(option.flag.as_str());
+                }
                 for token in tokenize_args(&option.flag) {
                     args.push(expand_placeholders(&token, &placeholders, &lookup)?);
                 }
@@ -333,6 +350,16 @@ impl ActionCatalog {
             }
         }
 
+        let mut risk = if template.destructive {
+            ActionRisk::destructive("the action template is marked destructive")
+        } else if template.shell_script {
+            classify_shell_script(&command_line)
+        } else {
+            classify_git_args(&args)
+        };
+        if let (ActionRisk::Safe, Some(flag)) = (&risk, destructive_option) {
+            risk = ActionRisk::destructive(&format!("the {flag} option is marked destructive"));
+        }
         Ok(ResolvedAction {
             id: template.id.clone(),
             title: template.title.clone(),
@@ -342,6 +369,7 @@ impl ActionCatalog {
             command_line,
             allow_non_zero_exit: template.allow_non_zero_exit,
             ignore_errors: template.ignore_errors,
+            risk,
         })
     }
 }
@@ -487,6 +515,7 @@ fn convert_raw_action(scope: ActionScope, index: usize, raw: RawAction) -> Actio
         immediate: raw.immediate.unwrap_or(false),
         ignore_errors: raw.ignore_errors.unwrap_or(false),
         allow_non_zero_exit: raw.ignore_errors.unwrap_or(false),
+        destructive: raw.destructive.unwrap_or(false),
     }
 }
 
@@ -521,6 +550,7 @@ fn convert_raw_option(index: usize, raw: RawOption) -> ActionOption {
         flag: raw.value,
         default_active: raw.default_active.unwrap_or(false),
         info: raw.info,
+        destructive: raw.destructive.unwrap_or(false),
     }
 }
 
@@ -609,6 +639,8 @@ struct RawAction {
     immediate: Option<bool>,
     #[serde(default)]
     ignore_errors: Option<bool>,
+    #[serde(default)]
+    destructive: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -629,6 +661,8 @@ struct RawOption {
     default_active: Option<bool>,
     #[serde(default)]
     info: Option<String>,
+    #[serde(default)]
+    destructive: Option<bool>,
 }
 
 #[cfg(test)]
@@ -664,6 +698,116 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resolves_note_actions_for_commit() {
+        let catalog = ActionCatalog::with_defaults();
+        let resolved = catalog
+            .resolve(ActionRequest {
+                template_id: "edit-note".to_string(),
+                params: HashMap::new(),
+                enabled_options: HashSet::new(),
+                context: ActionContext {
+                    commit_hash: Some("abc123".to_string()),
+                    commit_note: Some("ci: passed".to_string()),
+                    ..ActionContext::default()
+                },
+            })
+            .expect("resolves");
+        assert_eq!(resolved.scope, ActionScope::Commit);
+        assert_eq!(
+            resolved.args,
+            vec![
+                "notes",
+                "--ref",
+                "commits",
+                "add",
+                "--force",
+                "--message",
+                "ci: passed",
+                "abc123"
+            ]
+        );
+        assert!(catalog.find("add-note").is_some());
+    }
+
+    #[test]
+    fn classifies_resolved_actions_by_risk() {
+        let catalog = ActionCatalog::with_defaults();
+        let reset = |options: &[&str]| {
+            catalog
+                .resolve(ActionRequest {
+                    template_id: "commit:7:reset".to_string(),
+                    params: HashMap::new(),
+                    enabled_options: options.iter().map(ToString::to_string).collect(),
+                    context: ActionContext {
+                        commit_hash: Some("abc123".to_string()),
+                        ..ActionContext::default()
+                    },
+                })
+                .expect("resolves")
+                .risk
+        };
+        assert!(!reset(&[]).is_destructive());
+        assert!(!reset(&["--soft"]).is_destructive());
+        assert_eq!(
+            reset(&["--hard"]).reason(),
+            Some("reset --hard discards uncommitted changes")
+        );
+
+        // Forcing a branch delete or push is destructive.
+        let branch = |template_id: &str, options: &[&str]| {
+            catalog
+                .resolve(ActionRequest {
+                    template_id: template_id.to_string(),
+                    params: HashMap::from([("1".to_string(), "topic".to_string())]),
+                    enabled_options: options.iter().map(ToString::to_string).collect(),
+                    context: ActionContext::default(),
+                })
+                .expect("resolves")
+                .risk
+        };
+        assert!(!branch("branch:7:delete", &[]).is_destructive());
+        assert!(branch("branch:7:delete", &["--force"]).is_destructive());
+        assert!(branch("branch:4:push", &["--force"]).is_destructive());
+
+        let mut marked = catalog.clone();
+        for template in &mut marked.templates {
+            for option in &mut template.options {
+                option.destructive = true;
+            }
+        }
+        let resolved = marked
+            .resolve(ActionRequest {
+                template_id: "commit:7:reset".to_string(),
+                params: HashMap::new(),
+                enabled_options: HashSet::from(["--soft".to_string()]),
+                context: ActionContext {
+                    commit_hash: Some("abc123".to_string()),
+                    ..ActionContext::default()
+                },
+            })
+            .expect("resolves");
+        assert_eq!(
+            resolved.risk.reason(),
+            Some("the --soft option is marked destructive")
+        );
+
+        let abort = catalog
+            .templates
+            .iter()
+            .find(|template| template.destructive)
+            .expect("abort is marked destructive");
+        let resolved = catalog
+            .resolve(ActionRequest {
+                template_id: abort.id.clone(),
+                params: HashMap::new(),
+                enabled_options: HashSet::new(),
+                context: ActionContext::default(),
+            })
+            .expect("resolves");
+        assert!(resolved.risk.is_destructive());
+    }
+
     #[test]
     fn resolves_dynamic_lookup_placeholder() {
         let mut catalog = ActionCatalog::default();
@@ -685,6 +829,7 @@ mod tests {
             immediate: false,
             ignore_errors: false,
             allow_non_zero_exit: false,
+            destructive: false,
         });
         let request = ActionRequest {
             template_id: "test:dynamic".to_string(),
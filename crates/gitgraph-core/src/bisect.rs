@@ -0,0 +1,106 @@
+use crate::error::{GitLgError, Result};
+use crate::models::BisectStatus;
+
+pub fn bisect_refs_args() -> Vec<String> {
+    vec![
+        "for-each-ref".to_string(),
+        "--format=%(objectname) %(refname)".to_string(),
+        "refs/bisect/".to_string(),
+    ]
+}
+
+// Reads `refs/bisect/bad`, `refs/bisect/good-*` and `refs/bisect/skip-*`.
+// Custom terms (`--term-new`/`--term-old`) are not recognized.
+pub fn parse_bisect_refs(stdout: &str) -> Result<BisectStatus> {
+    let mut status = BisectStatus::default();
+    for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+        let (oid, refname) = line
+            .split_once(' ')
+            .ok_or_else(|| GitLgError::Parse(format!("invalid bisect ref line: {line:?}")))?;
+        let Some(name) = refname.strip_prefix("refs/bisect/") else {
+            continue;
+        };
+        if name == "bad" {
+            status.bad = Some(oid.to_string());
+        } else if name.starts_with("good-") {
+            status.good.push(oid.to_string());
+        } else if name.starts_with("skip-") {
+            status.skipped.push(oid.to_string());
+        }
+    }
+    Ok(status)
+}
+
+// `None` until both ends are marked.
+pub fn bisect_candidates_args(status: &BisectStatus) -> Option<Vec<String>> {
+    let bad = status.bad.as_ref()?;
+    if status.good.is_empty() {
+        return None;
+    }
+    let mut args = vec!["rev-list".to_string(), bad.clone(), "--not".to_string()];
+    args.extend(status.good.iter().cloned());
+    Some(args)
+}
+
+// Fills the remaining range from `rev-list <bad> --not <good>...`.
+pub fn apply_bisect_candidates(status: &mut BisectStatus, rev_list_stdout: &str) {
+    status.candidates = rev_list_stdout
+        .lines()
+        .map(str::trim)
+        .filter(|hash| !hash.is_empty())
+        .map(ToString::to_string)
+        .collect();
+    let untested = status
+        .candidates
+        .iter()
+        .filter(|hash| Some(hash.as_str()) != status.bad.as_deref())
+        .filter(|hash| !status.skipped.contains(hash))
+        .count();
+    status.steps_left = estimate_bisect_steps(untested);
+    // Skipped candidates left over mean git cannot tell which is first.
+    status.first_bad = status.bad.clone().filter(|_| status.candidates.len() == 1);
+}
+
+// Good/bad answers still needed in the worst case: ceil(log2(untested + 1)).
+pub fn estimate_bisect_steps(untested: usize) -> usize {
+    (usize::BITS - untested.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_bisect_candidates, bisect_candidates_args, estimate_bisect_steps, parse_bisect_refs,
+    };
+
+    #[test]
+    fn reads_marks_and_remaining_range() {
+        let mut status = parse_bisect_refs(
+            "aaaa refs/bisect/bad\nbbbb refs/bisect/good-bbbb\ncccc refs/bisect/skip-cccc\n",
+        )
+        .expect("parse");
+        assert_eq!(status.bad.as_deref(), Some("aaaa"));
+        assert_eq!(status.good, vec!["bbbb"]);
+        assert_eq!(status.skipped, vec!["cccc"]);
+        assert_eq!(
+            bisect_candidates_args(&status).expect("args"),
+            vec!["rev-list", "aaaa", "--not", "bbbb"]
+        );
+
+        apply_bisect_candidates(&mut status, "aaaa\ncccc\ndddd\neeee\nffff\n");
+        assert_eq!(status.candidates.len(), 5);
+        assert_eq!(status.steps_left, 2);
+        assert_eq!(status.first_bad, None);
+        assert_eq!(status.summary(), "5 candidates, roughly 2 steps left");
+
+        apply_bisect_candidates(&mut status, "aaaa\n");
+        assert_eq!(status.first_bad.as_deref(), Some("aaaa"));
+        assert_eq!(status.summary(), "first bad: aaaa");
+    }
+
+    #[test]
+    fn estimates_worst_case_steps() {
+        let steps = [0, 1, 2, 3, 4, 7, 8].map(estimate_bisect_steps).to_vec();
+        assert_eq!(steps, vec![0, 1, 2, 2, 3, 3, 4]);
+        assert!(bisect_candidates_args(&Default::default()).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{GitLgError, Result};
+use crate::models::{BlameCommit, BlameInfo, FileBlame};
+
+struct LineHeader {
+    hash: String,
+    original_line: usize,
+    final_line: usize,
+    original_path: Option<String>,
+}
+
+// Parses `git blame --porcelain`, where commit metadata is only written the
+// first time a commit appears and later blocks carry just the line header.
+pub fn parse_blame_porcelain(stdout: &str, file: &Path) -> Result<FileBlame> {
+    let mut commits: Vec<BlameCommit> = Vec::new();
+    let mut commit_index: HashMap<String, usize> = HashMap::new();
+    let mut commit_paths: HashMap<String, String> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut header: Option<LineHeader> = None;
+
+    for raw in stdout.lines() {
+        if let Some(content) = raw.strip_prefix('\t') {
+            let Some(current) = header.take() else {
+                return Err(GitLgError::Parse(format!(
+                    "blame content without header: {raw:?}"
+                )));
+            };
+            let commit = &mut commits[commit_index[&current.hash]];
+            commit.line_count += 1;
+            let original_path = current
+                .original_path
+                .or_else(|| commit_paths.get(&current.hash).cloned())
+                .unwrap_or_default();
+            lines.push(BlameInfo {
+                file: file.to_path_buf(),
+                line: current.final_line,
+                commit_hash: current.hash,
+                author_name: commit.author_name.clone(),
+                author_email: commit.author_email.clone(),
+                author_time_unix: commit.author_time_unix,
+                summary: commit.summary.clone(),
+                original_path,
+                original_line: current.original_line,
+                content: content.to_string(),
+            });
+            continue;
+        }
+
+        let Some(current) = header.as_mut() else {
+            let parsed = parse_line_header(raw)
+                .ok_or_else(|| GitLgError::Parse(format!("invalid blame header: {raw:?}")))?;
+            commit_index.entry(parsed.hash.clone()).or_insert_with(|| {
+                commits.push(BlameCommit {
+                    hash: parsed.hash.clone(),
+                    author_name: String::new(),
+                    author_email: String::new(),
+                    author_time_unix: 0,
+                    committer_name: String::new(),
+                    committer_email: String::new(),
+                    committer_time_unix: 0,
+                    summary: String::new(),
+                    previous: None,
+                    boundary: false,
+                    line_count: 0,
+                });
+                commits.len() - 1
+            });
+            header = Some(parsed);
+            continue;
+        };
+
+        let (key, value) = raw.split_once(' ').unwrap_or((raw, ""));
+        if key == "filename" {
+            current.original_path = Some(value.to_string());
+            commit_paths.insert(current.hash.clone(), value.to_string());
+            continue;
+        }
+        let commit = &mut commits[commit_index[&current.hash]];
+        match key {
+            "author" => commit.author_name = value.to_string(),
+            "author-mail" => commit.author_email = value.trim_matches(['<', '>']).to_string(),
+            "author-time" => commit.author_time_unix = value.parse::<i64>().unwrap_or(0),
+            "committer" => commit.committer_name = value.to_string(),
+            "committer-mail" => commit.committer_email = value.trim_matches(['<', '>']).to_string(),
+            "committer-time" => commit.committer_time_unix = value.parse::<i64>().unwrap_or(0),
+            "summary" => commit.summary = value.to_string(),
+            "previous" => {
+                commit.previous = value.split_whitespace().next().map(ToString::to_string)
+            }
+            "boundary" => commit.boundary = true,
+            _ => {}
+        }
+    }
+
+    Ok(FileBlame {
+        file: file.to_path_buf(),
+        lines,
+        commits,
+    })
+}
+
+// `<hash> <original line> <final line> [<lines in group>]`
+fn parse_line_header(raw: &str) -> Option<LineHeader> {
+    let mut parts = raw.split_whitespace();
+    let hash = parts.next()?;
+    if hash.len() < 40 || !hash.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(LineHeader {
+        hash: hash.to_string(),
+        original_line: parts.next()?.parse().ok()?,
+        final_line: parts.next()?.parse().ok()?,
+        original_path: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::parse_blame_porcelain;
+
+    const OLD: &str = "1111111111111111111111111111111111111111";
+    const NEW: &str = "2222222222222222222222222222222222222222";
+
+    #[test]
+    fn groups_commit_metadata_across_lines() {
+        let stdout = format!(
+            "{OLD} 1 1 1\nauthor Ann\nauthor-mail <ann@e>\nauthor-time 10\ncommitter Cid\ncommitter-mail <cid@e>\ncommitter-time 11\nsummary first\nboundary\nfilename old.rs\n\tfn a() {{}}\n\
+             {NEW} 4 2 1\nauthor Bob\nauthor-mail <bob@e>\nauthor-time 20\ncommitter Bob\ncommitter-mail <bob@e>\ncommitter-time 20\nsummary second\nprevious {OLD} new.rs\nfilename new.rs\n\tfn b() {{}}\n\
+             {OLD} 2 3\nfilename old.rs\n\tfn c() {{}}\n"
+        );
+        let blame = parse_blame_porcelain(&stdout, Path::new("/repo/new.rs")).expect("parse");
+
+        assert_eq!(blame.lines.len(), 3);
+        assert_eq!(blame.commits.len(), 2);
+        assert_eq!(blame.commits[0].hash, OLD);
+        assert_eq!(blame.commits[0].line_count, 2);
+        assert!(blame.commits[0].boundary);
+        assert_eq!(blame.commits[1].previous.as_deref(), Some(OLD));
+        assert_eq!(blame.commits[1].committer_time_unix, 20);
+
+        let third = &blame.lines[2];
+        assert_eq!(third.line, 3);
+        assert_eq!(third.original_line, 2);
+        assert_eq!(third.original_path, "old.rs");
+        assert_eq!(third.author_name, "Ann");
+        assert_eq!(third.author_email, "ann@e");
+        assert_eq!(third.summary, "first");
+        assert_eq!(third.content, "fn c() {}");
+        assert_eq!(blame.lines[1].content, "fn b() {}");
+    }
+
+    #[test]
+    fn rejects_content_without_header() {
+        assert!(parse_blame_porcelain("\torphan\n", Path::new("a.txt")).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::error::{GitLgError, Result};
+use crate::log_parser::{GraphBuilder, log_format, parse_git_log_records};
+use crate::models::{CommitOptions, GraphRow};
+
+// Splits a free-form message into a trimmed subject line and body, skipping
+// leading blank lines. `None` when there is no subject.
+pub fn split_commit_message(message: &str) -> Option<(String, String)> {
+    let mut lines = message.lines().skip_while(|line| line.trim().is_empty());
+    let subject = lines
+        .next()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())?
+        .to_string();
+    let body = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+    Some((subject, body))
+}
+
+// `git commit` arguments for `options`, after validating them. Staging for
+// `stage_all` is a separate `git add -A`. Shared with the Zed extension.
+pub fn commit_args(options: &CommitOptions) -> Result<Vec<String>> {
+    let invalid = |message: &str| Err(GitLgError::State(message.to_string()));
+    let mut args = vec!["commit".to_string()];
+    let fixup = options
+        .fixup
+        .as_deref()
+        .map(str::trim)
+        .filter(|target| !target.is_empty());
+    match (fixup, split_commit_message(&options.message)) {
+        (Some(_), _) if options.amend => {
+            return invalid("a fixup commit cannot amend HEAD");
+        }
+        (Some(_), Some(_)) => {
+            return invalid("a fixup commit takes its message from the target commit");
+        }
+        (Some(target), None) => args.push(format!("--fixup={target}")),
+        (None, Some((subject, body))) => {
+            args.extend(["-m".to_string(), subject]);
+            if !body.is_empty() {
+                args.extend(["-m".to_string(), body]);
+            }
+        }
+        (None, None) if options.amend => args.push("--no-edit".to_string()),
+        (None, None) => return invalid("commit message is empty"),
+    }
+    if options.amend {
+        args.push("--amend".to_string());
+    }
+    if options.signoff {
+        args.push("--signoff".to_string());
+    }
+    if options.allow_empty {
+        args.push("--allow-empty".to_string());
+    }
+    if let Some(author) = options.author.as_deref() {
+        // Anything else makes git search existing authors for a match.
+        let author = author.trim();
+        let well_formed = author
+            .split_once(" <")
+            .is_some_and(|(name, email)| !name.trim().is_empty() && email.ends_with('>'));
+        if !well_formed {
+            return invalid("author must look like `Name <email>`");
+        }
+        args.push(format!("--author={author}"));
+    }
+    Ok(args)
+}
+
+// `git log` arguments for the single commit `revision`, in the graph format.
+pub fn commit_row_args(revision: &str) -> Vec<String> {
+    vec![
+        "-c".to_string(),
+        "color.ui=never".to_string(),
+        "log".to_string(),
+        "-1".to_string(),
+        "--decorate=full".to_string(),
+        "--color=never".to_string(),
+        log_format(false, false),
+        "--no-show-signature".to_string(),
+        "--no-notes".to_string(),
+        "--end-of-options".to_string(),
+        revision.to_string(),
+    ]
+}
+
+pub fn parse_commit_row(stdout: &str) -> Result<GraphRow> {
+    let commit = parse_git_log_records(stdout)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| GitLgError::Parse("git log returned no commit".to_string()))?;
+    Ok(GraphBuilder::new().push(commit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commit_args, split_commit_message};
+    use crate::models::CommitOptions;
+
+    #[test]
+    fn builds_message_and_flag_arguments() {
+        let options = CommitOptions {
+            message: "\n  Subject line  \n\nBody one\nBody two\n".to_string(),
+            signoff: true,
+            author: Some("Ann <ann@example.com>".to_string()),
+            ..CommitOptions::default()
+        };
+        assert_eq!(
+            commit_args(&options).expect("args"),
+            vec![
+                "commit",
+                "-m",
+                "Subject line",
+                "-m",
+                "Body one\nBody two",
+                "--signoff",
+                "--author=Ann <ann@example.com>",
+            ]
+        );
+
+        let amend = CommitOptions {
+            amend: true,
+            ..CommitOptions::default()
+        };
+        assert_eq!(
+            commit_args(&amend).expect("amend args"),
+            vec!["commit", "--no-edit", "--amend"]
+        );
+
+        let fixup = CommitOptions {
+            fixup: Some("abc123".to_string()),
+            ..CommitOptions::default()
+        };
+        assert_eq!(
+            commit_args(&fixup).expect("fixup args"),
+            vec!["commit", "--fixup=abc123"]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        assert!(commit_args(&CommitOptions::default()).is_err());
+        assert!(
+            commit_args(&CommitOptions {
+                message: "msg".to_string(),
+                fixup: Some("abc".to_string()),
+                ..CommitOptions::default()
+            })
+            .is_err()
+        );
+        assert!(
+            commit_args(&CommitOptions {
+                amend: true,
+                fixup: Some("abc".to_string()),
+                ..CommitOptions::default()
+            })
+            .is_err()
+        );
+        assert!(
+            commit_args(&CommitOptions {
+                message: "msg".to_string(),
+                author: Some("ann".to_string()),
+                ..CommitOptions::default()
+            })
+            .is_err()
+        );
+        assert_eq!(split_commit_message(" \n \n"), None);
+    }
+}
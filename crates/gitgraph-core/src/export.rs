@@ -0,0 +1,577 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{GitRef, GitRefKind, GraphRow, GraphSegmentKind};
+
+// Hex counterparts of the TUI lane colors, indexed by `color_group`.
+pub const LANE_PALETTE: [&str; 8] = [
+    "#1fa5b8", "#4f8fe6", "#3fae5a", "#d29b00", "#b84fc0", "#e0524f", "#2cc3c9", "#7a7f87",
+];
+
+const SVG_ROW_HEIGHT: usize = 24;
+const SVG_LANE_WIDTH: usize = 16;
+const SVG_MARGIN: usize = 12;
+const SVG_NODE_RADIUS: usize = 5;
+const SVG_CHAR_WIDTH: usize = 7;
+const MERMAID_SUBJECT_CHARS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    Dot,
+    Mermaid,
+    Svg,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Mermaid => "mermaid",
+            ExportFormat::Svg => "svg",
+        }
+    }
+}
+
+pub fn export_graph(rows: &[GraphRow], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Dot => render_dot(rows),
+        ExportFormat::Mermaid => render_mermaid(rows),
+        ExportFormat::Svg => render_svg(rows),
+    }
+}
+
+pub fn lane_palette_color(color_group: u32) -> &'static str {
+    LANE_PALETTE[color_group as usize % LANE_PALETTE.len()]
+}
+
+pub fn render_dot(rows: &[GraphRow]) -> String {
+    let groups = rows
+        .iter()
+        .map(|row| (row.hash.as_str(), row.color_group))
+        .collect::<HashMap<_, _>>();
+    let mut out = String::new();
+    out.push_str("digraph gitgraph {\n");
+    out.push_str("  rankdir=TB;\n");
+    out.push_str("  node [shape=box, style=\"rounded\", fontname=\"monospace\", fontsize=10];\n");
+    out.push_str("  edge [arrowhead=none, penwidth=2];\n");
+    for row in rows {
+        let color = lane_palette_color(row.color_group);
+        let _ = writeln!(
+            out,
+            "  \"{}\" [label=\"{}\\n{}\", color=\"{}\", group=\"g{}\"{}];",
+            row.hash,
+            dot_escape(&row.short_hash),
+            dot_escape(&row.subject),
+            color,
+            row.color_group,
+            if row.parents.len() > 1 {
+                ", penwidth=2"
+            } else {
+                ""
+            }
+        );
+        for git_ref in &row.refs {
+            let ref_id = format!("ref:{}:{}", row.hash, git_ref.name);
+            let _ = writeln!(
+                out,
+                "  \"{}\" [label=\"{}\", shape={}, color=\"{}\", style=\"filled\", fillcolor=\"#f4f4f4\"];",
+                dot_escape(&ref_id),
+                dot_escape(&ref_label(git_ref)),
+                if git_ref.kind == GitRefKind::Tag {
+                    "cds"
+                } else {
+                    "box"
+                },
+                color
+            );
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [style=dashed, penwidth=1];",
+                dot_escape(&ref_id),
+                row.hash
+            );
+            let _ = writeln!(
+                out,
+                "  {{ rank=same; \"{}\"; \"{}\"; }}",
+                dot_escape(&ref_id),
+                row.hash
+            );
+        }
+    }
+    for row in rows {
+        for (idx, parent) in row.parents.iter().enumerate() {
+            let Some(parent_group) = groups.get(parent.as_str()) else {
+                continue;
+            };
+            let group = if idx == 0 {
+                row.color_group
+            } else {
+                *parent_group
+            };
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [color=\"{}\"{}];",
+                row.hash,
+                parent,
+                lane_palette_color(group),
+                if idx == 0 { "" } else { ", style=dashed" }
+            );
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Mermaid's gitGraph is a replay script: commits are emitted oldest first and
+// every commit must sit on the tip of a named branch. Each first-parent chain
+// becomes one branch; a commit with several first-parent children forks new
+// branches right after it is emitted. Merges name their second parent's
+// branch, so a branch that kept moving after being merged is approximated by
+// its current tip. Octopus parents beyond the second are dropped.
+pub fn render_mermaid(rows: &[GraphRow]) -> String {
+    let ordered = rows.iter().rev().collect::<Vec<_>>();
+    let visible = rows
+        .iter()
+        .map(|row| (row.hash.as_str(), row))
+        .collect::<HashMap<_, _>>();
+
+    let mut first_parent_children: HashMap<&str, Vec<&GraphRow>> = HashMap::new();
+    for row in rows {
+        if let Some(parent) = row.parents.first() {
+            first_parent_children
+                .entry(parent.as_str())
+                .or_default()
+                .push(row);
+        }
+    }
+
+    let mut branch_of: HashMap<&str, usize> = HashMap::new();
+    let mut branch_members: Vec<Vec<&GraphRow>> = Vec::new();
+    let mut forks: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut continued: HashSet<&str> = HashSet::new();
+    for row in &ordered {
+        // A parent listed after its child (e.g. with `--reverse`) has no
+        // branch yet, so the child starts one like a root does.
+        let first_parent = row.parents.first().and_then(|parent| {
+            let parent = parent.as_str();
+            Some((parent, visible.get(parent)?, *branch_of.get(parent)?))
+        });
+        let branch = match first_parent {
+            Some((parent, parent_row, parent_branch))
+                if !continued.contains(parent)
+                    && continues_branch(
+                        row,
+                        parent_row,
+                        first_parent_children.get(parent).map_or(&[], Vec::as_slice),
+                    ) =>
+            {
+                continued.insert(parent);
+                parent_branch
+            }
+            Some((parent, _, _)) => {
+                branch_members.push(Vec::new());
+                forks
+                    .entry(parent)
+                    .or_default()
+                    .push(branch_members.len() - 1);
+                branch_members.len() - 1
+            }
+            None => {
+                branch_members.push(Vec::new());
+                branch_members.len() - 1
+            }
+        };
+        branch_of.insert(row.hash.as_str(), branch);
+        branch_members[branch].push(row);
+    }
+
+    let names = mermaid_branch_names(&branch_members);
+    let mut out = String::new();
+    let main_name = names.first().map(String::as_str).unwrap_or("main");
+    let _ = writeln!(
+        out,
+        "%%{{init: {{'gitGraph': {{'mainBranchName': '{}'}}}}}}%%",
+        main_name
+    );
+    out.push_str("gitGraph\n");
+    let mut current = 0usize;
+    let mut created: HashSet<usize> = HashSet::from([0]);
+    for row in &ordered {
+        let branch = branch_of[row.hash.as_str()];
+        if !created.contains(&branch) {
+            // Only unrelated roots get here; they start from the current head.
+            let _ = writeln!(out, "  branch {}", names[branch]);
+            created.insert(branch);
+        } else if branch != current {
+            let _ = writeln!(out, "  checkout {}", names[branch]);
+        }
+        current = branch;
+
+        let id = mermaid_commit_id(row);
+        let tag = row
+            .refs
+            .iter()
+            .find(|git_ref| git_ref.kind == GitRefKind::Tag)
+            .map(|git_ref| format!(" tag: \"{}\"", mermaid_escape(&git_ref.name)))
+            .unwrap_or_default();
+        let merged_branch = row
+            .parents
+            .get(1)
+            .and_then(|parent| branch_of.get(parent.as_str()))
+            .filter(|merged| **merged != branch);
+        match merged_branch {
+            Some(merged) => {
+                let _ = writeln!(out, "  merge {} id: \"{}\"{}", names[*merged], id, tag);
+            }
+            None => {
+                let _ = writeln!(out, "  commit id: \"{}\"{}", id, tag);
+            }
+        }
+
+        // `branch` also checks the new branch out.
+        for fork in forks.get(row.hash.as_str()).into_iter().flatten() {
+            if current != branch {
+                let _ = writeln!(out, "  checkout {}", names[branch]);
+            }
+            let _ = writeln!(out, "  branch {}", names[*fork]);
+            created.insert(*fork);
+            current = *fork;
+        }
+    }
+    out
+}
+
+// Among several first-parent children, the one that shares the parent's
+// color group stays on the parent's branch; the others fork.
+fn continues_branch(row: &GraphRow, parent: &GraphRow, siblings: &[&GraphRow]) -> bool {
+    row.color_group == parent.color_group
+        || !siblings
+            .iter()
+            .any(|sibling| sibling.hash != row.hash && sibling.color_group == parent.color_group)
+}
+
+fn mermaid_branch_names(branch_members: &[Vec<&GraphRow>]) -> Vec<String> {
+    let mut used = HashSet::new();
+    branch_members
+        .iter()
+        .enumerate()
+        .map(|(idx, members)| {
+            let ref_name = members.iter().rev().find_map(|row| {
+                row.refs
+                    .iter()
+                    .filter_map(|git_ref| match git_ref.kind {
+                        GitRefKind::Head => git_ref.target.as_deref().map(|name| (0, name)),
+                        GitRefKind::LocalBranch => Some((1, git_ref.name.as_str())),
+                        GitRefKind::RemoteBranch => Some((2, git_ref.name.as_str())),
+                        _ => None,
+                    })
+                    .min()
+                    .map(|(_, name)| name)
+            });
+            let base = ref_name
+                .map(mermaid_branch_name)
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("branch-{}", idx + 1));
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !used.insert(name.clone()) {
+                name = format!("{base}-{suffix}");
+                suffix += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+fn mermaid_branch_name(name: &str) -> String {
+    name.chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/') {
+                ch
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn mermaid_commit_id(row: &GraphRow) -> String {
+    let mut subject = row
+        .subject
+        .chars()
+        .take(MERMAID_SUBJECT_CHARS)
+        .collect::<String>();
+    if row.subject.chars().count() > MERMAID_SUBJECT_CHARS {
+        subject.push('…');
+    }
+    mermaid_escape(format!("{} {}", row.short_hash, subject).trim_end())
+}
+
+fn mermaid_escape(value: &str) -> String {
+    value
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .map(|ch| if ch == '"' { '\'' } else { ch })
+        .collect()
+}
+
+fn dot_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            ch if ch.is_control() => {}
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+pub fn render_svg(rows: &[GraphRow]) -> String {
+    let lane_count = rows
+        .iter()
+        .flat_map(|row| {
+            row.segments
+                .iter()
+                .map(|segment| segment.from_lane.max(segment.to_lane) + 1)
+                .chain([row.lane + 1, row.active_lane_count])
+        })
+        .max()
+        .unwrap_or(1);
+    let text_x = SVG_MARGIN * 2 + lane_count * SVG_LANE_WIDTH;
+    let text_chars = rows
+        .iter()
+        .map(|row| svg_row_label(row).chars().count())
+        .max()
+        .unwrap_or(0);
+    let width = text_x + text_chars * SVG_CHAR_WIDTH + SVG_MARGIN;
+    let height = SVG_MARGIN * 2 + rows.len().max(1) * SVG_ROW_HEIGHT;
+    let lane_x = |lane: usize| SVG_MARGIN + lane * SVG_LANE_WIDTH + SVG_LANE_WIDTH / 2;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    );
+    out.push_str("  <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n");
+    out.push_str("  <g fill=\"none\" stroke-width=\"2\" stroke-linecap=\"round\">\n");
+    for (idx, row) in rows.iter().enumerate() {
+        let top = SVG_MARGIN + idx * SVG_ROW_HEIGHT;
+        let center = top + SVG_ROW_HEIGHT / 2;
+        let bottom = top + SVG_ROW_HEIGHT;
+        for segment in &row.segments {
+            let ((x1, y1), (x2, y2)) = match segment.kind {
+                GraphSegmentKind::PassThrough | GraphSegmentKind::Shift => (
+                    (lane_x(segment.from_lane), top),
+                    (lane_x(segment.to_lane), bottom),
+                ),
+                GraphSegmentKind::Incoming | GraphSegmentKind::Fork => (
+                    (lane_x(segment.from_lane), top),
+                    (lane_x(segment.to_lane), center),
+                ),
+                GraphSegmentKind::Outgoing | GraphSegmentKind::Merge => (
+                    (lane_x(segment.from_lane), center),
+                    (lane_x(segment.to_lane), bottom),
+                ),
+                GraphSegmentKind::Start | GraphSegmentKind::End => continue,
+            };
+            let color = lane_palette_color(segment.color_group);
+            if x1 == x2 {
+                let _ = writeln!(
+                    out,
+                    "    <path d=\"M{x1} {y1} L{x2} {y2}\" stroke=\"{color}\"/>"
+                );
+            } else {
+                let mid = (y1 + y2) / 2;
+                let _ = writeln!(
+                    out,
+                    "    <path d=\"M{x1} {y1} C{x1} {mid} {x2} {mid} {x2} {y2}\" stroke=\"{color}\"/>"
+                );
+            }
+        }
+    }
+    out.push_str("  </g>\n");
+
+    out.push_str("  <g font-family=\"monospace\" font-size=\"12\">\n");
+    for (idx, row) in rows.iter().enumerate() {
+        let center = SVG_MARGIN + idx * SVG_ROW_HEIGHT + SVG_ROW_HEIGHT / 2;
+        let color = lane_palette_color(row.color_group);
+        let x = lane_x(row.lane);
+        let fill = if row.parents.len() > 1 {
+            "#ffffff"
+        } else {
+            color
+        };
+        let _ = writeln!(
+            out,
+            "    <circle cx=\"{x}\" cy=\"{center}\" r=\"{SVG_NODE_RADIUS}\" fill=\"{fill}\" stroke=\"{color}\" stroke-width=\"2\"><title>{}</title></circle>",
+            xml_escape(&row.hash)
+        );
+        let _ = write!(
+            out,
+            "    <text x=\"{text_x}\" y=\"{}\"><tspan fill=\"#1f6feb\">{}</tspan>",
+            center + 4,
+            xml_escape(&row.short_hash)
+        );
+        if !row.refs.is_empty() {
+            let _ = write!(
+                out,
+                " <tspan fill=\"{color}\" font-weight=\"bold\">[{}]</tspan>",
+                xml_escape(&svg_ref_names(row))
+            );
+        }
+        let _ = writeln!(
+            out,
+            " <tspan fill=\"#24292f\">{}</tspan> <tspan fill=\"#6e7781\">· {}</tspan></text>",
+            xml_escape(&row.subject),
+            xml_escape(&row.author_name)
+        );
+    }
+    out.push_str("  </g>\n");
+    out.push_str("</svg>\n");
+    out
+}
+
+fn ref_label(git_ref: &GitRef) -> String {
+    match (&git_ref.kind, git_ref.target.as_deref()) {
+        (GitRefKind::Head, Some(target)) => format!("HEAD -> {target}"),
+        _ => git_ref.name.clone(),
+    }
+}
+
+fn svg_ref_names(row: &GraphRow) -> String {
+    row.refs
+        .iter()
+        .map(ref_label)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn svg_row_label(row: &GraphRow) -> String {
+    let refs = if row.refs.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", svg_ref_names(row))
+    };
+    format!(
+        "{}{} {} · {}",
+        row.short_hash, refs, row.subject, row.author_name
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            ch if ch.is_control() => {}
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::log_parser::{build_graph_rows, parse_git_log_records, test_record};
+    use crate::models::GraphRow;
+
+    use super::{render_dot, render_mermaid, render_svg};
+
+    // main: root -> base -> merge(base, feature); feature: base -> work
+    fn fork_and_merge_rows() -> Vec<GraphRow> {
+        let stdout = [
+            test_record(
+                "m1m1m1",
+                "b1b1b1 f1f1f1",
+                "HEAD -> refs/heads/main, tag: refs/tags/v1.0",
+                "Merge branch 'feature'",
+            ),
+            test_record("f1f1f1", "b1b1b1", "refs/heads/feature", "feature work"),
+            test_record("b1b1b1", "r0r0r0", "", "base \"quoted\""),
+            test_record("r0r0r0", "", "", "root"),
+        ]
+        .concat();
+        build_graph_rows(parse_git_log_records(&stdout).expect("parse fixture"))
+    }
+
+    #[test]
+    fn renders_dot_nodes_refs_and_edges() {
+        let dot = render_dot(&fork_and_merge_rows());
+        assert!(dot.starts_with("digraph gitgraph {"));
+        assert!(dot.contains("\"m1m1m1\" -> \"b1b1b1\""));
+        assert!(dot.contains("\"m1m1m1\" -> \"f1f1f1\""));
+        assert!(dot.contains("base \\\"quoted\\\""));
+        assert!(dot.contains("label=\"v1.0\", shape=cds"));
+        assert!(!dot.contains("-> \"\""));
+    }
+
+    #[test]
+    fn renders_mermaid_branches_and_merges() {
+        let mermaid = render_mermaid(&fork_and_merge_rows());
+        let lines = mermaid.lines().map(str::trim).collect::<Vec<_>>();
+        assert!(lines[0].contains("'mainBranchName': 'main'"));
+        assert_eq!(
+            &lines[1..],
+            &[
+                "gitGraph",
+                "commit id: \"r0r0 root\"",
+                "commit id: \"b1b1 base 'quoted'\"",
+                "branch feature",
+                "commit id: \"f1f1 feature work\"",
+                "checkout main",
+                "merge feature id: \"m1m1 Merge branch 'feature'\" tag: \"v1.0\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_mermaid_with_parents_after_children() {
+        let mut rows = fork_and_merge_rows();
+        rows.reverse();
+        let mermaid = render_mermaid(&rows);
+        let lines = mermaid.lines().map(str::trim).collect::<Vec<_>>();
+        let commits = lines
+            .iter()
+            .filter(|line| line.starts_with("commit ") || line.starts_with("merge "))
+            .count();
+        assert_eq!(commits, rows.len());
+        let branches = lines
+            .iter()
+            .filter(|line| line.starts_with("branch "))
+            .collect::<Vec<_>>();
+        let unique = branches.iter().collect::<HashSet<_>>();
+        assert_eq!(branches.len(), unique.len());
+    }
+
+    #[test]
+    fn renders_svg_from_row_segments() {
+        let rows = fork_and_merge_rows();
+        let svg = render_svg(&rows);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(svg.matches("<circle").count(), rows.len());
+        let drawn_segments = rows
+            .iter()
+            .flat_map(|row| &row.segments)
+            .filter(|segment| {
+                !matches!(
+                    segment.kind,
+                    crate::models::GraphSegmentKind::Start | crate::models::GraphSegmentKind::End
+                )
+            })
+            .count();
+        assert_eq!(svg.matches("<path").count(), drawn_segments);
+        assert!(svg.contains("base &quot;quoted&quot;"));
+        assert!(svg.contains("[HEAD -&gt; main, v1.0]"));
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread::JoinHandle;
 
 use crate::error::{GitLgError, Result};
 
@@ -11,6 +13,69 @@ pub struct GitOutput {
     pub exit_code: Option<i32>,
 }
 
+#[derive(Debug)]
+pub struct GitStream {
+    program: String,
+    args: Vec<String>,
+    allow_non_zero: bool,
+    child: Option<Child>,
+    stdout: BufReader<ChildStdout>,
+    stderr: Option<JoinHandle<String>>,
+}
+
+impl GitStream {
+    pub fn finish(&mut self) -> Result<Option<i32>> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(None);
+        };
+        let mut rest = Vec::new();
+        let _ = self.stdout.read_to_end(&mut rest);
+        let status = child
+            .wait()
+            .map_err(|source| GitLgError::io("waiting for git command", source))?;
+        let stderr = self
+            .stderr
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default();
+        if status.success() || self.allow_non_zero {
+            return Ok(status.code());
+        }
+        Err(GitLgError::GitCommandFailed {
+            program: self.program.clone(),
+            args: self.args.clone(),
+            exit_code: status.code(),
+            stderr,
+            stdout: String::from_utf8_lossy(&rest).to_string(),
+        })
+    }
+}
+
+impl Read for GitStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl BufRead for GitStream {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.stdout.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.stdout.consume(amt)
+    }
+}
+
+impl Drop for GitStream {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GitRunner {
     git_binary: String,
@@ -60,20 +125,70 @@ impl GitRunner {
         repo_path: &Path,
         args: &[String],
         allow_non_zero: bool,
+    ) -> Result<GitOutput> {
+        self.exec_inner(repo_path, args, None, allow_non_zero)
+    }
+
+    // Like `exec`, but writes `input` to git's stdin (e.g. a patch for `git apply -`).
+    pub fn exec_with_input(
+        &self,
+        repo_path: &Path,
+        args: &[String],
+        input: &str,
+        allow_non_zero: bool,
+    ) -> Result<GitOutput> {
+        self.exec_inner(repo_path, args, Some(input), allow_non_zero)
+    }
+
+    fn exec_inner(
+        &self,
+        repo_path: &Path,
+        args: &[String],
+        input: Option<&str>,
+        allow_non_zero: bool,
     ) -> Result<GitOutput> {
         let mut cmd = Command::new(&self.git_binary);
         cmd.current_dir(repo_path)
             .args(args)
-            .stdin(Stdio::null())
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
         for (k, v) in &self.env {
             cmd.env(k, v);
         }
 
-        let output = cmd
-            .output()
+        let mut child = cmd
+            .spawn()
             .map_err(|source| GitLgError::io("running git command", source))?;
+        // Feed stdin from another thread so a chatty git cannot deadlock on a
+        // full stdout pipe while we are still writing.
+        let writer = match (input, child.stdin.take()) {
+            (Some(input), Some(mut stdin)) => {
+                let input = input.to_string();
+                Some(std::thread::spawn(move || {
+                    stdin.write_all(input.as_bytes())
+                }))
+            }
+            _ => None,
+        };
+        let output = child
+            .wait_with_output()
+            .map_err(|source| GitLgError::io("running git command", source))?;
+        if let Some(writer) = writer {
+            writer
+                .join()
+                .map_err(|_| {
+                    GitLgError::io(
+                        "writing git stdin",
+                        std::io::Error::other("stdin writer panicked"),
+                    )
+                })?
+                .map_err(|source| GitLgError::io("writing git stdin", source))?;
+        }
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         let result = GitOutput {
@@ -93,6 +208,50 @@ impl GitRunner {
         })
     }
 
+    pub fn spawn(
+        &self,
+        repo_path: &Path,
+        args: &[String],
+        allow_non_zero: bool,
+    ) -> Result<GitStream> {
+        let mut cmd = Command::new(&self.git_binary);
+        cmd.current_dir(repo_path)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (k, v) in &self.env {
+            cmd.env(k, v);
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|source| GitLgError::io("spawning git command", source))?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            GitLgError::io(
+                "capturing git stdout",
+                std::io::Error::other("stdout was not piped"),
+            )
+        })?;
+        // Drain stderr separately so git never blocks on a full pipe while the
+        // caller is still consuming stdout.
+        let stderr = child.stderr.take().map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = pipe.read_to_end(&mut buf);
+                String::from_utf8_lossy(&buf).to_string()
+            })
+        });
+        Ok(GitStream {
+            program: self.git_binary.clone(),
+            args: args.to_vec(),
+            allow_non_zero,
+            child: Some(child),
+            stdout: BufReader::new(stdout),
+            stderr,
+        })
+    }
+
     pub fn exec_shell(
         &self,
         repo_path: &Path,
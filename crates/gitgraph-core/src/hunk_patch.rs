@@ -0,0 +1,200 @@
+use crate::error::{GitLgError, Result};
+use crate::line_history::parse_hunk_header;
+use crate::models::{HunkAction, HunkSelection};
+
+// Context used for the diffs that `HunkSelection` indexes refer to.
+pub const HUNK_CONTEXT_LINES: usize = 3;
+
+// `git apply` reading the patch from stdin: staging applies it to the index,
+// unstaging reverses it there and discarding reverses it in the worktree.
+pub fn hunk_apply_args(action: HunkAction) -> Vec<String> {
+    let mut args = vec!["apply".to_string(), "--whitespace=nowarn".to_string()];
+    if action != HunkAction::Discard {
+        args.push("--cached".to_string());
+    }
+    if action != HunkAction::Stage {
+        args.push("--reverse".to_string());
+    }
+    args.push("-".to_string());
+    args
+}
+
+// Cuts one hunk (or part of one) out of a single-file unified diff, keeping the
+// file header so the result can be fed to `git apply`. Unselected changes are
+// dropped or turned into context depending on the direction it will be
+// applied in: `reverse` is for `git apply --reverse`.
+pub fn select_hunk_patch(
+    file_patch: &str,
+    selection: &HunkSelection,
+    reverse: bool,
+) -> Result<String> {
+    let mut header = Vec::new();
+    let mut hunks: Vec<(&str, Vec<&str>)> = Vec::new();
+    // Only `\n` separates lines: a `\r` before it belongs to CRLF content and
+    // has to survive for the patch to apply.
+    for line in file_patch.split_inclusive('\n') {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        if line.starts_with("@@") {
+            hunks.push((line, Vec::new()));
+        } else if let Some((_, body)) = hunks.last_mut() {
+            body.push(line);
+        } else {
+            header.push(line);
+        }
+    }
+    if header.iter().any(|line| line.starts_with("Binary files ")) {
+        return Err(GitLgError::State(
+            "binary files cannot be split into hunks".to_string(),
+        ));
+    }
+    let (hunk_header, body) = hunks.get(selection.hunk).ok_or_else(|| {
+        GitLgError::State(format!(
+            "hunk {} does not exist (the diff has {})",
+            selection.hunk,
+            hunks.len()
+        ))
+    })?;
+    let hunk = parse_hunk_header(hunk_header)
+        .ok_or_else(|| GitLgError::Parse(format!("invalid hunk header: {hunk_header:?}")))?;
+
+    let selected = |idx: usize| {
+        selection
+            .lines
+            .is_none_or(|(start, end)| (start.min(end)..=start.max(end)).contains(&idx))
+    };
+    let mut lines = Vec::with_capacity(body.len());
+    let mut has_change = false;
+    let mut kept_previous = false;
+    for (idx, line) in body.iter().enumerate() {
+        // "\ No newline at end of file" belongs to the line before it.
+        if line.starts_with('\\') {
+            if kept_previous {
+                lines.push(line.to_string());
+            }
+            continue;
+        }
+        let (prefix, text) = line.split_at(line.len().min(1));
+        let kept = match prefix {
+            "+" | "-" if selected(idx) => {
+                has_change = true;
+                Some(line.to_string())
+            }
+            // Unselected additions are not in the preimage when applying
+            // forwards, and unselected deletions are not when reversing.
+            "+" if !reverse => None,
+            "-" if reverse => None,
+            "+" | "-" => Some(format!(" {text}")),
+            _ => Some(line.to_string()),
+        };
+        kept_previous = kept.is_some();
+        lines.extend(kept);
+    }
+    if !has_change {
+        return Err(GitLgError::State(
+            "the selection contains no changed lines".to_string(),
+        ));
+    }
+
+    let count = |skip: char| {
+        lines
+            .iter()
+            .filter(|line| !line.starts_with('\\') && !line.starts_with(skip))
+            .count()
+    };
+    let old_lines = count('+');
+    let new_lines = count('-');
+    let context = hunk_header
+        .split_once(" @@")
+        .map_or("", |(_, context)| context);
+    let mut patch = header.join("\n");
+    patch.push('\n');
+    patch.push_str(&format!(
+        "@@ -{},{old_lines} +{},{new_lines} @@{context}\n",
+        hunk.old_start, hunk.new_start
+    ));
+    for line in lines {
+        patch.push_str(&line);
+        patch.push('\n');
+    }
+    Ok(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select_hunk_patch;
+    use crate::models::HunkSelection;
+
+    const PATCH: &str = "diff --git a/f.txt b/f.txt\nindex 1111111..2222222 100644\n--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,3 @@ top\n a\n-b\n+B\n c\n@@ -10,2 +10,3 @@\n x\n-y\n+Y\n+Z\n";
+
+    #[test]
+    fn selects_whole_hunk_with_file_header() {
+        let selection = HunkSelection {
+            hunk: 1,
+            lines: None,
+        };
+        let patch = select_hunk_patch(PATCH, &selection, false).expect("select");
+        assert_eq!(
+            patch,
+            "diff --git a/f.txt b/f.txt\nindex 1111111..2222222 100644\n--- a/f.txt\n+++ b/f.txt\n@@ -10,2 +10,3 @@\n x\n-y\n+Y\n+Z\n"
+        );
+        assert!(
+            select_hunk_patch(
+                PATCH,
+                &HunkSelection {
+                    hunk: 2,
+                    lines: None
+                },
+                false
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn partial_selection_depends_on_direction() {
+        // Only `+Y`: the deletion of `y` becomes context going forwards and is
+        // dropped going backwards; `+Z` does the opposite.
+        let selection = HunkSelection {
+            hunk: 1,
+            lines: Some((2, 2)),
+        };
+        let forward = select_hunk_patch(PATCH, &selection, false).expect("forward");
+        assert!(forward.ends_with("@@ -10,2 +10,3 @@\n x\n y\n+Y\n"));
+        let reverse = select_hunk_patch(PATCH, &selection, true).expect("reverse");
+        assert!(reverse.ends_with("@@ -10,2 +10,3 @@\n x\n+Y\n Z\n"));
+
+        let context_only = HunkSelection {
+            hunk: 0,
+            lines: Some((0, 0)),
+        };
+        assert!(select_hunk_patch(PATCH, &context_only, false).is_err());
+    }
+
+    #[test]
+    fn keeps_carriage_returns_of_crlf_content() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\r\n-b\r\n+B\r\n";
+        let selection = HunkSelection {
+            hunk: 0,
+            lines: Some((2, 2)),
+        };
+        let forward = select_hunk_patch(patch, &selection, false).expect("forward");
+        assert_eq!(
+            forward,
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,3 @@\n a\r\n b\r\n+B\r\n"
+        );
+    }
+
+    #[test]
+    fn keeps_no_newline_marker_with_its_line() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1 +1 @@\n-old\n\\ No newline at end of file\n+new\n\\ No newline at end of file\n";
+        let selection = HunkSelection {
+            hunk: 0,
+            lines: Some((0, 0)),
+        };
+        let forward = select_hunk_patch(patch, &selection, false).expect("forward");
+        assert_eq!(
+            forward,
+            "--- a/f\n+++ b/f\n@@ -1,1 +1,0 @@\n-old\n\\ No newline at end of file\n"
+        );
+    }
+}
@@ -1,22 +1,64 @@
+pub mod action_risk;
 pub mod actions;
+pub mod bisect;
+pub mod blame;
+pub mod commit;
 pub mod error;
+pub mod export;
 pub mod git;
+pub mod hunk_patch;
+pub mod line_history;
 pub mod log_parser;
+pub mod merge_preview;
 pub mod models;
+pub mod oplog;
+pub mod rebase;
+pub mod repo_state;
 pub mod search;
 pub mod service;
 pub mod state;
+pub mod status;
+pub mod stream;
+pub mod text_graph;
 
+pub use action_risk::{ActionRisk, classify_git_args, classify_shell_script};
 pub use actions::{
     ActionCatalog, ActionContext, ActionOption, ActionParam, ActionRequest, ActionScope,
     ActionTemplate, ResolvedAction,
 };
+pub use bisect::{
+    apply_bisect_candidates, bisect_candidates_args, bisect_refs_args, estimate_bisect_steps,
+    parse_bisect_refs,
+};
+pub use blame::parse_blame_porcelain;
+pub use commit::{commit_args, commit_row_args, parse_commit_row, split_commit_message};
 pub use error::{GitLgError, Result};
-pub use git::{GitOutput, GitRunner};
+pub use export::{ExportFormat, export_graph};
+pub use git::{GitOutput, GitRunner, GitStream};
+pub use hunk_patch::{HUNK_CONTEXT_LINES, hunk_apply_args, select_hunk_patch};
+pub use line_history::{line_history_args, parse_hunk_header, parse_line_history};
+pub use merge_preview::{merge_tree_args, parse_merge_tree};
 pub use models::{
-    BlameInfo, BranchInfo, CommitSearchQuery, FileChange, GitRef, GitRefKind, GraphData, GraphEdge,
-    GraphQuery, GraphRow,
+    BisectMark, BisectStatus, BlameCommit, BlameInfo, BlameOptions, BranchInfo, CommitOptions,
+    CommitSearchQuery, CommitSignature, CommitTrailer, DiffHunk, FileBlame, FileChange,
+    FileHistoryEntry, FilePatch, FileStatus, GitRef, GitRefKind, GraphData, GraphEdge, GraphMode,
+    GraphQuery, GraphRow, GraphSegment, GraphSegmentKind, HunkAction, HunkSelection,
+    LineHistoryEntry, MergeConflict, MergePreview, OperationRecord, RebasePlan, RebaseState,
+    RebaseStatus, RebaseTodoAction, RebaseTodoItem, RefSnapshot, RefUpdate, RepoOperation,
+    RepoOperationControl, RepoOperationKind, RevisionComparison, SignatureStatus, StatusBranch,
+    StatusEntry, StatusEntryKind, TrailerIdentity, UNCOMMITTED_HASH, UndoPlan, WorkingTreeStatus,
+    WorktreeDiffSide,
+};
+pub use oplog::{
+    OperationLog, parse_ref_snapshot, plan_undo, ref_snapshot_args, update_ref_script,
 };
+pub use rebase::{count_todo_items, plan_rebase, render_rebase_todo};
+pub use repo_state::{detect_repo_operation, operation_control_args};
 pub use search::filter_commits;
 pub use service::{ActionExecutionResult, GitLgService};
 pub use state::{AppState, StateStore};
+pub use status::{parse_status_porcelain_v2, status_args};
+pub use stream::GraphStream;
+pub use text_graph::{
+    TextGraphCell, TextGraphCellKind, TextGraphStyle, graph_cells, graph_prefix, signature_badge,
+};
@@ -0,0 +1,196 @@
+use crate::error::{GitLgError, Result};
+use crate::log_parser::{GraphBuilder, LOG_FORMAT, RECORD_SEP, parse_git_log_record};
+use crate::models::{DiffHunk, LineHistoryEntry};
+
+// Ends each commit header so the `-L` patch that follows can be split off.
+const PATCH_SEP: char = '\u{001d}';
+
+// `git log -L` arguments for lines `start..=end` of `file` (repo-relative) as
+// numbered in `revision`. Shared with the Zed extension, which runs git itself.
+pub fn line_history_args(revision: &str, file: &str, start: usize, end: usize) -> Vec<String> {
+    let fields = LOG_FORMAT
+        .trim_start_matches("--pretty=format:")
+        .trim_end_matches("%x1e");
+    vec![
+        "-c".to_string(),
+        "core.quotePath=false".to_string(),
+        "log".to_string(),
+        "--no-color".to_string(),
+        "--no-ext-diff".to_string(),
+        format!("--pretty=format:%x1e{fields}%x1d"),
+        format!("-L{start},{end}:{file}"),
+        "--end-of-options".to_string(),
+        revision.to_string(),
+    ]
+}
+
+// `git log -L` cannot rewrite parents to the commits it shows, so rows are
+// drawn as one chain in log order, like `file_history`, and keep their real
+// parents.
+pub fn parse_line_history(stdout: &str) -> Result<Vec<LineHistoryEntry>> {
+    let mut parsed = Vec::new();
+    for chunk in stdout.split(RECORD_SEP) {
+        if chunk.trim().is_empty() {
+            continue;
+        }
+        let (record, patch) = chunk.split_once(PATCH_SEP).ok_or_else(|| {
+            GitLgError::Parse(format!(
+                "line history record without patch marker: {chunk:?}"
+            ))
+        })?;
+        let Some(commit) = parse_git_log_record(record)? else {
+            continue;
+        };
+        parsed.push((commit, patch));
+    }
+
+    let older = parsed
+        .iter()
+        .skip(1)
+        .map(|(commit, _)| Some(commit.hash.clone()))
+        .chain([None])
+        .collect::<Vec<_>>();
+    let mut builder = GraphBuilder::new();
+    Ok(parsed
+        .into_iter()
+        .zip(older)
+        .map(|((commit, patch), older)| {
+            let (path, previous_path, hunks) = parse_patch(patch);
+            LineHistoryEntry {
+                path,
+                previous_path,
+                row: builder.push_with_layout_parents(commit, older.into_iter().collect()),
+                hunks,
+            }
+        })
+        .collect())
+}
+
+fn parse_patch(patch: &str) -> (String, Option<String>, Vec<DiffHunk>) {
+    let mut old_path: Option<String> = None;
+    let mut new_path = String::new();
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut in_hunk = false;
+    for line in patch.lines() {
+        if line.starts_with("diff --git ") {
+            in_hunk = false;
+            continue;
+        }
+        if let Some(hunk) = parse_hunk_header(line) {
+            hunks.push(hunk);
+            in_hunk = true;
+            continue;
+        }
+        if in_hunk {
+            if let Some(hunk) = hunks.last_mut() {
+                hunk.lines.push(line.to_string());
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("--- ") {
+            old_path = diff_header_path(path, "a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            new_path = diff_header_path(path, "b/").unwrap_or_default();
+        }
+    }
+    if new_path.is_empty() {
+        new_path = old_path.clone().unwrap_or_default();
+    }
+    let previous_path = old_path.filter(|old| *old != new_path);
+    (new_path, previous_path, hunks)
+}
+
+fn diff_header_path(raw: &str, prefix: &str) -> Option<String> {
+    let raw = raw.trim_end();
+    if raw == "/dev/null" {
+        return None;
+    }
+    Some(raw.strip_prefix(prefix).unwrap_or(raw).to_string())
+}
+
+// Parses `@@ -old_start[,old_lines] +new_start[,new_lines] @@ [context]`.
+pub fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    let ranges = line.strip_prefix("@@ -")?;
+    let (ranges, _) = ranges.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_lines) = parse_hunk_range(old)?;
+    let (new_start, new_lines) = parse_hunk_range(new)?;
+    Some(DiffHunk {
+        header: line.to_string(),
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: Vec::new(),
+    })
+}
+
+fn parse_hunk_range(raw: &str) -> Option<(usize, usize)> {
+    match raw.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((raw.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::log_parser::{RECORD_SEP, test_record_fields};
+
+    use super::{PATCH_SEP, parse_hunk_header, parse_line_history};
+
+    fn record(hash: &str, parents: &str, subject: &str) -> String {
+        format!(
+            "{RECORD_SEP}{}{PATCH_SEP}",
+            test_record_fields(hash, parents, "", subject)
+        )
+    }
+
+    #[test]
+    fn parses_commits_and_hunks_with_renames() {
+        let stdout = format!(
+            "{}\n\ndiff --git a/old.rs b/new.rs\n--- a/old.rs\n+++ b/new.rs\n@@ -2,2 +2,3 @@ fn main()\n a\n-b\n+b2\n+c\n{}\n\ndiff --git a/old.rs b/old.rs\n--- /dev/null\n+++ b/old.rs\n@@ -0,0 +1,2 @@\n+a\n+b\n",
+            record("c2c2", "b0b0", "edit"),
+            record("c1c1", "", "add")
+        );
+        let entries = parse_line_history(&stdout).expect("parse line history");
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].row.subject, "edit");
+        assert_eq!(entries[0].path, "new.rs");
+        assert_eq!(entries[0].previous_path.as_deref(), Some("old.rs"));
+        // The unrelated parent b0b0 is kept; the rows are still one chain.
+        assert_eq!(entries[0].row.parents, vec!["b0b0".to_string()]);
+        assert_eq!((entries[0].row.lane, entries[1].row.lane), (0, 0));
+        let hunk = &entries[0].hunks[0];
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (2, 2, 2, 3)
+        );
+        assert_eq!(hunk.lines, vec![" a", "-b", "+b2", "+c"]);
+
+        assert_eq!(entries[1].path, "old.rs");
+        assert_eq!(entries[1].previous_path, None);
+        assert!(entries[1].row.parents.is_empty());
+        assert_eq!(entries[1].hunks[0].lines, vec!["+a", "+b"]);
+    }
+
+    #[test]
+    fn hunk_header_counts_default_to_one() {
+        let hunk = parse_hunk_header("@@ -3 +4 @@").expect("header");
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (3, 1, 4, 1)
+        );
+        assert!(parse_hunk_header("@@@ -1,2 -1,2 +1,3 @@@").is_none());
+    }
+}
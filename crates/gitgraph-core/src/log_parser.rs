@@ -1,8 +1,47 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::BufRead;
+
 use crate::error::{GitLgError, Result};
-use crate::models::{GitRef, GitRefKind, GraphEdge, GraphRow};
+use crate::models::{
+    BranchInfo, CommitSignature, CommitTrailer, GitRef, GitRefKind, GraphEdge, GraphMode, GraphRow,
+    GraphSegment, GraphSegmentKind, SignatureStatus, TrailerIdentity,
+};
 
 pub const FIELD_SEP: char = '\u{001f}';
 pub const RECORD_SEP: char = '\u{001e}';
+pub const LOG_FORMAT: &str = "--pretty=format:%H%x1f%h%x1f%P%x1f%an%x1f%ae%x1f%at%x1f%ct%x1f%cn%x1f%ce%x1f%ai%x1f%ci%x1f%D%x1f%s%x1f%b%x1e";
+const LOG_FIELD_COUNT: usize = 14;
+const NOTES_TAG: &str = "notes:";
+const SIGNATURE_STATUS_TAG: &str = "sig:";
+const SIGNATURE_SIGNER_TAG: &str = "signer:";
+const SIGNATURE_KEY_TAG: &str = "key:";
+// Optional fields are appended after the body as `tag:value` so the parser
+// does not depend on which of them were requested. `%N` is printed literally
+// unless `--notes` is given, and `%G?` runs a verification per commit, so
+// both are only requested when the query asks for them.
+pub fn log_format(include_notes: bool, include_signatures: bool) -> String {
+    let mut format = LOG_FORMAT.trim_end_matches("%x1e").to_string();
+    if include_notes {
+        format.push_str("%x1f");
+        format.push_str(NOTES_TAG);
+        format.push_str("%N");
+    }
+    if include_signatures {
+        for (tag, placeholder) in [
+            (SIGNATURE_STATUS_TAG, "%G?"),
+            (SIGNATURE_SIGNER_TAG, "%GS"),
+            (SIGNATURE_KEY_TAG, "%GK"),
+        ] {
+            format.push_str("%x1f");
+            format.push_str(tag);
+            format.push_str(placeholder);
+        }
+    }
+    format.push_str("%x1e");
+    format
+}
+
+pub const BRANCH_LIST_FORMAT: &str = "--format=%(refname)%1f%(upstream)%1f%(upstream:remotename)%1f%(upstream:track,nobracket)%1f%(objectname)%1f%(committerdate:unix)";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RawCommit {
@@ -13,69 +52,432 @@ pub struct RawCommit {
     pub author_email: String,
     pub authored_unix: i64,
     pub committed_unix: i64,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub author_tz_offset_minutes: i32,
+    pub committer_tz_offset_minutes: i32,
     pub refs: Vec<GitRef>,
     pub subject: String,
     pub body: String,
+    pub notes: String,
+    pub signature: Option<CommitSignature>,
 }
 
 pub fn parse_git_log_records(stdout: &str) -> Result<Vec<RawCommit>> {
     let mut commits = Vec::new();
     for raw_record in stdout.split(RECORD_SEP) {
-        let record = raw_record.trim_matches(['\r', '\n', ' ']);
-        if record.is_empty() {
-            continue;
-        }
-        let fields: Vec<&str> = record.splitn(10, FIELD_SEP).collect();
-        if fields.len() != 10 {
-            return Err(GitLgError::Parse(format!(
-                "expected 10 fields, got {} in record {:?}",
-                fields.len(),
-                record
-            )));
+        if let Some(commit) = parse_git_log_record(raw_record)? {
+            commits.push(commit);
         }
-        let authored_unix = fields[5].parse::<i64>().map_err(|e| {
-            GitLgError::Parse(format!(
-                "invalid authored unix timestamp {:?}: {}",
-                fields[5], e
-            ))
-        })?;
-        let committed_unix = fields[6].parse::<i64>().map_err(|e| {
-            GitLgError::Parse(format!(
-                "invalid committed unix timestamp {:?}: {}",
-                fields[6], e
-            ))
-        })?;
-        let parents = if fields[2].trim().is_empty() {
-            Vec::new()
+    }
+    Ok(commits)
+}
+
+pub fn parse_git_log_record(raw_record: &str) -> Result<Option<RawCommit>> {
+    let record = raw_record.trim_matches(['\r', '\n', ' ']);
+    if record.is_empty() {
+        return Ok(None);
+    }
+    let fields: Vec<&str> = record.split(FIELD_SEP).collect();
+    if fields.len() < LOG_FIELD_COUNT {
+        return Err(GitLgError::Parse(format!(
+            "expected {} fields, got {} in record {:?}",
+            LOG_FIELD_COUNT,
+            fields.len(),
+            record
+        )));
+    }
+    let authored_unix = fields[5].parse::<i64>().map_err(|e| {
+        GitLgError::Parse(format!(
+            "invalid authored unix timestamp {:?}: {}",
+            fields[5], e
+        ))
+    })?;
+    let committed_unix = fields[6].parse::<i64>().map_err(|e| {
+        GitLgError::Parse(format!(
+            "invalid committed unix timestamp {:?}: {}",
+            fields[6], e
+        ))
+    })?;
+    let parents = if fields[2].trim().is_empty() {
+        Vec::new()
+    } else {
+        fields[2]
+            .split_whitespace()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    let mut body = fields[13].to_string();
+    let mut notes = String::new();
+    let mut signature: Option<CommitSignature> = None;
+    for extra in &fields[LOG_FIELD_COUNT..] {
+        if let Some(value) = extra.strip_prefix(NOTES_TAG) {
+            notes = value.trim_end().to_string();
+        } else if let Some(value) = extra.strip_prefix(SIGNATURE_STATUS_TAG) {
+            signature
+                .get_or_insert_with(CommitSignature::default)
+                .status = SignatureStatus::from_git_code(value.trim());
+        } else if let Some(value) = extra.strip_prefix(SIGNATURE_SIGNER_TAG) {
+            signature
+                .get_or_insert_with(CommitSignature::default)
+                .signer = value.trim().to_string();
+        } else if let Some(value) = extra.strip_prefix(SIGNATURE_KEY_TAG) {
+            signature
+                .get_or_insert_with(CommitSignature::default)
+                .key_id = value.trim().to_string();
         } else {
-            fields[2]
-                .split_whitespace()
-                .map(ToString::to_string)
-                .collect::<Vec<_>>()
-        };
+            // An untagged field means the body itself contained a separator.
+            body.push(FIELD_SEP);
+            body.push_str(extra);
+        }
+    }
 
-        commits.push(RawCommit {
-            hash: fields[0].to_string(),
-            short_hash: fields[1].to_string(),
-            parents,
-            author_name: fields[3].to_string(),
-            author_email: fields[4].to_string(),
-            authored_unix,
-            committed_unix,
-            refs: parse_refs(fields[7]),
-            subject: fields[8].to_string(),
-            body: fields[9].to_string(),
-        });
+    Ok(Some(RawCommit {
+        hash: fields[0].to_string(),
+        short_hash: fields[1].to_string(),
+        parents,
+        author_name: fields[3].to_string(),
+        author_email: fields[4].to_string(),
+        authored_unix,
+        committed_unix,
+        committer_name: fields[7].to_string(),
+        committer_email: fields[8].to_string(),
+        author_tz_offset_minutes: parse_tz_offset(fields[9])?,
+        committer_tz_offset_minutes: parse_tz_offset(fields[10])?,
+        refs: parse_refs(fields[11]),
+        subject: fields[12].to_string(),
+        body,
+        notes,
+        signature,
+    }))
+}
+
+// `%ai` ends in a `+hhmm`/`-hhmm` offset; only that suffix is kept.
+fn parse_tz_offset(iso_date: &str) -> Result<i32> {
+    let offset = iso_date.rsplit(' ').next().unwrap_or_default();
+    let invalid = || GitLgError::Parse(format!("invalid timezone offset in {:?}", iso_date));
+    let (sign, digits) = match offset.split_at_checked(1) {
+        Some(("+", digits)) => (1, digits),
+        Some(("-", digits)) => (-1, digits),
+        _ => return Err(invalid()),
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours = digits[..2].parse::<i32>().map_err(|_| invalid())?;
+    let minutes = digits[2..].parse::<i32>().map_err(|_| invalid())?;
+    Ok(sign * (hours * 60 + minutes))
+}
+
+#[derive(Debug)]
+pub struct LogRecordReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> LogRecordReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<RawCommit>> {
+        loop {
+            self.buf.clear();
+            let read = self
+                .reader
+                .read_until(RECORD_SEP as u8, &mut self.buf)
+                .map_err(|source| GitLgError::io("reading git log output", source))?;
+            if read == 0 {
+                return Ok(None);
+            }
+            if self.buf.last() == Some(&(RECORD_SEP as u8)) {
+                self.buf.pop();
+            }
+            let record = String::from_utf8_lossy(&self.buf);
+            if let Some(commit) = parse_git_log_record(&record)? {
+                return Ok(Some(commit));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LogRecordReader<R> {
+    type Item = Result<RawCommit>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
     }
-    Ok(commits)
 }
 
 pub fn build_graph_rows(commits: Vec<RawCommit>) -> Vec<GraphRow> {
-    let mut active_lanes: Vec<Option<String>> = Vec::new();
-    let mut rows = Vec::with_capacity(commits.len());
+    build_graph_rows_for_mode(commits, GraphMode::Full)
+}
 
-    for commit in commits {
-        let lane = find_or_allocate_lane(&commit.hash, &mut active_lanes);
+pub fn build_graph_rows_for_mode(commits: Vec<RawCommit>, mode: GraphMode) -> Vec<GraphRow> {
+    let mut builder = GraphBuilder::for_mode(mode);
+    if mode == GraphMode::CollapseLinear {
+        builder = builder.with_chain_collapse(ChainCollapse::new(usize::MAX, 0));
+    }
+    let mut rows = commits
+        .into_iter()
+        .flat_map(|commit| builder.accept(commit))
+        .collect::<Vec<_>>();
+    rows.extend(builder.finish());
+    rows
+}
+
+// Commits with exactly one parent, exactly one child and no refs are hidden;
+// visible rows have their parents rewritten past the hidden run. git lists
+// children before parents, so a commit's fate is known when it arrives. A
+// visible commit is queued until its parents are decided; after `lookahead`
+// more records without a release, the front is released anyway and its
+// undecided parents are kept visible.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainCollapse {
+    lookahead: usize,
+    // Records still to read only for their children, as with `--skip`.
+    skip: usize,
+    child_counts: HashMap<String, usize>,
+    seen: HashSet<String>,
+    hidden: HashMap<String, String>,
+    pinned: HashSet<String>,
+    pending: VecDeque<RawCommit>,
+    // Records read since a queued commit was last released.
+    held: usize,
+}
+
+impl ChainCollapse {
+    pub fn new(lookahead: usize, skip: usize) -> Self {
+        Self {
+            lookahead: lookahead.max(1),
+            skip,
+            ..Self::default()
+        }
+    }
+
+    pub fn hidden_count(&self) -> usize {
+        self.hidden.len()
+    }
+
+    pub fn is_hidden(&self, hash: &str) -> bool {
+        self.hidden.contains_key(hash)
+    }
+
+    // Returns the queued commits that became ready, with their parents
+    // rewritten and the number of commits hidden below the first parent.
+    pub fn push(&mut self, commit: RawCommit) -> Vec<(RawCommit, usize)> {
+        for parent in unique_parents(&commit.parents) {
+            *self.child_counts.entry(parent.to_string()).or_default() += 1;
+        }
+        self.seen.insert(commit.hash.clone());
+        let hidden = commit.refs.is_empty()
+            && commit.parents.len() == 1
+            && self.child_counts.get(&commit.hash) == Some(&1)
+            && !self.pinned.contains(&commit.hash);
+        if hidden {
+            self.hidden
+                .insert(commit.hash.clone(), commit.parents[0].clone());
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+        } else if !hidden {
+            self.pending.push_back(commit);
+        }
+        if !self.pending.is_empty() {
+            self.held += 1;
+        }
+        self.drain(false)
+    }
+
+    // Releases every queued commit at the end of the log.
+    pub fn finish(&mut self) -> Vec<(RawCommit, usize)> {
+        self.drain(true)
+    }
+
+    fn drain(&mut self, end_of_log: bool) -> Vec<(RawCommit, usize)> {
+        let mut ready = Vec::new();
+        while let Some(front) = self.pending.front() {
+            let forced = end_of_log || self.held > self.lookahead;
+            if !forced && !front.parents.iter().all(|parent| self.is_decided(parent)) {
+                break;
+            }
+            let Some(commit) = self.pending.pop_front() else {
+                break;
+            };
+            self.held = 0;
+            ready.push(self.rewrite_parents(commit, !end_of_log));
+        }
+        ready
+    }
+
+    fn is_decided<'a>(&'a self, mut hash: &'a str) -> bool {
+        for _ in 0..=self.hidden.len() {
+            if !self.seen.contains(hash) {
+                return false;
+            }
+            match self.hidden.get(hash) {
+                Some(parent) => hash = parent,
+                None => return true,
+            }
+        }
+        true
+    }
+
+    fn rewrite_parents(&mut self, mut commit: RawCommit, pin: bool) -> (RawCommit, usize) {
+        let mut collapsed = 0;
+        let mut parents = Vec::new();
+        for (idx, parent) in unique_parents(&commit.parents).enumerate() {
+            let mut hash = parent;
+            let mut skipped = 0;
+            while let Some(next) = self.hidden.get(hash) {
+                hash = next;
+                skipped += 1;
+                if skipped > self.hidden.len() {
+                    break;
+                }
+            }
+            if idx == 0 {
+                collapsed = skipped;
+            }
+            parents.push(hash.to_string());
+        }
+        if pin {
+            // Rows now point at these, so they must not be hidden later.
+            self.pinned.extend(
+                parents
+                    .iter()
+                    .filter(|hash| !self.seen.contains(*hash))
+                    .cloned(),
+            );
+        }
+        commit.parents = unique_parents(&parents).map(ToString::to_string).collect();
+        (commit, collapsed)
+    }
+}
+
+fn unique_parents(parents: &[String]) -> impl Iterator<Item = &str> {
+    let mut seen = HashSet::new();
+    parents
+        .iter()
+        .map(String::as_str)
+        .filter(move |parent| seen.insert(*parent))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ColorClaim {
+    rank: u8,
+    group: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphBuilder {
+    mode: GraphMode,
+    collapse: Option<ChainCollapse>,
+    active_lanes: Vec<Option<String>>,
+    lane_groups: Vec<u32>,
+    color_claims: HashMap<String, ColorClaim>,
+    rows_built: usize,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn for_mode(mode: GraphMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_chain_collapse(mut self, collapse: ChainCollapse) -> Self {
+        self.collapse = Some(collapse);
+        self
+    }
+
+    pub fn mode(&self) -> GraphMode {
+        self.mode
+    }
+
+    pub fn rows_built(&self) -> usize {
+        self.rows_built
+    }
+
+    pub fn active_lane_count(&self) -> usize {
+        self.active_lanes.len()
+    }
+
+    // Applies the builder's mode before laying out the commit: git keeps every
+    // parent in %P under --first-parent, and rewritten parents may repeat.
+    // With chain collapse rows can come out later than their commit.
+    pub fn accept(&mut self, mut commit: RawCommit) -> Vec<GraphRow> {
+        if self.mode == GraphMode::FirstParent {
+            commit.parents.truncate(1);
+        }
+        match self.collapse.as_mut() {
+            Some(collapse) => {
+                let ready = collapse.push(commit);
+                self.push_collapsed(ready)
+            }
+            None => {
+                commit.parents = unique_parents(&commit.parents)
+                    .map(ToString::to_string)
+                    .collect();
+                vec![self.push(commit)]
+            }
+        }
+    }
+
+    // Lays out the commits still held back for chain collapse.
+    pub fn finish(&mut self) -> Vec<GraphRow> {
+        let ready = self
+            .collapse
+            .as_mut()
+            .map(ChainCollapse::finish)
+            .unwrap_or_default();
+        self.push_collapsed(ready)
+    }
+
+    fn push_collapsed(&mut self, ready: Vec<(RawCommit, usize)>) -> Vec<GraphRow> {
+        ready
+            .into_iter()
+            .map(|(commit, collapsed_commits)| {
+                let mut row = self.push(commit);
+                row.collapsed_commits = collapsed_commits;
+                row
+            })
+            .collect()
+    }
+
+    // Draws the commit as if `layout_parents` were its parents, for histories
+    // whose rows are not parent-linked; the row keeps the real parents.
+    pub fn push_with_layout_parents(
+        &mut self,
+        mut commit: RawCommit,
+        layout_parents: Vec<String>,
+    ) -> GraphRow {
+        let parents = std::mem::replace(&mut commit.parents, layout_parents);
+        let mut row = self.push(commit);
+        row.parents = parents;
+        row
+    }
+
+    pub fn push(&mut self, commit: RawCommit) -> GraphRow {
+        let incoming_lanes = self.active_lanes.clone();
+        let incoming_groups = self.lane_groups.clone();
+        let claim = self.resolve_color(&commit);
+        let color_group = claim.group;
+        let active_lanes = &mut self.active_lanes;
+        let lane = find_or_allocate_lane(&commit.hash, active_lanes);
 
         // A hash can appear in multiple lanes due to merge ancestry.
         // Collapse duplicates after choosing primary lane.
@@ -85,6 +487,9 @@ pub fn build_graph_rows(commits: Vec<RawCommit>) -> Vec<GraphRow> {
             }
         }
 
+        self.lane_groups.resize(active_lanes.len(), 0);
+        self.lane_groups[lane] = color_group;
+
         let mut edges = Vec::new();
         if let Some(first_parent) = commit.parents.first() {
             active_lanes[lane] = Some(first_parent.clone());
@@ -92,12 +497,36 @@ pub fn build_graph_rows(commits: Vec<RawCommit>) -> Vec<GraphRow> {
                 to_lane: lane,
                 parent_hash: first_parent.clone(),
             });
+            // The first parent continues this row's color unless a branch
+            // with a stronger claim (e.g. HEAD's branch) already reached it.
+            let existing = self.color_claims.get(first_parent);
+            if existing.is_none_or(|existing| claim.rank < existing.rank) {
+                self.color_claims.insert(first_parent.clone(), claim);
+            }
         } else {
             active_lanes[lane] = None;
         }
 
-        for parent in commit.parents.iter().skip(1) {
-            let target_lane = find_or_allocate_lane(parent, &mut active_lanes);
+        let merged_branch = merged_branch_name(&commit.subject);
+        for (idx, parent) in commit.parents.iter().enumerate().skip(1) {
+            let known_lane = active_lanes
+                .iter()
+                .position(|slot| slot.as_deref() == Some(parent.as_str()));
+            let target_lane = find_or_allocate_lane(parent, active_lanes);
+            if known_lane.is_none() {
+                // Merged-in parents get a weak claim, named after the branch
+                // from the merge subject when git's default message is used.
+                let group = match merged_branch.filter(|_| idx == 1) {
+                    Some(name) => color_group_for_key("branch", name),
+                    None => commit_color_group(parent),
+                };
+                let claim = *self
+                    .color_claims
+                    .entry(parent.clone())
+                    .or_insert(ColorClaim { rank: 3, group });
+                self.lane_groups.resize(active_lanes.len(), 0);
+                self.lane_groups[target_lane] = claim.group;
+            }
             edges.push(GraphEdge {
                 to_lane: target_lane,
                 parent_hash: parent.clone(),
@@ -107,8 +536,18 @@ pub fn build_graph_rows(commits: Vec<RawCommit>) -> Vec<GraphRow> {
         while active_lanes.last().is_some_and(Option::is_none) {
             active_lanes.pop();
         }
+        self.lane_groups.truncate(active_lanes.len());
 
-        rows.push(GraphRow {
+        let segments = build_row_segments(
+            &commit,
+            lane,
+            color_group,
+            (&incoming_lanes, &incoming_groups),
+            (active_lanes, &self.lane_groups),
+            &edges,
+        );
+        self.rows_built += 1;
+        GraphRow {
             hash: commit.hash,
             short_hash: commit.short_hash,
             parents: commit.parents,
@@ -116,15 +555,162 @@ pub fn build_graph_rows(commits: Vec<RawCommit>) -> Vec<GraphRow> {
             author_email: commit.author_email,
             authored_unix: commit.authored_unix,
             committed_unix: commit.committed_unix,
+            committer_name: commit.committer_name,
+            committer_email: commit.committer_email,
+            author_tz_offset_minutes: commit.author_tz_offset_minutes,
+            committer_tz_offset_minutes: commit.committer_tz_offset_minutes,
             subject: commit.subject,
+            trailers: parse_trailers(&commit.body),
             body: commit.body,
+            notes: commit.notes,
+            signature: commit.signature,
             refs: commit.refs,
             lane,
             active_lane_count: active_lanes.len(),
+            color_group,
+            collapsed_commits: 0,
             edges,
-        });
+            segments,
+        }
     }
-    rows
+
+    // A commit reached through a first-parent link keeps its child's color;
+    // otherwise the branch ref decorating the tip (or the tip hash itself)
+    // keys a new group, so colors survive refreshes and pagination. A tip's
+    // own branch ref beats a weaker claim, such as the one a merge leaves on
+    // its merged-in parent.
+    fn resolve_color(&mut self, commit: &RawCommit) -> ColorClaim {
+        let own = Self::ref_color(commit);
+        match self.color_claims.remove(&commit.hash) {
+            Some(claim) if claim.rank <= own.rank => claim,
+            _ => own,
+        }
+    }
+
+    fn ref_color(commit: &RawCommit) -> ColorClaim {
+        commit
+            .refs
+            .iter()
+            .filter_map(|git_ref| match git_ref.kind {
+                GitRefKind::Head => git_ref.target.as_deref().map(|name| (0, name)),
+                GitRefKind::LocalBranch => Some((1, git_ref.name.as_str())),
+                GitRefKind::RemoteBranch => git_ref
+                    .name
+                    .split_once('/')
+                    .map(|(_, name)| (2, name))
+                    .filter(|(_, name)| *name != "HEAD"),
+                _ => None,
+            })
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(rank, name)| ColorClaim {
+                rank,
+                group: color_group_for_key("branch", name),
+            })
+            .unwrap_or(ColorClaim {
+                rank: 3,
+                group: commit_color_group(&commit.hash),
+            })
+    }
+}
+
+fn merged_branch_name(subject: &str) -> Option<&str> {
+    if let Some(rest) = subject.strip_prefix("Merge pull request ") {
+        let (_, source) = rest.split_once(" from ")?;
+        let source = source.split_whitespace().next()?;
+        return source.split_once('/').map(|(_, name)| name);
+    }
+    let (rest, remote) = if let Some(rest) = subject.strip_prefix("Merge branch '") {
+        (rest, false)
+    } else {
+        (
+            subject.strip_prefix("Merge remote-tracking branch '")?,
+            true,
+        )
+    };
+    let name = rest.split_once('\'')?.0;
+    if remote {
+        name.split_once('/').map(|(_, name)| name)
+    } else {
+        Some(name)
+    }
+}
+
+fn commit_color_group(hash: &str) -> u32 {
+    color_group_for_key("commit", hash)
+}
+
+// FNV-1a keeps the ids identical across runs and builds, unlike std's hasher.
+fn color_group_for_key(namespace: &str, key: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in namespace.bytes().chain([b':']).chain(key.bytes()) {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+fn build_row_segments(
+    commit: &RawCommit,
+    lane: usize,
+    color_group: u32,
+    (incoming_lanes, incoming_groups): (&[Option<String>], &[u32]),
+    (outgoing_lanes, outgoing_groups): (&[Option<String>], &[u32]),
+    edges: &[GraphEdge],
+) -> Vec<GraphSegment> {
+    let segment = |kind, from_lane, to_lane, color_group| GraphSegment {
+        kind,
+        from_lane,
+        to_lane,
+        color_group,
+    };
+    let mut segments = Vec::new();
+    let mut has_incoming = false;
+    for (idx, slot) in incoming_lanes.iter().enumerate() {
+        let Some(hash) = slot.as_deref() else {
+            continue;
+        };
+        if hash == commit.hash {
+            let kind = if idx == lane {
+                has_incoming = true;
+                GraphSegmentKind::Incoming
+            } else {
+                GraphSegmentKind::Fork
+            };
+            segments.push(segment(kind, idx, lane, incoming_groups[idx]));
+            continue;
+        }
+        let target = if outgoing_lanes.get(idx).and_then(Option::as_deref) == Some(hash) {
+            Some(idx)
+        } else {
+            outgoing_lanes
+                .iter()
+                .position(|slot| slot.as_deref() == Some(hash))
+        };
+        let Some(to) = target else {
+            continue;
+        };
+        let kind = if to == idx {
+            GraphSegmentKind::PassThrough
+        } else {
+            GraphSegmentKind::Shift
+        };
+        segments.push(segment(kind, idx, to, incoming_groups[idx]));
+    }
+    if !has_incoming {
+        segments.push(segment(GraphSegmentKind::Start, lane, lane, color_group));
+    }
+    for (idx, edge) in edges.iter().enumerate() {
+        let (kind, group) = if idx == 0 {
+            (GraphSegmentKind::Outgoing, color_group)
+        } else {
+            (GraphSegmentKind::Merge, outgoing_groups[edge.to_lane])
+        };
+        segments.push(segment(kind, lane, edge.to_lane, group));
+    }
+    if edges.is_empty() {
+        segments.push(segment(GraphSegmentKind::End, lane, lane, color_group));
+    }
+    segments
 }
 
 fn find_or_allocate_lane(hash: &str, active_lanes: &mut Vec<Option<String>>) -> usize {
@@ -147,6 +733,102 @@ fn find_or_allocate_lane(hash: &str, active_lanes: &mut Vec<Option<String>>) ->
     active_lanes.len() - 1
 }
 
+// Trailers are the `Key: value` lines of the body's last paragraph; indented
+// lines continue the previous value. Any other line means there are none.
+pub fn parse_trailers(body: &str) -> Vec<CommitTrailer> {
+    let body = body.replace("\r\n", "\n");
+    let Some(block) = body.trim_end().rsplit("\n\n").next() else {
+        return Vec::new();
+    };
+    let mut trailers: Vec<CommitTrailer> = Vec::new();
+    for line in block.lines().filter(|line| !line.trim().is_empty()) {
+        if line.starts_with([' ', '\t']) {
+            let Some(last) = trailers.last_mut() else {
+                return Vec::new();
+            };
+            last.value.push(' ');
+            last.value.push_str(line.trim());
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Vec::new();
+        };
+        let key = key.trim_end();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Vec::new();
+        }
+        trailers.push(CommitTrailer {
+            key: key.to_string(),
+            value: value.trim().to_string(),
+            identity: None,
+        });
+    }
+    for trailer in &mut trailers {
+        trailer.identity = parse_trailer_identity(&trailer.value);
+    }
+    trailers
+}
+
+fn parse_trailer_identity(value: &str) -> Option<TrailerIdentity> {
+    let (name, rest) = value.rsplit_once('<')?;
+    let email = rest.strip_suffix('>')?.trim();
+    if !email.contains('@') {
+        return None;
+    }
+    Some(TrailerIdentity {
+        name: name.trim().to_string(),
+        email: email.to_string(),
+    })
+}
+
+pub fn parse_branch_list(stdout: &str) -> Vec<BranchInfo> {
+    stdout.lines().filter_map(parse_branch_line).collect()
+}
+
+fn parse_branch_line(line: &str) -> Option<BranchInfo> {
+    let fields: Vec<&str> = line.trim().split(FIELD_SEP).map(str::trim).collect();
+    let full_ref = *fields.first()?;
+    if full_ref.is_empty() {
+        return None;
+    }
+    let field = |idx: usize| fields.get(idx).copied().unwrap_or_default();
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    let (ahead, behind, upstream_gone) = parse_upstream_track(field(3));
+    Some(BranchInfo {
+        name: full_ref
+            .strip_prefix("refs/heads/")
+            .or_else(|| full_ref.strip_prefix("refs/remotes/"))
+            .unwrap_or(full_ref)
+            .to_string(),
+        full_ref: full_ref.to_string(),
+        is_remote: full_ref.starts_with("refs/remotes/"),
+        remote_name: non_empty(field(2)),
+        upstream: non_empty(field(1)),
+        ahead,
+        behind,
+        upstream_gone,
+        tip_hash: field(4).to_string(),
+        last_commit_unix: field(5).parse().unwrap_or_default(),
+    })
+}
+
+// `%(upstream:track,nobracket)` renders as "ahead 2, behind 1", "gone" or "".
+fn parse_upstream_track(track: &str) -> (usize, usize, bool) {
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(',').map(str::trim) {
+        if part == "gone" {
+            return (0, 0, true);
+        }
+        if let Some(count) = part.strip_prefix("ahead ") {
+            ahead = count.parse().unwrap_or_default();
+        } else if let Some(count) = part.strip_prefix("behind ") {
+            behind = count.parse().unwrap_or_default();
+        }
+    }
+    (ahead, behind, false)
+}
+
 fn parse_refs(decorations: &str) -> Vec<GitRef> {
     let cleaned = decorations.trim();
     if cleaned.is_empty() {
@@ -224,32 +906,196 @@ fn simplify_ref_name(raw: &str) -> String {
         .to_string()
 }
 
+// Log records for tests, in `log_format` field order with fixed author
+// details and an empty body.
+#[cfg(test)]
+pub(crate) fn test_record_fields(hash: &str, parents: &str, refs: &str, subject: &str) -> String {
+    [
+        hash,
+        &hash[..hash.len().min(4)],
+        parents,
+        "A",
+        "a@e",
+        "1",
+        "1",
+        "A",
+        "a@e",
+        "x +0000",
+        "x +0000",
+        refs,
+        subject,
+        "",
+    ]
+    .join(&FIELD_SEP.to_string())
+}
+
+#[cfg(test)]
+pub(crate) fn test_record(hash: &str, parents: &str, refs: &str, subject: &str) -> String {
+    format!(
+        "{}{RECORD_SEP}",
+        test_record_fields(hash, parents, refs, subject)
+    )
+}
+
+// c3 merges p1 and p2, which both fork from the root p0.
+#[cfg(test)]
+pub(crate) fn merge_fixture() -> String {
+    [
+        test_record("c3", "p1 p2", "", "merge"),
+        test_record("p1", "p0", "", "parent1"),
+        test_record("p2", "p0", "", "parent2"),
+        test_record("p0", "", "", "root"),
+    ]
+    .concat()
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
+    use crate::models::{GraphSegmentKind, SignatureStatus};
+
     use super::{
-        FIELD_SEP, GitRefKind, RECORD_SEP, build_graph_rows, parse_git_log_records, parse_ref_token,
+        ChainCollapse, FIELD_SEP, GitRefKind, GraphBuilder, LogRecordReader, RECORD_SEP,
+        build_graph_rows, color_group_for_key, log_format, merge_fixture, parse_branch_list,
+        parse_git_log_records, parse_ref_token, parse_trailers, test_record,
     };
 
     #[test]
     fn parses_one_record() {
-        let rec = format!(
-            "aaaaaaaa{}aaaaaaa{}bbbbbbbb{}Alice{}alice@example.com{}1700000000{}1700000001{}HEAD -> refs/heads/main, refs/remotes/origin/main, tag: refs/tags/v1.0{}Subject{}Body{}",
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            FIELD_SEP,
-            RECORD_SEP
-        );
+        let rec = [
+            "aaaaaaaa",
+            "aaaaaaa",
+            "bbbbbbbb",
+            "Alice",
+            "alice@example.com",
+            "1700000000",
+            "1700000001",
+            "Bob",
+            "bob@example.com",
+            "2023-11-14 23:13:20 +0530",
+            "2023-11-14 17:43:21 -0030",
+            "HEAD -> refs/heads/main, refs/remotes/origin/main, tag: refs/tags/v1.0",
+            "Subject",
+            "Body",
+        ]
+        .join(&FIELD_SEP.to_string())
+            + &RECORD_SEP.to_string();
         let parsed = parse_git_log_records(&rec).expect("parse records");
         assert_eq!(parsed.len(), 1);
         assert_eq!(parsed[0].hash, "aaaaaaaa");
         assert_eq!(parsed[0].parents, vec!["bbbbbbbb"]);
         assert_eq!(parsed[0].refs.len(), 3);
+        assert_eq!(parsed[0].committer_name, "Bob");
+        assert_eq!(parsed[0].committer_email, "bob@example.com");
+        assert_eq!(parsed[0].author_tz_offset_minutes, 330);
+        assert_eq!(parsed[0].committer_tz_offset_minutes, -30);
+        assert_eq!(parsed[0].body, "Body");
+    }
+
+    #[test]
+    fn parses_tagged_notes_and_signature_fields() {
+        let fields = [
+            "aaaaaaaa",
+            "aaaaaaa",
+            "",
+            "Alice",
+            "alice@example.com",
+            "1700000000",
+            "1700000000",
+            "Alice",
+            "alice@example.com",
+            "2023-11-14 23:13:20 +0000",
+            "2023-11-14 23:13:20 +0000",
+            "",
+            "Subject",
+            "Body",
+            "notes:ci: passed\n",
+            "sig:X",
+            "signer:Alice <alice@example.com>",
+            "key:ABCDEF0123456789",
+        ];
+        let format = log_format(true, true);
+        assert_eq!(format.matches("%x1f").count(), fields.len() - 1);
+        let rec = fields.join(&FIELD_SEP.to_string()) + &RECORD_SEP.to_string();
+        let parsed = parse_git_log_records(&rec).expect("parse records");
+        assert_eq!(parsed[0].body, "Body");
+        assert_eq!(parsed[0].notes, "ci: passed");
+        let signature = parsed[0].signature.as_ref().expect("signature fields");
+        assert_eq!(signature.status, SignatureStatus::Expired);
+        assert_eq!(signature.signer, "Alice <alice@example.com>");
+        assert_eq!(signature.key_id, "ABCDEF0123456789");
+
+        let unsigned = fields[..15].join(&FIELD_SEP.to_string());
+        let parsed = parse_git_log_records(&unsigned).expect("parse records");
+        assert_eq!(parsed[0].signature, None);
+    }
+
+    #[test]
+    fn parses_trailers_with_identities() {
+        let body = "Longer explanation.\n\nReviewed-by: Carol <carol@example.com>\nCo-authored-by: Dan Smith\n  <dan@example.com>\nSigned-off-by: Alice <alice@example.com>\nFixes: #42\n";
+        let trailers = parse_trailers(body);
+        assert_eq!(trailers.len(), 4);
+        assert!(trailers[1].is_co_author());
+        assert_eq!(
+            trailers[1].identity.as_ref().map(|id| id.email.as_str()),
+            Some("dan@example.com")
+        );
+        assert_eq!(
+            trailers[1].identity.as_ref().map(|id| id.name.as_str()),
+            Some("Dan Smith")
+        );
+        assert!(trailers[2].is_sign_off());
+        assert_eq!(trailers[3].key, "Fixes");
+        assert_eq!(trailers[3].identity, None);
+
+        assert!(parse_trailers("Just a body line.\nSigned-off-by: A <a@e>").is_empty());
+        assert!(parse_trailers("").is_empty());
+    }
+
+    #[test]
+    fn parses_branch_tracking_fields() {
+        let line = |fields: &[&str]| fields.join(&FIELD_SEP.to_string());
+        let stdout = [
+            line(&[
+                "refs/heads/main",
+                "refs/remotes/origin/main",
+                "origin",
+                "ahead 2, behind 1",
+                "abc123",
+                "1700000000",
+            ]),
+            line(&[
+                "refs/heads/old",
+                "refs/remotes/origin/old",
+                "origin",
+                "gone",
+                "def456",
+                "1600000000",
+            ]),
+            line(&[
+                "refs/remotes/origin/main",
+                "",
+                "",
+                "",
+                "abc000",
+                "1690000000",
+            ]),
+        ]
+        .join("\n");
+        let branches = parse_branch_list(&stdout);
+        assert_eq!(branches.len(), 3);
+        assert_eq!(branches[0].name, "main");
+        assert_eq!(
+            branches[0].upstream.as_deref(),
+            Some("refs/remotes/origin/main")
+        );
+        assert_eq!((branches[0].ahead, branches[0].behind), (2, 1));
+        assert_eq!(branches[0].last_commit_unix, 1_700_000_000);
+        assert!(branches[1].upstream_gone);
+        assert!(branches[2].is_remote);
+        assert_eq!(branches[2].upstream, None);
+        assert_eq!(branches[2].tip_hash, "abc000");
     }
 
     #[test]
@@ -269,12 +1115,7 @@ mod tests {
 
     #[test]
     fn assigns_lanes_for_merge() {
-        let rec = format!(
-            "c3{f}c3{f}p1 p2{f}A{f}a@e{f}10{f}10{f}{f}merge{f}{r}p1{f}p1{f}p0{f}A{f}a@e{f}9{f}9{f}{f}parent1{f}{r}p2{f}p2{f}p0{f}A{f}a@e{f}8{f}8{f}{f}parent2{f}{r}p0{f}p0{f}{f}A{f}a@e{f}7{f}7{f}{f}root{f}{r}",
-            f = FIELD_SEP,
-            r = RECORD_SEP
-        );
-        let raw = parse_git_log_records(&rec).expect("valid records");
+        let raw = parse_git_log_records(&merge_fixture()).expect("valid records");
         let rows = build_graph_rows(raw);
         assert_eq!(rows.len(), 4);
         assert_eq!(rows[0].hash, "c3");
@@ -282,4 +1123,170 @@ mod tests {
         assert_eq!(rows[0].edges.len(), 2);
         assert!(rows[0].edges.iter().any(|e| e.to_lane == 1));
     }
+
+    #[test]
+    fn reads_records_incrementally() {
+        let fixture = format!("{}\n", merge_fixture());
+        let mut reader = LogRecordReader::new(Cursor::new(fixture.into_bytes()));
+        let first = reader.next_record().expect("read").expect("first record");
+        assert_eq!(first.hash, "c3");
+        assert_eq!(first.parents, vec!["p1", "p2"]);
+        let rest = reader.collect::<Result<Vec<_>, _>>().expect("read rest");
+        assert_eq!(
+            rest.iter().map(|c| c.hash.as_str()).collect::<Vec<_>>(),
+            vec!["p1", "p2", "p0"]
+        );
+    }
+
+    #[test]
+    fn builder_carries_lanes_across_pages() {
+        let raw = parse_git_log_records(&merge_fixture()).expect("valid records");
+        let expected = build_graph_rows(raw.clone());
+
+        let mut builder = GraphBuilder::new();
+        let mut paged = Vec::new();
+        for page in raw.chunks(2) {
+            paged.extend(page.iter().cloned().map(|c| builder.push(c)));
+        }
+        assert_eq!(paged, expected);
+        assert_eq!(builder.rows_built(), 4);
+        assert_eq!(builder.active_lane_count(), 0);
+    }
+
+    #[test]
+    fn emits_row_segments_for_merge_and_fork() {
+        use GraphSegmentKind::{End, Fork, Incoming, Merge, Outgoing, PassThrough, Start};
+
+        let raw = parse_git_log_records(&merge_fixture()).expect("valid records");
+        let rows = build_graph_rows(raw);
+        let segments = rows
+            .iter()
+            .map(|row| {
+                row.segments
+                    .iter()
+                    .map(|s| (s.kind, s.from_lane, s.to_lane))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            vec![
+                vec![(Start, 0, 0), (Outgoing, 0, 0), (Merge, 0, 1)],
+                vec![(Incoming, 0, 0), (PassThrough, 1, 1), (Outgoing, 0, 0)],
+                vec![(PassThrough, 0, 0), (Incoming, 1, 1), (Outgoing, 1, 1)],
+                vec![(Incoming, 0, 0), (Fork, 1, 0), (End, 0, 0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn color_groups_follow_branches_across_pages_and_refreshes() {
+        let history = [
+            test_record("m2", "m1 f2", "", "Merge branch 'feature'"),
+            test_record("f2", "f1", "refs/heads/feature", "feature 2"),
+            test_record("m1", "m0", "", "main 1"),
+            test_record("f1", "m0", "", "feature 1"),
+            test_record("m0", "", "", "root"),
+        ]
+        .concat();
+        let raw = parse_git_log_records(&format!(
+            "{}{history}",
+            test_record("m3", "m2", "HEAD -> refs/heads/main", "main 3")
+        ))
+        .expect("valid records");
+        let rows = build_graph_rows(raw.clone());
+        let group = |hash: &str| {
+            rows.iter()
+                .find(|row| row.hash == hash)
+                .map(|row| row.color_group)
+                .expect("row exists")
+        };
+        assert_eq!(group("m3"), group("m2"));
+        assert_eq!(group("m2"), group("m1"));
+        assert_eq!(group("m1"), group("m0"));
+        assert_eq!(group("f2"), group("f1"));
+        assert_ne!(group("m3"), group("f2"));
+        let merge = rows[1]
+            .segments
+            .iter()
+            .find(|s| s.kind == GraphSegmentKind::Merge)
+            .expect("merge segment");
+        assert_eq!(merge.color_group, group("f2"));
+
+        let mut builder = GraphBuilder::new();
+        let mut paged = Vec::new();
+        for chunk in raw.chunks(2) {
+            paged.extend(chunk.iter().cloned().map(|commit| builder.push(commit)));
+        }
+        assert_eq!(paged, rows);
+
+        let refreshed = build_graph_rows(
+            parse_git_log_records(&format!(
+                "{}{}{history}",
+                test_record("m4", "m3", "HEAD -> refs/heads/main", "main 4"),
+                test_record("m3", "m2", "", "main 3")
+            ))
+            .expect("valid records"),
+        );
+        assert_eq!(refreshed[0].color_group, group("m3"));
+        assert_eq!(refreshed[3].color_group, group("f2"));
+
+        // A tip only reached through a merge with a custom subject still
+        // takes its branch color over the merge's weak hash claim.
+        let custom = build_graph_rows(
+            parse_git_log_records(&history.replace("Merge branch 'feature'", "Merge topic work"))
+                .expect("valid records"),
+        );
+        let branch_group = color_group_for_key("branch", "feature");
+        assert_eq!(custom[1].hash, "f2");
+        assert_eq!(custom[1].color_group, branch_group);
+        assert_eq!(custom[3].hash, "f1");
+        assert_eq!(custom[3].color_group, branch_group);
+    }
+
+    #[test]
+    fn chain_collapse_is_bounded_by_its_lookahead() {
+        let commit = |hash: &str, parent: &str, refs: &str| test_record(hash, parent, refs, hash);
+        let chain = parse_git_log_records(
+            &[
+                commit("c5", "c4", "HEAD -> refs/heads/main"),
+                commit("c4", "c3", ""),
+                commit("c3", "c2", ""),
+                commit("c2", "c1", ""),
+                commit("c1", "c0", ""),
+                commit("c0", "", ""),
+            ]
+            .concat(),
+        )
+        .expect("valid records");
+        let layout = |collapse: ChainCollapse| {
+            let mut builder = GraphBuilder::new().with_chain_collapse(collapse);
+            let mut rows = Vec::new();
+            for commit in chain.clone() {
+                rows.extend(builder.accept(commit));
+            }
+            rows.extend(builder.finish());
+            rows.into_iter()
+                .map(|row| (row.hash, row.parents.join(" "), row.collapsed_commits))
+                .collect::<Vec<_>>()
+        };
+        let row = |hash: &str, parents: &str, collapsed| {
+            (hash.to_string(), parents.to_string(), collapsed)
+        };
+
+        assert_eq!(
+            layout(ChainCollapse::new(usize::MAX, 0)),
+            vec![row("c5", "c0", 4), row("c0", "", 0)]
+        );
+        // c5 stops waiting after two more records and keeps c2 visible.
+        assert_eq!(
+            layout(ChainCollapse::new(2, 0)),
+            vec![row("c5", "c2", 2), row("c2", "c0", 1), row("c0", "", 0)]
+        );
+        // Skipped records still count as children.
+        assert_eq!(
+            layout(ChainCollapse::new(usize::MAX, 1)),
+            vec![row("c0", "", 0)]
+        );
+    }
 }
@@ -0,0 +1,127 @@
+use crate::error::{GitLgError, Result};
+use crate::models::{MergeConflict, MergePreview};
+
+pub fn merge_tree_args(ours: &str, theirs: &str) -> Vec<String> {
+    vec![
+        "merge-tree".to_string(),
+        "--write-tree".to_string(),
+        "-z".to_string(),
+        "--name-only".to_string(),
+        "--end-of-options".to_string(),
+        ours.to_string(),
+        theirs.to_string(),
+    ]
+}
+
+// Parses `git merge-tree --write-tree -z --name-only`: the tree id, the
+// conflicted paths up to an empty field, then messages as
+// `<path count> <paths...> <type> <message>`. Types other than
+// `CONFLICT (...)`, such as `Auto-merging`, are informational.
+pub fn parse_merge_tree(
+    stdout: &str,
+    ours: &str,
+    theirs: &str,
+    clean: bool,
+) -> Result<MergePreview> {
+    let invalid = || GitLgError::Parse(format!("invalid merge-tree output: {stdout:?}"));
+    let mut fields = stdout.split('\0');
+    let tree = fields
+        .next()
+        .map(str::trim)
+        .filter(|tree| !tree.is_empty())
+        .ok_or_else(invalid)?
+        .to_string();
+    let mut conflicts = fields
+        .by_ref()
+        .take_while(|path| !path.is_empty())
+        .map(|path| MergeConflict {
+            path: path.to_string(),
+            kinds: Vec::new(),
+            messages: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    while let Some(count) = fields.next().filter(|count| !count.is_empty()) {
+        let count = count.parse::<usize>().map_err(|_| invalid())?;
+        let paths = fields.by_ref().take(count).collect::<Vec<_>>();
+        let kind = fields.next().ok_or_else(invalid)?;
+        let message = fields.next().ok_or_else(invalid)?.trim_end().to_string();
+        let Some(kind) = kind
+            .strip_prefix("CONFLICT (")
+            .and_then(|kind| kind.strip_suffix(')'))
+        else {
+            continue;
+        };
+        // Attach to every listed path that is conflicted; a message about
+        // paths that left nothing in the index gets an entry of its own.
+        let mut attached = false;
+        for conflict in conflicts
+            .iter_mut()
+            .filter(|conflict| paths.contains(&conflict.path.as_str()))
+        {
+            push_kind(conflict, kind, &message);
+            attached = true;
+        }
+        if !attached && let Some(path) = paths.first() {
+            let mut conflict = MergeConflict {
+                path: path.to_string(),
+                kinds: Vec::new(),
+                messages: Vec::new(),
+            };
+            push_kind(&mut conflict, kind, &message);
+            conflicts.push(conflict);
+        }
+    }
+
+    Ok(MergePreview {
+        ours: ours.to_string(),
+        theirs: theirs.to_string(),
+        tree,
+        clean: clean && conflicts.is_empty(),
+        conflicts,
+    })
+}
+
+fn push_kind(conflict: &mut MergeConflict, kind: &str, message: &str) {
+    if !conflict.kinds.iter().any(|existing| existing == kind) {
+        conflict.kinds.push(kind.to_string());
+    }
+    conflict.messages.push(message.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_merge_tree;
+
+    #[test]
+    fn groups_conflict_messages_by_path() {
+        let stdout = "ea59\0a\0r2\0\0\
+            1\0a\0Auto-merging\0Auto-merging a\n\0\
+            1\0a\0CONFLICT (contents)\0CONFLICT (content): Merge conflict in a\n\0\
+            2\0r2\0r\0CONFLICT (rename/delete)\0CONFLICT (rename/delete): r renamed to r2 in other, but deleted in master.\n\0";
+        let preview = parse_merge_tree(stdout, "ours", "theirs", false).expect("parse");
+        assert!(!preview.clean);
+        assert_eq!(preview.tree, "ea59");
+        assert_eq!(preview.conflicts.len(), 2);
+        assert_eq!(preview.conflicts[0].path, "a");
+        assert_eq!(preview.conflicts[0].kinds, vec!["contents"]);
+        assert_eq!(
+            preview.conflicts[0].messages,
+            vec!["CONFLICT (content): Merge conflict in a"]
+        );
+        assert_eq!(preview.conflicts[1].kinds, vec!["rename/delete"]);
+        assert_eq!(
+            preview.summary(),
+            "2 conflicts: a (contents), r2 (rename/delete)"
+        );
+    }
+
+    #[test]
+    fn clean_merge_has_only_a_tree() {
+        let preview = parse_merge_tree("04a1\0", "ours", "theirs", true).expect("parse");
+        assert!(preview.clean);
+        assert!(preview.conflicts.is_empty());
+        assert_eq!(preview.summary(), "clean");
+        assert!(parse_merge_tree("", "ours", "theirs", true).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
@@ -25,6 +26,18 @@ pub struct BranchInfo {
     pub full_ref: String,
     pub is_remote: bool,
     pub remote_name: Option<String>,
+    #[serde(default)]
+    pub upstream: Option<String>,
+    #[serde(default)]
+    pub ahead: usize,
+    #[serde(default)]
+    pub behind: usize,
+    #[serde(default)]
+    pub upstream_gone: bool,
+    #[serde(default)]
+    pub tip_hash: String,
+    #[serde(default)]
+    pub last_commit_unix: i64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -33,6 +46,108 @@ pub struct GraphEdge {
     pub parent_hash: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphSegmentKind {
+    PassThrough,
+    Shift,
+    Incoming,
+    Outgoing,
+    Fork,
+    Merge,
+    Start,
+    End,
+}
+
+// `from_lane` is the lane on the row's top boundary (or the commit lane for
+// segments leaving the node); `to_lane` is the lane on the bottom boundary
+// (or the commit lane for segments entering the node).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSegment {
+    pub kind: GraphSegmentKind,
+    pub from_lane: usize,
+    pub to_lane: usize,
+    #[serde(default)]
+    pub color_group: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrailerIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitTrailer {
+    pub key: String,
+    pub value: String,
+    pub identity: Option<TrailerIdentity>,
+}
+
+impl CommitTrailer {
+    pub fn key_is(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+
+    pub fn is_co_author(&self) -> bool {
+        self.key_is("Co-authored-by")
+    }
+
+    pub fn is_sign_off(&self) -> bool {
+        self.key_is("Signed-off-by")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignatureStatus {
+    Good,
+    Bad,
+    Unknown,
+    Expired,
+    #[default]
+    None,
+}
+
+impl SignatureStatus {
+    // Maps git's `%G?` codes; revoked keys count as bad and unverifiable
+    // signatures (missing key, unknown validity) as unknown.
+    pub fn from_git_code(code: &str) -> Self {
+        match code {
+            "G" => SignatureStatus::Good,
+            "B" | "R" => SignatureStatus::Bad,
+            "U" | "E" => SignatureStatus::Unknown,
+            "X" | "Y" => SignatureStatus::Expired,
+            _ => SignatureStatus::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SignatureStatus::Good => "good",
+            SignatureStatus::Bad => "bad",
+            SignatureStatus::Unknown => "unknown",
+            SignatureStatus::Expired => "expired",
+            SignatureStatus::None => "none",
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        self != SignatureStatus::None
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSignature {
+    pub status: SignatureStatus,
+    pub signer: String,
+    pub key_id: String,
+}
+
+// Hash of the synthetic row for staged and unstaged changes on top of HEAD;
+// git itself uses the null id for "not committed yet" lines in blame.
+pub const UNCOMMITTED_HASH: &str = "0000000000000000000000000000000000000000";
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GraphRow {
     pub hash: String,
@@ -42,12 +157,38 @@ pub struct GraphRow {
     pub author_email: String,
     pub authored_unix: i64,
     pub committed_unix: i64,
+    #[serde(default)]
+    pub committer_name: String,
+    #[serde(default)]
+    pub committer_email: String,
+    #[serde(default)]
+    pub author_tz_offset_minutes: i32,
+    #[serde(default)]
+    pub committer_tz_offset_minutes: i32,
     pub subject: String,
     pub body: String,
+    #[serde(default)]
+    pub trailers: Vec<CommitTrailer>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub signature: Option<CommitSignature>,
     pub refs: Vec<GitRef>,
     pub lane: usize,
     pub active_lane_count: usize,
+    #[serde(default)]
+    pub color_group: u32,
+    #[serde(default)]
+    pub collapsed_commits: usize,
     pub edges: Vec<GraphEdge>,
+    #[serde(default)]
+    pub segments: Vec<GraphSegment>,
+}
+
+impl GraphRow {
+    pub fn is_uncommitted(&self) -> bool {
+        self.hash == UNCOMMITTED_HASH
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -57,6 +198,41 @@ pub struct GraphData {
     pub query: GraphQuery,
     pub commits: Vec<GraphRow>,
     pub branches: Vec<BranchInfo>,
+    #[serde(default)]
+    pub operation: Option<RepoOperation>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphMode {
+    #[default]
+    Full,
+    FirstParent,
+    DecoratedOnly,
+    CollapseLinear,
+}
+
+impl GraphMode {
+    pub const ALL: [GraphMode; 4] = [
+        GraphMode::Full,
+        GraphMode::FirstParent,
+        GraphMode::DecoratedOnly,
+        GraphMode::CollapseLinear,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GraphMode::Full => "full",
+            GraphMode::FirstParent => "first-parent",
+            GraphMode::DecoratedOnly => "decorated-only",
+            GraphMode::CollapseLinear => "collapse-linear",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -65,6 +241,16 @@ pub struct GraphQuery {
     pub skip: usize,
     pub all_refs: bool,
     pub include_stash_ref: bool,
+    #[serde(default)]
+    pub mode: GraphMode,
+    #[serde(default)]
+    pub notes_refs: Vec<String>,
+    #[serde(default)]
+    pub verify_signatures: bool,
+    // Prepends an `UNCOMMITTED_HASH` row on HEAD's lane to the first page
+    // when the working tree has changes.
+    #[serde(default)]
+    pub include_uncommitted: bool,
     pub additional_args: Vec<String>,
 }
 
@@ -75,12 +261,17 @@ impl Default for GraphQuery {
             skip: 0,
             all_refs: true,
             include_stash_ref: true,
+            mode: GraphMode::Full,
+            notes_refs: Vec::new(),
+            verify_signatures: false,
+            include_uncommitted: false,
             additional_args: Vec::new(),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CommitSearchQuery {
     pub text: String,
     pub case_sensitive: bool,
@@ -92,6 +283,8 @@ pub struct CommitSearchQuery {
     pub include_author: bool,
     pub include_email: bool,
     pub include_refs: bool,
+    pub include_trailers: bool,
+    pub include_notes: bool,
 }
 
 impl Default for CommitSearchQuery {
@@ -107,10 +300,14 @@ impl Default for CommitSearchQuery {
             include_author: true,
             include_email: true,
             include_refs: true,
+            include_trailers: true,
+            include_notes: true,
         }
     }
 }
 
+// `original_path`/`original_line` locate the line in `commit_hash`, which
+// differ from `file`/`line` when it was moved or copied (`-M`/`-C`).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlameInfo {
     pub file: PathBuf,
@@ -120,6 +317,50 @@ pub struct BlameInfo {
     pub author_email: String,
     pub author_time_unix: i64,
     pub summary: String,
+    #[serde(default)]
+    pub original_path: String,
+    #[serde(default)]
+    pub original_line: usize,
+    #[serde(default)]
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameCommit {
+    pub hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub author_time_unix: i64,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub committer_time_unix: i64,
+    pub summary: String,
+    // Parent commit that had the line before, absent for boundary commits.
+    pub previous: Option<String>,
+    pub boundary: bool,
+    pub line_count: usize,
+}
+
+// `ignore_revs_file` is passed in addition to `blame.ignoreRevsFile`, which git
+// reads from config on its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlameOptions {
+    #[serde(default)]
+    pub ignore_revs: Vec<String>,
+    #[serde(default)]
+    pub ignore_revs_file: Option<PathBuf>,
+    #[serde(default)]
+    pub detect_moves: bool,
+    #[serde(default)]
+    pub detect_copies: bool,
+}
+
+// `commits` are listed in order of first appearance in the file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileBlame {
+    pub file: PathBuf,
+    pub lines: Vec<BlameInfo>,
+    pub commits: Vec<BlameCommit>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -128,3 +369,612 @@ pub struct FileChange {
     pub added: Option<u32>,
     pub removed: Option<u32>,
 }
+
+// `path` is the file's name in that commit; `previous_path` is only set on the
+// commit that renamed or copied it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHistoryEntry {
+    pub path: String,
+    pub previous_path: Option<String>,
+    pub row: GraphRow,
+}
+
+// One `@@` hunk of a unified diff. `lines` keep their ` `, `+` or `-` prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<String>,
+}
+
+// A commit that changed the tracked line range, with the hunks that touched it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineHistoryEntry {
+    pub path: String,
+    pub previous_path: Option<String>,
+    pub row: GraphRow,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePatch {
+    pub path: String,
+    pub patch: String,
+}
+
+// `files` is the direct tree diff from `left` to `right`; `patches` is only
+// filled when a caller asks for them, since they can be large.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevisionComparison {
+    pub left: String,
+    pub right: String,
+    pub left_hash: String,
+    pub right_hash: String,
+    pub merge_base: Option<String>,
+    pub only_in_left: Vec<GraphRow>,
+    pub only_in_right: Vec<GraphRow>,
+    pub files: Vec<FileChange>,
+    #[serde(default)]
+    pub patches: Vec<FilePatch>,
+}
+
+// One side (index or worktree) of a porcelain status `XY` code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileStatus {
+    #[default]
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    Unmerged,
+}
+
+impl FileStatus {
+    pub fn from_git_code(code: char) -> Self {
+        match code {
+            'M' => FileStatus::Modified,
+            'T' => FileStatus::TypeChanged,
+            'A' => FileStatus::Added,
+            'D' => FileStatus::Deleted,
+            'R' => FileStatus::Renamed,
+            'C' => FileStatus::Copied,
+            'U' => FileStatus::Unmerged,
+            _ => FileStatus::Unmodified,
+        }
+    }
+
+    pub fn code(self) -> char {
+        match self {
+            FileStatus::Unmodified => ' ',
+            FileStatus::Modified => 'M',
+            FileStatus::TypeChanged => 'T',
+            FileStatus::Added => 'A',
+            FileStatus::Deleted => 'D',
+            FileStatus::Renamed => 'R',
+            FileStatus::Copied => 'C',
+            FileStatus::Unmerged => 'U',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatusEntryKind {
+    Changed,
+    Renamed,
+    Conflicted,
+    Untracked,
+    Ignored,
+}
+
+// `original_path` is set for renames and copies. For conflicts, `index` and
+// `worktree` hold "ours" and "theirs" of the unmerged `XY` code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusEntry {
+    pub path: String,
+    pub original_path: Option<String>,
+    pub kind: StatusEntryKind,
+    pub index: FileStatus,
+    pub worktree: FileStatus,
+}
+
+impl StatusEntry {
+    pub fn is_staged(&self) -> bool {
+        matches!(
+            self.kind,
+            StatusEntryKind::Changed | StatusEntryKind::Renamed
+        ) && self.index != FileStatus::Unmodified
+    }
+
+    pub fn is_unstaged(&self) -> bool {
+        matches!(
+            self.kind,
+            StatusEntryKind::Changed | StatusEntryKind::Renamed
+        ) && self.worktree != FileStatus::Unmodified
+    }
+
+    pub fn is_conflicted(&self) -> bool {
+        self.kind == StatusEntryKind::Conflicted
+    }
+
+    pub fn is_untracked(&self) -> bool {
+        self.kind == StatusEntryKind::Untracked
+    }
+
+    // The `git status --short` form: `XY path` or `XY old -> new`.
+    pub fn short_line(&self) -> String {
+        let code = match self.kind {
+            StatusEntryKind::Untracked => "??".to_string(),
+            StatusEntryKind::Ignored => "!!".to_string(),
+            _ => format!("{}{}", self.index.code(), self.worktree.code()),
+        };
+        match &self.original_path {
+            Some(original) => format!("{code} {original} -> {}", self.path),
+            None => format!("{code} {}", self.path),
+        }
+    }
+}
+
+// `oid` is `None` before the first commit and `head` is `None` when detached.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusBranch {
+    pub oid: Option<String>,
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkingTreeStatus {
+    pub branch: StatusBranch,
+    pub entries: Vec<StatusEntry>,
+}
+
+impl WorkingTreeStatus {
+    pub fn is_clean(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| entry.kind == StatusEntryKind::Ignored)
+    }
+
+    pub fn staged(&self) -> impl Iterator<Item = &StatusEntry> {
+        self.entries.iter().filter(|entry| entry.is_staged())
+    }
+
+    pub fn unstaged(&self) -> impl Iterator<Item = &StatusEntry> {
+        self.entries.iter().filter(|entry| entry.is_unstaged())
+    }
+
+    pub fn untracked(&self) -> impl Iterator<Item = &StatusEntry> {
+        self.entries.iter().filter(|entry| entry.is_untracked())
+    }
+
+    pub fn conflicted(&self) -> impl Iterator<Item = &StatusEntry> {
+        self.entries.iter().filter(|entry| entry.is_conflicted())
+    }
+
+    // e.g. `2 staged, 1 unstaged, 3 untracked` or `clean`.
+    pub fn summary(&self) -> String {
+        let parts = [
+            (self.staged().count(), "staged"),
+            (self.unstaged().count(), "unstaged"),
+            (self.untracked().count(), "untracked"),
+            (self.conflicted().count(), "conflicted"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{count} {label}"))
+        .collect::<Vec<_>>();
+        if parts.is_empty() {
+            "clean".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+// Which working-tree diff a hunk comes from: index vs worktree, or HEAD vs index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorktreeDiffSide {
+    #[default]
+    Unstaged,
+    Staged,
+}
+
+impl WorktreeDiffSide {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Unstaged => "unstaged",
+            Self::Staged => "staged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HunkAction {
+    Stage,
+    Unstage,
+    Discard,
+}
+
+impl HunkAction {
+    // The diff the selected hunk is taken from.
+    pub fn side(self) -> WorktreeDiffSide {
+        match self {
+            Self::Stage | Self::Discard => WorktreeDiffSide::Unstaged,
+            Self::Unstage => WorktreeDiffSide::Staged,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Stage => "stage",
+            Self::Unstage => "unstage",
+            Self::Discard => "discard",
+        }
+    }
+}
+
+// `hunk` indexes the `@@` hunks of the file's diff for the action's side;
+// `lines` optionally narrows it to an inclusive range of that hunk's body lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HunkSelection {
+    pub hunk: usize,
+    #[serde(default)]
+    pub lines: Option<(usize, usize)>,
+}
+
+// Input for `GitLgService::commit`. Without `stage_all` only what is already
+// staged is committed. An empty `message` is only allowed with `amend` (keep
+// the old message) or `fixup` (git writes `fixup! <subject>`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitOptions {
+    pub message: String,
+    #[serde(default)]
+    pub stage_all: bool,
+    #[serde(default)]
+    pub amend: bool,
+    #[serde(default)]
+    pub fixup: Option<String>,
+    #[serde(default)]
+    pub signoff: bool,
+    // `Name <email>`
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub allow_empty: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RebaseTodoAction {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl RebaseTodoAction {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Pick => "pick",
+            Self::Reword => "reword",
+            Self::Edit => "edit",
+            Self::Squash => "squash",
+            Self::Fixup => "fixup",
+            Self::Drop => "drop",
+        }
+    }
+}
+
+// `message` is the new message for `Reword`, and replaces the combined
+// message for `Squash` (without it git joins both messages).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RebaseTodoItem {
+    pub action: RebaseTodoAction,
+    pub hash: String,
+    pub subject: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+// Items run oldest first. `onto` is the parent of the oldest commit, or `None`
+// when the range starts at a root commit; `head` is the newest commit, which
+// must still be checked out when the plan runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RebasePlan {
+    pub onto: Option<String>,
+    pub head: String,
+    pub items: Vec<RebaseTodoItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RebaseState {
+    Completed,
+    // Stopped on an `edit` item or a failed step; continue or abort.
+    Stopped,
+    // Stopped on conflicts; resolve and stage them, then continue, or abort.
+    Conflicted,
+}
+
+// `done` and `total` count todo items, so they line up with the plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RebaseStatus {
+    pub state: RebaseState,
+    pub head: String,
+    pub stopped_at: Option<String>,
+    pub done: usize,
+    pub total: usize,
+    pub conflicted_files: Vec<String>,
+}
+
+// One conflicted path of a merge preview. `kinds` are git's conflict types,
+// e.g. `contents`, `rename/delete` or `modify/delete`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub path: String,
+    pub kinds: Vec<String>,
+    pub messages: Vec<String>,
+}
+
+// Outcome of merging `theirs` into `ours` without touching the working tree.
+// `tree` is the merged tree, with conflict markers where `clean` is false.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergePreview {
+    pub ours: String,
+    pub theirs: String,
+    pub tree: String,
+    pub clean: bool,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergePreview {
+    // e.g. `clean` or `2 conflicts: a.txt (contents), b.txt (rename/delete)`.
+    pub fn summary(&self) -> String {
+        if self.clean {
+            return "clean".to_string();
+        }
+        let files = self
+            .conflicts
+            .iter()
+            .map(|conflict| format!("{} ({})", conflict.path, conflict.kinds.join(", ")))
+            .collect::<Vec<_>>();
+        format!(
+            "{} conflict{}: {}",
+            files.len(),
+            if files.len() == 1 { "" } else { "s" },
+            files.join(", ")
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RepoOperationKind {
+    Merge,
+    Rebase,
+    // `git am`, which shares `.git/rebase-apply` with the apply backend.
+    ApplyMailbox,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl RepoOperationKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+            Self::ApplyMailbox => "am",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+            Self::Bisect => "bisect",
+        }
+    }
+
+    pub fn supports(self, control: RepoOperationControl) -> bool {
+        !matches!(
+            (self, control),
+            (Self::Bisect, RepoOperationControl::Continue)
+                | (Self::Merge, RepoOperationControl::Skip)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RepoOperationControl {
+    Continue,
+    Abort,
+    Skip,
+}
+
+impl RepoOperationControl {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Continue => "continue",
+            Self::Abort => "abort",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+// An operation git left in progress, as recorded in the git directory.
+// `current` is the commit being applied (or merged); `step` and `total` are
+// only known for rebases and `git am`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoOperation {
+    pub kind: RepoOperationKind,
+    pub head_name: Option<String>,
+    pub onto: Option<String>,
+    pub current: Option<String>,
+    pub step: Option<usize>,
+    pub total: Option<usize>,
+}
+
+impl RepoOperation {
+    // e.g. `rebase 2/5 of main onto 1a2b3c4` or `merge of 5d6e7f8`.
+    pub fn summary(&self) -> String {
+        let mut summary = self.kind.label().to_string();
+        if let (Some(step), Some(total)) = (self.step, self.total) {
+            summary.push_str(&format!(" {step}/{total}"));
+        }
+        if let Some(head_name) = &self.head_name {
+            summary.push_str(&format!(
+                " of {}",
+                head_name.strip_prefix("refs/heads/").unwrap_or(head_name)
+            ));
+        } else if let Some(current) = &self.current {
+            summary.push_str(&format!(" of {}", short_oid(current)));
+        }
+        if let Some(onto) = &self.onto {
+            summary.push_str(&format!(" onto {}", short_oid(onto)));
+        }
+        summary
+    }
+}
+
+fn short_oid(oid: &str) -> &str {
+    oid.get(..7).unwrap_or(oid)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BisectMark {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl BisectMark {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Bad => "bad",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+// Where a bisect stands. `candidates` are the commits that may still be the
+// first bad one (the bad commit included, skipped ones too), newest first;
+// `first_bad` is set once only one is left.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BisectStatus {
+    pub current: Option<String>,
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    pub skipped: Vec<String>,
+    pub candidates: Vec<String>,
+    pub steps_left: usize,
+    pub first_bad: Option<String>,
+}
+
+impl BisectStatus {
+    pub fn mark_of(&self, hash: &str) -> Option<BisectMark> {
+        if self.bad.as_deref() == Some(hash) {
+            Some(BisectMark::Bad)
+        } else if self.good.iter().any(|good| good == hash) {
+            Some(BisectMark::Good)
+        } else if self.skipped.iter().any(|skipped| skipped == hash) {
+            Some(BisectMark::Skip)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_candidate(&self, hash: &str) -> bool {
+        self.candidates.iter().any(|candidate| candidate == hash)
+    }
+
+    // e.g. `12 candidates, roughly 3 steps left` or `first bad: 1a2b3c4`.
+    pub fn summary(&self) -> String {
+        if let Some(first_bad) = &self.first_bad {
+            return format!("first bad: {}", short_oid(first_bad));
+        }
+        format!(
+            "{} candidate{}, roughly {} step{} left",
+            self.candidates.len(),
+            if self.candidates.len() == 1 { "" } else { "s" },
+            self.steps_left,
+            if self.steps_left == 1 { "" } else { "s" }
+        )
+    }
+}
+
+// Local branches and tags and where HEAD points. `head` is the symbolic
+// target (e.g. `refs/heads/main`), `None` when detached. Logged operations
+// keep only the refs they changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefSnapshot {
+    pub head: Option<String>,
+    pub head_oid: Option<String>,
+    pub refs: BTreeMap<String, String>,
+}
+
+// One executed action with the refs before and after it. Records are keyed
+// by the absolute git dir, so linked worktrees keep separate histories.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationRecord {
+    pub id: u64,
+    pub git_dir: PathBuf,
+    pub action_id: String,
+    pub command_line: String,
+    pub executed_at_unix: i64,
+    pub succeeded: bool,
+    pub before: RefSnapshot,
+    pub after: RefSnapshot,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+// `from` is the current value, `to` the one restored; `None` means absent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefUpdate {
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+// What undoing `operation` changes. `checkout` is the branch ref or commit
+// HEAD goes back to when the operation moved it; `affects_worktree` is set
+// when HEAD or the checked-out branch moves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UndoPlan {
+    pub operation: OperationRecord,
+    pub ref_updates: Vec<RefUpdate>,
+    pub checkout: Option<String>,
+    pub affects_worktree: bool,
+}
+
+impl UndoPlan {
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "undo {} ({}): {} ref{}",
+            self.operation.action_id,
+            self.operation.command_line,
+            self.ref_updates.len(),
+            if self.ref_updates.len() == 1 { "" } else { "s" }
+        );
+        if let Some(checkout) = &self.checkout {
+            summary.push_str(&format!(
+                ", checkout {}",
+                checkout.strip_prefix("refs/heads/").unwrap_or(checkout)
+            ));
+        }
+        summary
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{GitLgError, Result};
+use crate::models::{OperationRecord, RefSnapshot, RefUpdate, UndoPlan};
+
+const OPERATION_LOG_FILENAME: &str = "operations.jsonl";
+// Older records are dropped once the log grows past twice this.
+const OPERATION_LOG_LIMIT: usize = 200;
+
+// Executed actions with the refs they changed, kept as JSON lines next to
+// `state.json` and shared by every repository. Records and undo markers are
+// appended; the file is only rewritten when it is compacted.
+#[derive(Debug, Clone)]
+pub struct OperationLog {
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "kebab-case")]
+enum LogEntry {
+    Operation(Box<OperationRecord>),
+    Undone { id: u64 },
+}
+
+impl OperationLog {
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn next_to_state(state_path: &Path) -> Self {
+        Self::at(state_path.with_file_name(OPERATION_LOG_FILENAME))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn load(&self) -> Result<Vec<OperationRecord>> {
+        let mut records = Vec::new();
+        for entry in self.entries()? {
+            match entry {
+                LogEntry::Operation(record) => records.push(*record),
+                LogEntry::Undone { id } => {
+                    if let Some(record) = records.iter_mut().find(|record| record.id == id) {
+                        record.undone = true;
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    // Stores `record` under the next id and returns that id. Only HEAD and
+    // the refs that differ between the snapshots are kept.
+    pub fn append(&self, mut record: OperationRecord) -> Result<u64> {
+        let entries = self.entries()?;
+        let operations = entries
+            .iter()
+            .filter(|entry| matches!(entry, LogEntry::Operation(_)))
+            .count();
+        if operations >= 2 * OPERATION_LOG_LIMIT {
+            self.compact()?;
+        }
+        record.id = entries
+            .iter()
+            .rev()
+            .find_map(|entry| match entry {
+                LogEntry::Operation(record) => Some(record.id + 1),
+                LogEntry::Undone { .. } => None,
+            })
+            .unwrap_or(1);
+        let (before, after) = (&mut record.before, &mut record.after);
+        let changed = before
+            .refs
+            .keys()
+            .chain(after.refs.keys())
+            .filter(|name| before.refs.get(*name) != after.refs.get(*name))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        before.refs.retain(|name, _| changed.contains(name));
+        after.refs.retain(|name, _| changed.contains(name));
+        let id = record.id;
+        self.write_entry(&LogEntry::Operation(Box::new(record)))?;
+        Ok(id)
+    }
+
+    pub fn mark_undone(&self, id: u64) -> Result<()> {
+        if !self.load()?.iter().any(|record| record.id == id) {
+            return Err(GitLgError::State(format!("no operation with id {id}")));
+        }
+        self.write_entry(&LogEntry::Undone { id })
+    }
+
+    // The latest operation in `git_dir` that moved a ref and is not undone.
+    pub fn last_undoable(&self, git_dir: &Path) -> Result<Option<OperationRecord>> {
+        Ok(self.load()?.into_iter().rev().find(|record| {
+            record.git_dir == git_dir && !record.undone && record.before != record.after
+        }))
+    }
+
+    fn entries(&self) -> Result<Vec<LogEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let text = fs::read_to_string(&self.path)
+            .map_err(|source| GitLgError::io("reading operation log", source))?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|e| GitLgError::State(format!("invalid operation log json: {}", e)))
+            })
+            .collect()
+    }
+
+    fn write_entry(&self, entry: &LogEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|source| GitLgError::io("creating state directory", source))?;
+        }
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| GitLgError::State(format!("serialize operation log failed: {}", e)))?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|source| GitLgError::io("opening operation log", source))?;
+        file.write_all(line.as_bytes())
+            .map_err(|source| GitLgError::io("writing operation log", source))
+    }
+
+    // Keeps the latest `OPERATION_LOG_LIMIT` records, undo markers folded in.
+    fn compact(&self) -> Result<()> {
+        let mut records = self.load()?;
+        let overflow = records.len().saturating_sub(OPERATION_LOG_LIMIT);
+        records.drain(..overflow);
+        let mut text = String::new();
+        for record in records {
+            let line = serde_json::to_string(&LogEntry::Operation(Box::new(record)))
+                .map_err(|e| GitLgError::State(format!("serialize operation log failed: {}", e)))?;
+            text.push_str(&line);
+            text.push('\n');
+        }
+        fs::write(&self.path, text)
+            .map_err(|source| GitLgError::io("writing operation log", source))
+    }
+}
+
+// Only local branches and tags: rewinding remote-tracking refs does not
+// undo anything on the remote, and rewriting `refs/stash` adds reflog
+// entries instead of restoring the stash list.
+pub fn ref_snapshot_args() -> Vec<String> {
+    vec![
+        "for-each-ref".to_string(),
+        "--format=%(objectname) %(refname)".to_string(),
+        "refs/heads".to_string(),
+        "refs/tags".to_string(),
+    ]
+}
+
+pub fn parse_ref_snapshot(
+    stdout: &str,
+    head: Option<String>,
+    head_oid: Option<String>,
+) -> Result<RefSnapshot> {
+    let mut snapshot = RefSnapshot {
+        head,
+        head_oid,
+        ..RefSnapshot::default()
+    };
+    for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+        let (oid, name) = line
+            .split_once(' ')
+            .ok_or_else(|| GitLgError::Parse(format!("invalid ref line: {line:?}")))?;
+        snapshot.refs.insert(name.to_string(), oid.to_string());
+    }
+    Ok(snapshot)
+}
+
+// Plans restoring `record.before`. Every ref the operation changed, and HEAD,
+// must still be where the operation left them.
+pub fn plan_undo(record: &OperationRecord, current: &RefSnapshot) -> Result<UndoPlan> {
+    let (before, after) = (&record.before, &record.after);
+    let names = before
+        .refs
+        .keys()
+        .chain(after.refs.keys())
+        .collect::<BTreeSet<_>>();
+    let mut ref_updates = Vec::new();
+    for name in names {
+        let (from, to) = (after.refs.get(name), before.refs.get(name));
+        if from == to {
+            continue;
+        }
+        if current.refs.get(name) != from {
+            return Err(GitLgError::State(format!(
+                "{name} moved after operation {}; not undoing it",
+                record.id
+            )));
+        }
+        ref_updates.push(RefUpdate {
+            name: name.clone(),
+            from: from.cloned(),
+            to: to.cloned(),
+        });
+    }
+
+    if !same_head(current, after) {
+        return Err(GitLgError::State(format!(
+            "HEAD moved after operation {}; not undoing it",
+            record.id
+        )));
+    }
+    let checkout = (!same_head(before, after))
+        .then(|| before.head.clone().or_else(|| before.head_oid.clone()))
+        .flatten();
+    let affects_worktree = checkout.is_some()
+        || after
+            .head
+            .as_ref()
+            .is_some_and(|branch| ref_updates.iter().any(|update| update.name == *branch));
+    Ok(UndoPlan {
+        operation: record.clone(),
+        ref_updates,
+        checkout,
+        affects_worktree,
+    })
+}
+
+// A detached HEAD is only "the same" at the same commit.
+fn same_head(left: &RefSnapshot, right: &RefSnapshot) -> bool {
+    left.head == right.head && (left.head.is_some() || left.head_oid == right.head_oid)
+}
+
+// Input for `git update-ref --stdin`; old values are verified, so a ref that
+// moved meanwhile fails the whole transaction.
+pub fn update_ref_script(updates: &[RefUpdate]) -> String {
+    updates
+        .iter()
+        .map(|update| match (&update.from, &update.to) {
+            (Some(from), Some(to)) => format!("update {} {to} {from}\n", update.name),
+            (None, Some(to)) => format!("create {} {to}\n", update.name),
+            (Some(from), None) => format!("delete {} {from}\n", update.name),
+            (None, None) => String::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::{OperationLog, parse_ref_snapshot, plan_undo, update_ref_script};
+    use crate::models::{OperationRecord, RefSnapshot};
+
+    fn snapshot(head: Option<&str>, head_oid: &str, refs: &str) -> RefSnapshot {
+        parse_ref_snapshot(
+            refs,
+            head.map(ToString::to_string),
+            Some(head_oid.to_string()),
+        )
+        .expect("snapshot")
+    }
+
+    fn record(before: RefSnapshot, after: RefSnapshot) -> OperationRecord {
+        OperationRecord {
+            id: 0,
+            git_dir: PathBuf::from("/repo/.git"),
+            action_id: "branch:7:delete".to_string(),
+            command_line: "branch --delete topic".to_string(),
+            executed_at_unix: 0,
+            succeeded: true,
+            before,
+            after,
+            undone: false,
+        }
+    }
+
+    #[test]
+    fn plans_ref_restores_and_checkouts() {
+        let main = Some("refs/heads/main");
+        let before = snapshot(main, "a1", "a1 refs/heads/main\nb2 refs/heads/topic\n");
+        let after = snapshot(main, "a1", "a1 refs/heads/main\n");
+        let plan = plan_undo(&record(before.clone(), after.clone()), &after).expect("plan");
+        assert!(!plan.affects_worktree);
+        assert_eq!(plan.checkout, None);
+        assert_eq!(
+            update_ref_script(&plan.ref_updates),
+            "create refs/heads/topic b2\n"
+        );
+
+        // A reset of the checked-out branch moves the working tree back.
+        let reset = snapshot(main, "c3", "c3 refs/heads/main\n");
+        let plan = plan_undo(&record(after.clone(), reset.clone()), &reset).expect("plan");
+        assert!(plan.affects_worktree);
+        assert_eq!(
+            update_ref_script(&plan.ref_updates),
+            "update refs/heads/main a1 c3\n"
+        );
+
+        let detached = snapshot(None, "a1", "a1 refs/heads/main\n");
+        let plan = plan_undo(&record(after.clone(), detached.clone()), &detached).expect("plan");
+        assert_eq!(plan.checkout.as_deref(), Some("refs/heads/main"));
+        assert!(plan.affects_worktree);
+
+        // Refs moved since the operation are left alone; unrelated ones do
+        // not matter.
+        assert!(plan_undo(&record(before.clone(), after.clone()), &reset).is_ok());
+        let recreated = snapshot(main, "a1", "a1 refs/heads/main\nd4 refs/heads/topic\n");
+        assert!(plan_undo(&record(before, after), &recreated).is_err());
+        assert!(plan_undo(&record(reset.clone(), reset), &detached).is_err());
+    }
+
+    #[test]
+    fn appends_and_finds_undoable_operations() {
+        let tmp = TempDir::new().expect("tempdir");
+        let log = OperationLog::next_to_state(&tmp.path().join("state.json"));
+        assert_eq!(log.path(), tmp.path().join("operations.jsonl"));
+        assert_eq!(
+            log.last_undoable(&PathBuf::from("/repo/.git"))
+                .expect("load"),
+            None
+        );
+
+        let moved = record(
+            snapshot(None, "a1", "a1 refs/heads/main\nb2 refs/heads/keep\n"),
+            snapshot(None, "a1", "b2 refs/heads/keep\n"),
+        );
+        let unchanged = record(RefSnapshot::default(), RefSnapshot::default());
+        assert_eq!(log.append(moved).expect("append"), 1);
+        assert_eq!(log.append(unchanged).expect("append"), 2);
+        let last = log
+            .last_undoable(&PathBuf::from("/repo/.git"))
+            .expect("load")
+            .expect("undoable");
+        assert_eq!(last.id, 1);
+        // Unchanged refs are not stored.
+        assert_eq!(last.before, snapshot(None, "a1", "a1 refs/heads/main\n"));
+        assert_eq!(last.after, snapshot(None, "a1", ""));
+        assert_eq!(
+            log.last_undoable(&PathBuf::from("/other/.git"))
+                .expect("load"),
+            None
+        );
+
+        log.mark_undone(1).expect("mark undone");
+        assert_eq!(
+            log.last_undoable(&PathBuf::from("/repo/.git"))
+                .expect("load"),
+            None
+        );
+        assert!(log.mark_undone(9).is_err());
+        let text = std::fs::read_to_string(log.path()).expect("read log");
+        assert_eq!(text.lines().count(), 3);
+        assert!(
+            text.lines()
+                .last()
+                .is_some_and(|line| line.contains("undone"))
+        );
+    }
+
+    #[test]
+    fn compacts_old_records() {
+        let tmp = TempDir::new().expect("tempdir");
+        let log = OperationLog::next_to_state(&tmp.path().join("state.json"));
+        for _ in 0..2 * super::OPERATION_LOG_LIMIT {
+            log.append(record(RefSnapshot::default(), RefSnapshot::default()))
+                .expect("append");
+        }
+        log.mark_undone(400).expect("mark undone");
+        let id = log
+            .append(record(RefSnapshot::default(), RefSnapshot::default()))
+            .expect("append");
+        assert_eq!(id, 401);
+        let records = log.load().expect("load");
+        assert_eq!(records.len(), super::OPERATION_LOG_LIMIT + 1);
+        assert_eq!(records[0].id, 201);
+        assert!(records[199].undone);
+    }
+}
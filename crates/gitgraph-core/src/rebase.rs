@@ -0,0 +1,212 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::{GitLgError, Result};
+use crate::models::{GraphRow, RebasePlan, RebaseTodoAction, RebaseTodoItem};
+
+// Builds a plan that picks every commit of `rows`, given newest first as the
+// graph lists them. The rows must form one first-parent chain without merges.
+pub fn plan_rebase(rows: &[GraphRow]) -> Result<RebasePlan> {
+    let (newest, oldest) = match (rows.first(), rows.last()) {
+        (Some(newest), Some(oldest)) => (newest, oldest),
+        _ => return Err(GitLgError::State("no commits to rebase".to_string())),
+    };
+    for (row, older) in rows.iter().zip(rows.iter().skip(1)) {
+        if row.parents.len() > 1 {
+            return Err(GitLgError::State(format!(
+                "cannot rebase merge commit {}",
+                row.short_hash
+            )));
+        }
+        if row.parents.first() != Some(&older.hash) {
+            return Err(GitLgError::State(format!(
+                "{} is not the parent of {}; pick a contiguous range",
+                older.short_hash, row.short_hash
+            )));
+        }
+    }
+    if oldest.parents.len() > 1 {
+        return Err(GitLgError::State(format!(
+            "cannot rebase merge commit {}",
+            oldest.short_hash
+        )));
+    }
+    Ok(RebasePlan {
+        onto: oldest.parents.first().cloned(),
+        head: newest.hash.clone(),
+        items: rows
+            .iter()
+            .rev()
+            .map(|row| RebaseTodoItem {
+                action: RebaseTodoAction::Pick,
+                hash: row.hash.clone(),
+                subject: row.subject.clone(),
+                message: None,
+            })
+            .collect(),
+    })
+}
+
+// Renders the todo list git will run, plus the message files it refers to
+// (written under `message_dir`). New messages are applied by `exec`-ing
+// `git commit --amend -F <file>` right after the commit they replace.
+pub fn render_rebase_todo(
+    plan: &RebasePlan,
+    message_dir: &Path,
+) -> Result<(String, Vec<(PathBuf, String)>)> {
+    let invalid = |message: String| Err(GitLgError::State(message));
+    if plan
+        .items
+        .iter()
+        .all(|item| item.action == RebaseTodoAction::Drop)
+    {
+        return invalid("the plan keeps no commits".to_string());
+    }
+    let mut todo = String::new();
+    let mut messages = Vec::new();
+    let mut has_base = false;
+    for item in &plan.items {
+        let message = item
+            .message
+            .as_deref()
+            .map(str::trim)
+            .filter(|message| !message.is_empty());
+        let action = match (item.action, message) {
+            (RebaseTodoAction::Squash | RebaseTodoAction::Fixup, _) if !has_base => {
+                return invalid(format!(
+                    "{} {} has no earlier commit to fold into",
+                    item.action.keyword(),
+                    item.hash
+                ));
+            }
+            (RebaseTodoAction::Reword, None) => {
+                return invalid(format!("reword {} needs a new message", item.hash));
+            }
+            (RebaseTodoAction::Reword, Some(_)) => RebaseTodoAction::Pick,
+            (RebaseTodoAction::Squash, Some(_)) => RebaseTodoAction::Fixup,
+            (action, _) => action,
+        };
+        has_base |= action != RebaseTodoAction::Drop;
+        todo.push_str(&format!(
+            "{} {} {}\n",
+            action.keyword(),
+            item.hash,
+            item.subject.replace('\n', " ")
+        ));
+        if let (RebaseTodoAction::Reword | RebaseTodoAction::Squash, Some(message)) =
+            (item.action, message)
+        {
+            let path = message_dir.join(format!("message-{}", messages.len()));
+            // `whitespace` keeps lines starting with `#`, which are part of
+            // the message the user typed rather than comments.
+            todo.push_str(&format!(
+                "exec git commit --amend --allow-empty --quiet --cleanup=whitespace -F {}\n",
+                shell_quote(&path.to_string_lossy())
+            ));
+            messages.push((path, format!("{message}\n")));
+        }
+    }
+    Ok((todo, messages))
+}
+
+// Counts the commits in a todo or `done` file, skipping comments and our
+// `exec` lines so progress lines up with the plan's items.
+pub fn count_todo_items(todo: &str) -> usize {
+    todo.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| {
+            let command = line.split_whitespace().next().unwrap_or_default();
+            !matches!(command, "exec" | "x" | "break" | "b" | "label" | "reset")
+        })
+        .count()
+}
+
+// Quotes for the POSIX shell git runs editors and `exec` lines through.
+pub(crate) fn shell_quote(raw: &str) -> String {
+    format!("'{}'", raw.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{count_todo_items, plan_rebase, render_rebase_todo};
+    use crate::log_parser::{GraphBuilder, RawCommit};
+    use crate::models::RebaseTodoAction;
+
+    fn rows(chain: &[(&str, &[&str])]) -> Vec<crate::models::GraphRow> {
+        let mut builder = GraphBuilder::new();
+        chain
+            .iter()
+            .map(|(hash, parents)| {
+                builder.push(RawCommit {
+                    hash: hash.to_string(),
+                    short_hash: hash.to_string(),
+                    parents: parents.iter().map(ToString::to_string).collect(),
+                    author_name: String::new(),
+                    author_email: String::new(),
+                    authored_unix: 0,
+                    committed_unix: 0,
+                    committer_name: String::new(),
+                    committer_email: String::new(),
+                    author_tz_offset_minutes: 0,
+                    committer_tz_offset_minutes: 0,
+                    refs: Vec::new(),
+                    subject: format!("subject {hash}"),
+                    body: String::new(),
+                    notes: String::new(),
+                    signature: None,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plans_oldest_first_and_rejects_gaps_and_merges() {
+        let chain = rows(&[("c3", &["c2"]), ("c2", &["c1"]), ("c1", &["base"])]);
+        let plan = plan_rebase(&chain).expect("plan");
+        assert_eq!(plan.onto.as_deref(), Some("base"));
+        assert_eq!(plan.head, "c3");
+        let hashes = plan
+            .items
+            .iter()
+            .map(|i| i.hash.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, vec!["c1", "c2", "c3"]);
+
+        assert!(plan_rebase(&rows(&[("c3", &["c2"]), ("c1", &["base"])])).is_err());
+        assert!(plan_rebase(&rows(&[("m", &["a", "b"]), ("a", &["base"])])).is_err());
+        assert!(plan_rebase(&[]).is_err());
+        let root = plan_rebase(&rows(&[("c1", &[])])).expect("root plan");
+        assert_eq!(root.onto, None);
+    }
+
+    #[test]
+    fn renders_messages_through_exec_lines() {
+        let chain = rows(&[("c3", &["c2"]), ("c2", &["c1"]), ("c1", &["base"])]);
+        let mut plan = plan_rebase(&chain).expect("plan");
+        plan.items[0].action = RebaseTodoAction::Reword;
+        plan.items[0].message = Some("New c1".to_string());
+        plan.items[1].action = RebaseTodoAction::Squash;
+        plan.items[1].message = Some("c1 and c2".to_string());
+        plan.items[2].action = RebaseTodoAction::Drop;
+
+        let (todo, messages) = render_rebase_todo(&plan, Path::new("/tmp/it's")).expect("todo");
+        assert_eq!(
+            todo,
+            "pick c1 subject c1\n\
+             exec git commit --amend --allow-empty --quiet --cleanup=whitespace -F '/tmp/it'\\''s/message-0'\n\
+             fixup c2 subject c2\n\
+             exec git commit --amend --allow-empty --quiet --cleanup=whitespace -F '/tmp/it'\\''s/message-1'\n\
+             drop c3 subject c3\n"
+        );
+        assert_eq!(messages[1].1, "c1 and c2\n");
+        assert_eq!(count_todo_items(&todo), 3);
+
+        plan.items[0].action = RebaseTodoAction::Fixup;
+        assert!(render_rebase_todo(&plan, Path::new("/tmp")).is_err());
+        plan.items[0].action = RebaseTodoAction::Reword;
+        plan.items[0].message = None;
+        assert!(render_rebase_todo(&plan, Path::new("/tmp")).is_err());
+    }
+}
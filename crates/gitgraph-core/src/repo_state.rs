@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::{GitLgError, Result};
+use crate::models::{RepoOperation, RepoOperationControl, RepoOperationKind};
+
+// Reads the operation git left in progress from `git_dir`, the way `git
+// status` does. A rebase wins over the `CHERRY_PICK_HEAD` it may create; a
+// bisect is only reported when nothing else is in progress.
+pub fn detect_repo_operation(git_dir: &Path) -> Option<RepoOperation> {
+    let read = |name: &str| {
+        fs::read_to_string(git_dir.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let number = |name: &str| read(name).and_then(|value| value.parse::<usize>().ok());
+    let operation = |kind| RepoOperation {
+        kind,
+        head_name: None,
+        onto: None,
+        current: None,
+        step: None,
+        total: None,
+    };
+
+    if git_dir.join("rebase-merge").is_dir() {
+        return Some(RepoOperation {
+            head_name: read("rebase-merge/head-name").filter(|name| name != "detached HEAD"),
+            onto: read("rebase-merge/onto"),
+            current: read("rebase-merge/stopped-sha"),
+            step: number("rebase-merge/msgnum"),
+            total: number("rebase-merge/end"),
+            ..operation(RepoOperationKind::Rebase)
+        });
+    }
+    if git_dir.join("rebase-apply").is_dir() {
+        let kind = if git_dir.join("rebase-apply/applying").exists() {
+            RepoOperationKind::ApplyMailbox
+        } else {
+            RepoOperationKind::Rebase
+        };
+        return Some(RepoOperation {
+            head_name: read("rebase-apply/head-name").filter(|name| name != "detached HEAD"),
+            onto: read("rebase-apply/onto"),
+            current: read("rebase-apply/original-commit"),
+            step: number("rebase-apply/next"),
+            total: number("rebase-apply/last"),
+            ..operation(kind)
+        });
+    }
+    for (file, kind) in [
+        ("MERGE_HEAD", RepoOperationKind::Merge),
+        ("CHERRY_PICK_HEAD", RepoOperationKind::CherryPick),
+        ("REVERT_HEAD", RepoOperationKind::Revert),
+    ] {
+        if let Some(head) = read(file) {
+            return Some(RepoOperation {
+                // An octopus merge lists one head per line.
+                current: head.lines().next().map(ToString::to_string),
+                ..operation(kind)
+            });
+        }
+    }
+    if git_dir.join("BISECT_LOG").exists() {
+        return Some(RepoOperation {
+            head_name: read("BISECT_START"),
+            ..operation(RepoOperationKind::Bisect)
+        });
+    }
+    None
+}
+
+// `git <operation> --continue` and friends. Aborting a bisect is
+// `bisect reset`, which checks out the branch it started from.
+pub fn operation_control_args(
+    kind: RepoOperationKind,
+    control: RepoOperationControl,
+) -> Result<Vec<String>> {
+    if !kind.supports(control) {
+        return Err(GitLgError::State(format!(
+            "cannot {} a {}",
+            control.label(),
+            kind.label()
+        )));
+    }
+    let args = match (kind, control) {
+        (RepoOperationKind::Bisect, RepoOperationControl::Abort) => vec!["bisect", "reset"],
+        (RepoOperationKind::Bisect, _) => vec!["bisect", "skip"],
+        (kind, control) => {
+            return Ok(vec![
+                kind.label().to_string(),
+                format!("--{}", control.label()),
+            ]);
+        }
+    };
+    Ok(args.into_iter().map(ToString::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::{detect_repo_operation, operation_control_args};
+    use crate::models::{RepoOperationControl, RepoOperationKind};
+
+    #[test]
+    fn reads_rebase_progress_and_sequencer_heads() {
+        let tmp = TempDir::new().expect("tempdir");
+        assert_eq!(detect_repo_operation(tmp.path()), None);
+
+        fs::write(tmp.path().join("BISECT_LOG"), "# bad: x\n").expect("bisect log");
+        fs::write(tmp.path().join("BISECT_START"), "main\n").expect("bisect start");
+        let bisect = detect_repo_operation(tmp.path()).expect("bisect");
+        assert_eq!(bisect.kind, RepoOperationKind::Bisect);
+        assert_eq!(bisect.summary(), "bisect of main");
+
+        fs::write(tmp.path().join("CHERRY_PICK_HEAD"), "abcdef0123\n").expect("pick head");
+        let pick = detect_repo_operation(tmp.path()).expect("cherry-pick");
+        assert_eq!(pick.kind, RepoOperationKind::CherryPick);
+        assert_eq!(pick.summary(), "cherry-pick of abcdef0");
+
+        let state = tmp.path().join("rebase-merge");
+        fs::create_dir(&state).expect("rebase-merge");
+        for (name, value) in [
+            ("head-name", "refs/heads/topic\n"),
+            ("onto", "1234567890\n"),
+            ("msgnum", "2\n"),
+            ("end", "5\n"),
+        ] {
+            fs::write(state.join(name), value).expect("rebase state");
+        }
+        let rebase = detect_repo_operation(tmp.path()).expect("rebase");
+        assert_eq!(rebase.kind, RepoOperationKind::Rebase);
+        assert_eq!((rebase.step, rebase.total), (Some(2), Some(5)));
+        assert_eq!(rebase.summary(), "rebase 2/5 of topic onto 1234567");
+    }
+
+    #[test]
+    fn maps_controls_to_git_commands() {
+        assert_eq!(
+            operation_control_args(RepoOperationKind::CherryPick, RepoOperationControl::Skip)
+                .expect("args"),
+            vec!["cherry-pick", "--skip"]
+        );
+        assert_eq!(
+            operation_control_args(RepoOperationKind::Bisect, RepoOperationControl::Abort)
+                .expect("args"),
+            vec!["bisect", "reset"]
+        );
+        assert!(
+            operation_control_args(RepoOperationKind::Merge, RepoOperationControl::Skip).is_err()
+        );
+    }
+}
@@ -61,6 +61,16 @@ fn search_parts(
     if query.include_email && matches(row.author_email.as_str()) {
         return true;
     }
+    if query.include_notes && matches(row.notes.as_str()) {
+        return true;
+    }
+    if query.include_trailers {
+        for trailer in &row.trailers {
+            if matches(trailer.key.as_str()) || matches(trailer.value.as_str()) {
+                return true;
+            }
+        }
+    }
     if query.include_refs {
         for git_ref in &row.refs {
             if matches(git_ref.name.as_str()) {
@@ -78,7 +88,9 @@ fn search_parts(
 
 #[cfg(test)]
 mod tests {
-    use crate::models::{CommitSearchQuery, GitRef, GitRefKind, GraphRow};
+    use crate::models::{
+        CommitSearchQuery, CommitTrailer, GitRef, GitRefKind, GraphRow, TrailerIdentity,
+    };
 
     use super::filter_commits;
 
@@ -92,8 +104,15 @@ mod tests {
                 author_email: "alice@example.com".to_string(),
                 authored_unix: 10,
                 committed_unix: 10,
+                committer_name: String::new(),
+                committer_email: String::new(),
+                author_tz_offset_minutes: 0,
+                committer_tz_offset_minutes: 0,
                 subject: "Add parser".to_string(),
                 body: "Adds commit parser".to_string(),
+                trailers: vec![],
+                notes: "ci: flaky on windows".to_string(),
+                signature: None,
                 refs: vec![GitRef {
                     kind: GitRefKind::LocalBranch,
                     name: "main".to_string(),
@@ -101,7 +120,10 @@ mod tests {
                 }],
                 lane: 0,
                 active_lane_count: 1,
+                color_group: 0,
+                collapsed_commits: 0,
                 edges: vec![],
+                segments: vec![],
             },
             GraphRow {
                 hash: "cccccccc".to_string(),
@@ -111,31 +133,96 @@ mod tests {
                 author_email: "bob@example.com".to_string(),
                 authored_unix: 11,
                 committed_unix: 11,
+                committer_name: String::new(),
+                committer_email: String::new(),
+                author_tz_offset_minutes: 0,
+                committer_tz_offset_minutes: 0,
                 subject: "Fix ui".to_string(),
-                body: "Nothing about parser".to_string(),
+                body: "Nothing about parser\n\nCo-authored-by: Carol <carol@example.com>"
+                    .to_string(),
+                trailers: vec![CommitTrailer {
+                    key: "Co-authored-by".to_string(),
+                    value: "Carol <carol@example.com>".to_string(),
+                    identity: Some(TrailerIdentity {
+                        name: "Carol".to_string(),
+                        email: "carol@example.com".to_string(),
+                    }),
+                }],
+                notes: String::new(),
+                signature: None,
                 refs: vec![],
                 lane: 0,
                 active_lane_count: 1,
+                color_group: 0,
+                collapsed_commits: 0,
                 edges: vec![],
+                segments: vec![],
             },
         ]
     }
 
     #[test]
     fn filters_substring_case_insensitive() {
-        let mut q = CommitSearchQuery::default();
-        q.text = "PARSER".to_string();
+        let q = CommitSearchQuery {
+            text: "PARSER".to_string(),
+            ..CommitSearchQuery::default()
+        };
         let filtered = filter_commits(&sample_rows(), &q).expect("search");
         assert_eq!(filtered.len(), 2);
     }
 
     #[test]
     fn filters_regex() {
-        let mut q = CommitSearchQuery::default();
-        q.use_regex = true;
-        q.text = "^Fix\\s".to_string();
+        let q = CommitSearchQuery {
+            use_regex: true,
+            text: "^Fix\\s".to_string(),
+            ..CommitSearchQuery::default()
+        };
+        let filtered = filter_commits(&sample_rows(), &q).expect("search");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].author_name, "Bob");
+    }
+
+    #[test]
+    fn matches_co_authors_through_trailers() {
+        let q = CommitSearchQuery {
+            text: "carol@".to_string(),
+            include_body: false,
+            ..CommitSearchQuery::default()
+        };
         let filtered = filter_commits(&sample_rows(), &q).expect("search");
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].author_name, "Bob");
+
+        let without_trailers = CommitSearchQuery {
+            include_trailers: false,
+            ..q
+        };
+        assert!(
+            filter_commits(&sample_rows(), &without_trailers)
+                .expect("search")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn matches_notes_when_enabled() {
+        let q = CommitSearchQuery {
+            text: "flaky".to_string(),
+            ..CommitSearchQuery::default()
+        };
+        let filtered = filter_commits(&sample_rows(), &q).expect("search");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].author_name, "Alice");
+
+        let without_notes = CommitSearchQuery {
+            include_notes: false,
+            ..q
+        };
+        assert!(
+            filter_commits(&sample_rows(), &without_notes)
+                .expect("search")
+                .is_empty()
+        );
     }
 }
@@ -1,15 +1,47 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use regex::Regex;
 
 use crate::actions::{ActionCatalog, ActionContext, ActionRequest, ResolvedAction};
+use crate::bisect::{
+    apply_bisect_candidates, bisect_candidates_args, bisect_refs_args, parse_bisect_refs,
+};
+use crate::blame::parse_blame_porcelain;
+use crate::commit::{commit_args, commit_row_args, parse_commit_row};
 use crate::error::{GitLgError, Result};
 use crate::git::{GitOutput, GitRunner};
-use crate::log_parser::{FIELD_SEP, build_graph_rows, parse_git_log_records};
-use crate::models::{BlameInfo, BranchInfo, CommitSearchQuery, FileChange, GraphData, GraphQuery};
+use crate::hunk_patch::{HUNK_CONTEXT_LINES, hunk_apply_args, select_hunk_patch};
+use crate::line_history::{line_history_args, parse_line_history};
+use crate::log_parser::{
+    BRANCH_LIST_FORMAT, ChainCollapse, GraphBuilder, RawCommit, log_format, parse_branch_list,
+    parse_git_log_records,
+};
+use crate::merge_preview::{merge_tree_args, parse_merge_tree};
+use crate::models::{
+    BisectMark, BisectStatus, BlameInfo, BlameOptions, BranchInfo, CommitOptions,
+    CommitSearchQuery, FileBlame, FileChange, FileHistoryEntry, FilePatch, GraphData, GraphMode,
+    GraphQuery, GraphRow, HunkAction, HunkSelection, LineHistoryEntry, MergePreview,
+    OperationRecord, RebasePlan, RebaseState, RebaseStatus, RefSnapshot, RefUpdate, RepoOperation,
+    RepoOperationControl, RevisionComparison, UNCOMMITTED_HASH, UndoPlan, WorkingTreeStatus,
+    WorktreeDiffSide,
+};
+use crate::oplog::{
+    OperationLog, parse_ref_snapshot, plan_undo, ref_snapshot_args, update_ref_script,
+};
+use crate::rebase::{count_todo_items, render_rebase_todo, shell_quote};
+use crate::repo_state::{detect_repo_operation, operation_control_args};
 use crate::search::filter_commits;
+use crate::status::{parse_status_porcelain_v2, status_args};
+use crate::stream::GraphStream;
+
+// Todo list and message files of a running `run_rebase`, inside the git dir.
+const REBASE_PLAN_DIR: &str = "gitgraph-rebase";
+// Commits held back while a collapsed chain is decided; a longer chain
+// keeps its commit at this depth visible.
+const CHAIN_COLLAPSE_LOOKAHEAD: usize = 1_000;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ActionExecutionResult {
@@ -17,17 +49,30 @@ pub struct ActionExecutionResult {
     pub command_line: String,
     pub args: Vec<String>,
     pub output: GitOutput,
+    // Set when the action ran but could not be recorded for undo.
+    pub log_warning: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GitLgService {
     git: GitRunner,
     actions: ActionCatalog,
+    operation_log: Option<OperationLog>,
 }
 
 impl GitLgService {
     pub fn new(git: GitRunner, actions: ActionCatalog) -> Self {
-        Self { git, actions }
+        Self {
+            git,
+            actions,
+            operation_log: None,
+        }
+    }
+
+    // Records every `execute_action` with ref snapshots so it can be undone.
+    pub fn with_operation_log(mut self, log: OperationLog) -> Self {
+        self.operation_log = Some(log);
+        self
     }
 
     pub fn with_default_actions(git: GitRunner) -> Self {
@@ -40,18 +85,72 @@ impl GitLgService {
 
     pub fn graph(&self, repo_path: &Path, query: &GraphQuery) -> Result<GraphData> {
         self.git.validate_repo(repo_path)?;
+        let mut builder = self.graph_builder(query);
+        let mut commits = self
+            .uncommitted_row(repo_path, query, &mut builder)?
+            .into_iter()
+            .collect::<Vec<_>>();
         let log_output = self.run_log(repo_path, query)?;
-        let commits = build_graph_rows(parse_git_log_records(&log_output.stdout)?);
+        for commit in parse_git_log_records(&log_output.stdout)? {
+            commits.extend(builder.accept(commit));
+        }
+        commits.extend(builder.finish());
         let branches = self.read_branches(repo_path)?;
+        let operation = self.repo_operation(repo_path)?;
         Ok(GraphData {
             repository: normalize_repo_path(repo_path),
             generated_at_unix: current_unix_timestamp(),
             query: query.clone(),
             commits,
             branches,
+            operation,
         })
     }
 
+    pub fn graph_stream(
+        &self,
+        repo_path: &Path,
+        query: &GraphQuery,
+        batch_size: usize,
+    ) -> Result<GraphStream> {
+        self.git.validate_repo(repo_path)?;
+        let builder = self.graph_builder(query);
+        let args = self.log_args(repo_path, query)?;
+        let stream = self.git.spawn(repo_path, &args, false)?;
+        Ok(GraphStream::with_builder(stream, batch_size, builder))
+    }
+
+    pub fn graph_paged(
+        &self,
+        repo_path: &Path,
+        query: &GraphQuery,
+        page_size: usize,
+    ) -> Result<(GraphData, GraphStream)> {
+        self.git.validate_repo(repo_path)?;
+        let mut builder = self.graph_builder(query);
+        let mut commits = self
+            .uncommitted_row(repo_path, query, &mut builder)?
+            .into_iter()
+            .collect::<Vec<_>>();
+        let args = self.log_args(repo_path, query)?;
+        let mut stream =
+            GraphStream::with_builder(self.git.spawn(repo_path, &args, false)?, page_size, builder);
+        commits.extend(stream.next_batch(stream.batch_size())?);
+        let branches = self.read_branches(repo_path)?;
+        let operation = self.repo_operation(repo_path)?;
+        Ok((
+            GraphData {
+                repository: normalize_repo_path(repo_path),
+                generated_at_unix: current_unix_timestamp(),
+                query: query.clone(),
+                commits,
+                branches,
+                operation,
+            },
+            stream,
+        ))
+    }
+
     pub fn graph_filtered(
         &self,
         repo_path: &Path,
@@ -88,22 +187,65 @@ impl GitLgService {
         let resolved = self.actions.resolve_with_lookup(request, |placeholder| {
             self.lookup_dynamic_placeholder(repo_path, placeholder)
         })?;
+        let before = self
+            .operation_log
+            .as_ref()
+            .map(|_| self.ref_snapshot(repo_path));
         let output = if let Some(script) = &resolved.shell_script {
             let script = format!("{} {}", self.git.git_binary(), script);
             self.git
-                .exec_shell(repo_path, &script, resolved.allow_non_zero_exit)?
+                .exec_shell(repo_path, &script, resolved.allow_non_zero_exit)
         } else {
             self.git
-                .exec(repo_path, &resolved.args, resolved.allow_non_zero_exit)?
+                .exec(repo_path, &resolved.args, resolved.allow_non_zero_exit)
+        };
+        // The action has already run, so failing to log it is only reported.
+        let log_warning = match (&self.operation_log, before) {
+            (Some(log), Some(before)) => before
+                .and_then(|before| {
+                    self.log_action(log, repo_path, &resolved, before, output.is_ok())
+                })
+                .err()
+                .map(|err| format!("not recorded for undo: {err}")),
+            _ => None,
         };
+        let output = output?;
         Ok(ActionExecutionResult {
             action_id: resolved.id,
             command_line: resolved.command_line,
             args: resolved.args,
             output,
+            log_warning,
         })
     }
 
+    fn log_action(
+        &self,
+        log: &OperationLog,
+        repo_path: &Path,
+        resolved: &ResolvedAction,
+        before: RefSnapshot,
+        succeeded: bool,
+    ) -> Result<()> {
+        let after = self.ref_snapshot(repo_path)?;
+        // A failed action is only worth undoing if it moved something.
+        if !succeeded && before == after {
+            return Ok(());
+        }
+        log.append(OperationRecord {
+            id: 0,
+            git_dir: self.git_dir(repo_path)?,
+            action_id: resolved.id.clone(),
+            command_line: resolved.command_line.clone(),
+            executed_at_unix: current_unix_timestamp(),
+            succeeded,
+            before,
+            after,
+            undone: false,
+        })?;
+        Ok(())
+    }
+
     pub fn resolve_action_preview(
         &self,
         request: ActionRequest,
@@ -121,281 +263,1237 @@ impl GitLgService {
 
     pub fn blame_line(&self, repo_path: &Path, file: &Path, line: usize) -> Result<BlameInfo> {
         let line_no = line.max(1);
+        let blame = self.run_blame(repo_path, file, Some(line_no), &BlameOptions::default())?;
+        blame
+            .lines
+            .into_iter()
+            .next()
+            .ok_or_else(|| GitLgError::Parse(format!("git blame returned no line {line_no}")))
+    }
+
+    pub fn blame_file(
+        &self,
+        repo_path: &Path,
+        file: &Path,
+        options: &BlameOptions,
+    ) -> Result<FileBlame> {
+        self.run_blame(repo_path, file, None, options)
+    }
+
+    fn run_blame(
+        &self,
+        repo_path: &Path,
+        file: &Path,
+        line: Option<usize>,
+        options: &BlameOptions,
+    ) -> Result<FileBlame> {
         let repo = normalize_repo_path(repo_path);
         let repo_file = normalize_repo_file_input(&repo, file);
-        let out = self.git.exec(
-            &repo,
-            &[
-                "blame".to_string(),
-                format!("-L{line_no},{line_no}"),
-                "--porcelain".to_string(),
-                "--".to_string(),
-                repo_file.to_string_lossy().to_string(),
-            ],
-            false,
-        )?;
-        let mut commit_hash = String::new();
-        let mut author_name = String::new();
-        let mut author_email = String::new();
-        let mut author_time_unix: i64 = 0;
-        let mut summary = String::new();
-        for (idx, line) in out.stdout.lines().enumerate() {
-            if idx == 0 {
-                commit_hash = line
-                    .split_whitespace()
-                    .next()
-                    .unwrap_or_default()
-                    .to_string();
-                continue;
-            }
-            if let Some(v) = line.strip_prefix("author ") {
-                author_name = v.to_string();
-                continue;
-            }
-            if let Some(v) = line.strip_prefix("author-mail ") {
-                author_email = v.trim_matches(['<', '>']).to_string();
-                continue;
-            }
-            if let Some(v) = line.strip_prefix("author-time ") {
-                author_time_unix = v.parse::<i64>().unwrap_or(0);
-                continue;
-            }
-            if let Some(v) = line.strip_prefix("summary ") {
-                summary = v.to_string();
-                continue;
-            }
+        let mut args = vec!["blame".to_string(), "--porcelain".to_string()];
+        if let Some(line_no) = line {
+            args.push(format!("-L{line_no},{line_no}"));
         }
+        if options.detect_moves {
+            args.push("-M".to_string());
+        }
+        if options.detect_copies {
+            args.push("-C".to_string());
+        }
+        if let Some(ignore_file) = &options.ignore_revs_file {
+            args.push("--ignore-revs-file".to_string());
+            args.push(ignore_file.to_string_lossy().to_string());
+        }
+        for rev in &options.ignore_revs {
+            args.push("--ignore-rev".to_string());
+            args.push(rev.clone());
+        }
+        args.push("--".to_string());
+        args.push(repo_file.to_string_lossy().to_string());
+        let out = self.git.exec(&repo, &args, false)?;
+        parse_blame_porcelain(&out.stdout, &repo.join(repo_file))
+    }
 
-        Ok(BlameInfo {
-            file: repo.join(repo_file),
-            line: line_no,
-            commit_hash,
-            author_name,
-            author_email,
-            author_time_unix,
-            summary,
-        })
+    pub fn line_history(
+        &self,
+        repo_path: &Path,
+        file: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<LineHistoryEntry>> {
+        self.line_history_at(repo_path, "HEAD", file, start, end)
     }
 
-    pub fn commit_file_changes(
+    // Like `line_history`, with `start..=end` numbered as in `revision`.
+    pub fn line_history_at(
         &self,
         repo_path: &Path,
-        commit_hash: &str,
-    ) -> Result<Vec<FileChange>> {
+        revision: &str,
+        file: &Path,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<LineHistoryEntry>> {
         self.git.validate_repo(repo_path)?;
+        let start = start.max(1);
+        let end = end.max(start);
+        let repo = normalize_repo_path(repo_path);
+        let hash = self.resolve_commit(&repo, revision)?;
+        // Accepts paths as listed by `commit_file_changes`, renames included.
+        let repo_file = normalize_numstat_path(
+            &normalize_repo_file_input(&repo, file)
+                .to_string_lossy()
+                .replace('\\', "/"),
+        );
         let out = self.git.exec(
-            repo_path,
-            &[
-                "-c".to_string(),
-                "color.ui=never".to_string(),
-                "-c".to_string(),
-                "core.quotePath=false".to_string(),
-                "show".to_string(),
-                "--numstat".to_string(),
-                "--no-color".to_string(),
-                "--no-ext-diff".to_string(),
-                "--format=".to_string(),
-                "--find-renames".to_string(),
-                "--find-copies".to_string(),
-                commit_hash.to_string(),
-            ],
+            &repo,
+            &line_history_args(&hash, &repo_file, start, end),
             false,
         )?;
-        let mut files = Vec::new();
-        for line in out.stdout.lines() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-            let mut parts = trimmed.splitn(3, '\t');
-            let Some(added_raw) = parts.next() else {
-                continue;
-            };
-            let Some(removed_raw) = parts.next() else {
-                continue;
-            };
-            let Some(path) = parts.next() else {
-                continue;
-            };
+        parse_line_history(&out.stdout)
+    }
 
-            files.push(FileChange {
-                path: path.to_string(),
-                added: parse_numstat_value(added_raw),
-                removed: parse_numstat_value(removed_raw),
-            });
+    pub fn working_tree_status(&self, repo_path: &Path) -> Result<WorkingTreeStatus> {
+        self.git.validate_repo(repo_path)?;
+        let out = self.git.exec(repo_path, &status_args(), false)?;
+        parse_status_porcelain_v2(&out.stdout)
+    }
+
+    // Commits with `options` and returns the new commit as a standalone row.
+    pub fn commit(&self, repo_path: &Path, options: &CommitOptions) -> Result<GraphRow> {
+        self.git.validate_repo(repo_path)?;
+        let args = commit_args(options)?;
+        if options.stage_all {
+            self.git
+                .exec(repo_path, &["add".to_string(), "-A".to_string()], false)?;
         }
-        Ok(files)
+        // Amending may only reword, so an empty index is fine there.
+        if !options.amend
+            && !options.allow_empty
+            && self
+                .working_tree_status(repo_path)?
+                .staged()
+                .next()
+                .is_none()
+        {
+            return Err(GitLgError::State("nothing staged to commit".to_string()));
+        }
+        self.git.exec(repo_path, &args, false)?;
+        self.commit_row(repo_path, "HEAD")
     }
 
-    pub fn commit_file_patch(
+    // One commit as a standalone row, without graph lanes.
+    pub fn commit_row(&self, repo_path: &Path, revision: &str) -> Result<GraphRow> {
+        self.git.validate_repo(repo_path)?;
+        let out = self
+            .git
+            .exec(repo_path, &commit_row_args(revision), false)?;
+        parse_commit_row(&out.stdout)
+    }
+
+    // Dry-runs merging `theirs` into `ours` with `git merge-tree --write-tree`
+    // (git 2.38+); neither the index nor the working tree is touched.
+    pub fn preview_merge(
         &self,
         repo_path: &Path,
-        commit_hash: &str,
-        file_path: &str,
-        context_lines: usize,
-    ) -> Result<String> {
+        ours: &str,
+        theirs: &str,
+    ) -> Result<MergePreview> {
         self.git.validate_repo(repo_path)?;
-        let normalized_path = normalize_numstat_path(file_path);
-        let mut candidate_paths = vec![file_path.trim().to_string()];
-        if normalized_path != file_path.trim() {
-            candidate_paths.push(normalized_path);
+        let ours = self.resolve_commit(repo_path, ours)?;
+        let theirs = self.resolve_commit(repo_path, theirs)?;
+        let args = merge_tree_args(&ours, &theirs);
+        // Exit code 1 means "conflicts"; anything else is a real failure.
+        let out = self.git.exec(repo_path, &args, true)?;
+        if !matches!(out.exit_code, Some(0 | 1)) {
+            return Err(GitLgError::GitCommandFailed {
+                program: self.git.git_binary().to_string(),
+                args,
+                exit_code: out.exit_code,
+                stderr: out.stderr,
+                stdout: out.stdout,
+            });
         }
+        parse_merge_tree(&out.stdout, &ours, &theirs, out.exit_code == Some(0))
+    }
 
-        for candidate in candidate_paths {
-            if candidate.is_empty() {
-                continue;
-            }
-            if let Some(patch) = self.try_commit_file_patch(
-                repo_path,
-                commit_hash,
-                &candidate,
-                context_lines,
-                false,
-            )? {
-                return Ok(patch);
-            }
-            if let Some(patch) =
-                self.try_commit_file_patch(repo_path, commit_hash, &candidate, context_lines, true)?
-            {
-                return Ok(patch);
-            }
+    // Runs `plan` through `git rebase -i`, with the todo list supplied by
+    // `GIT_SEQUENCE_EDITOR` and `GIT_EDITOR` accepting every prepared message.
+    pub fn run_rebase(&self, repo_path: &Path, plan: &RebasePlan) -> Result<RebaseStatus> {
+        self.git.validate_repo(repo_path)?;
+        let head = self.resolve_commit(repo_path, "HEAD")?;
+        if head != plan.head {
+            return Err(GitLgError::State(format!(
+                "the plan ends at {} but HEAD is {head}",
+                plan.head
+            )));
+        }
+        let plan_dir = self.git_dir(repo_path)?.join(REBASE_PLAN_DIR);
+        let (todo, messages) = render_rebase_todo(plan, &plan_dir)?;
+        if plan_dir.exists() {
+            fs::remove_dir_all(&plan_dir)
+                .map_err(|source| GitLgError::io("clearing rebase plan", source))?;
+        }
+        fs::create_dir_all(&plan_dir)
+            .map_err(|source| GitLgError::io("creating rebase plan directory", source))?;
+        let todo_path = plan_dir.join("todo");
+        for (path, contents) in std::iter::once((todo_path.clone(), todo)).chain(messages) {
+            fs::write(&path, contents)
+                .map_err(|source| GitLgError::io("writing rebase plan", source))?;
         }
 
-        Ok(String::new())
+        let runner = self.rebase_runner().with_env(
+            "GIT_SEQUENCE_EDITOR",
+            format!("cp {}", shell_quote(&todo_path.to_string_lossy())),
+        );
+        let mut args = vec!["rebase".to_string(), "--interactive".to_string()];
+        match &plan.onto {
+            Some(onto) => args.push(onto.clone()),
+            None => args.push("--root".to_string()),
+        }
+        let out = runner.exec(repo_path, &args, true)?;
+        self.finish_rebase_step(repo_path, &args, out)
     }
 
-    fn try_commit_file_patch(
-        &self,
-        repo_path: &Path,
-        commit_hash: &str,
-        file_path: &str,
-        context_lines: usize,
-        split_merge_parents: bool,
-    ) -> Result<Option<String>> {
-        let mut args = vec![
-            "-c".to_string(),
-            "color.ui=never".to_string(),
-            "-c".to_string(),
-            "core.quotePath=false".to_string(),
-            "show".to_string(),
-            "--patch".to_string(),
-            "--no-color".to_string(),
-            "--no-ext-diff".to_string(),
-            "--format=".to_string(),
-            "--find-renames".to_string(),
-            "--find-copies".to_string(),
-            format!("--unified={context_lines}"),
-        ];
-        if split_merge_parents {
-            args.push("-m".to_string());
-        }
-        args.push(commit_hash.to_string());
-        args.push("--".to_string());
-        args.push(file_path.to_string());
+    // Continues a stopped rebase once conflicts are resolved and staged.
+    pub fn continue_rebase(&self, repo_path: &Path) -> Result<RebaseStatus> {
+        self.git.validate_repo(repo_path)?;
+        let args = vec!["rebase".to_string(), "--continue".to_string()];
+        let out = self.rebase_runner().exec(repo_path, &args, true)?;
+        self.finish_rebase_step(repo_path, &args, out)
+    }
 
-        let out = self.git.exec(repo_path, &args, false)?;
-        if out.stdout.trim().is_empty() {
-            return Ok(None);
+    pub fn abort_rebase(&self, repo_path: &Path) -> Result<()> {
+        self.git.validate_repo(repo_path)?;
+        self.git.exec(
+            repo_path,
+            &["rebase".to_string(), "--abort".to_string()],
+            false,
+        )?;
+        self.clear_rebase_plan(repo_path)
+    }
+
+    // Where an interactive rebase stands, read from `.git/rebase-merge`.
+    pub fn rebase_status(&self, repo_path: &Path) -> Result<RebaseStatus> {
+        self.git.validate_repo(repo_path)?;
+        let head = self.resolve_commit(repo_path, "HEAD")?;
+        let state_dir = self.git_dir(repo_path)?.join("rebase-merge");
+        if !state_dir.is_dir() {
+            return Ok(RebaseStatus {
+                state: RebaseState::Completed,
+                head,
+                stopped_at: None,
+                done: 0,
+                total: 0,
+                conflicted_files: Vec::new(),
+            });
         }
-        Ok(Some(out.stdout))
+        let read = |name: &str| fs::read_to_string(state_dir.join(name)).unwrap_or_default();
+        let done = count_todo_items(&read("done"));
+        let conflicted_files = self
+            .working_tree_status(repo_path)?
+            .conflicted()
+            .map(|entry| entry.path.clone())
+            .collect::<Vec<_>>();
+        Ok(RebaseStatus {
+            state: if conflicted_files.is_empty() {
+                RebaseState::Stopped
+            } else {
+                RebaseState::Conflicted
+            },
+            head,
+            stopped_at: Some(read("stopped-sha").trim().to_string())
+                .filter(|hash| !hash.is_empty()),
+            done,
+            total: done + count_todo_items(&read("git-rebase-todo")),
+            conflicted_files,
+        })
     }
 
-    fn run_log(&self, repo_path: &Path, query: &GraphQuery) -> Result<GitOutput> {
-        let mut args = vec![
-            "-c".to_string(),
-            "color.ui=never".to_string(),
-            "log".to_string(),
-            "--date-order".to_string(),
-            "--topo-order".to_string(),
-            "--decorate=full".to_string(),
-            "--color=never".to_string(),
-            format!(
-                "--pretty=format:%H%x1f%h%x1f%P%x1f%an%x1f%ae%x1f%at%x1f%ct%x1f%D%x1f%s%x1f%b%x1e"
-            ),
-            "--no-show-signature".to_string(),
-            "--no-notes".to_string(),
-            "-n".to_string(),
-            query.limit.to_string(),
-            "--skip".to_string(),
-            query.skip.to_string(),
-        ];
+    // The merge, rebase, cherry-pick, revert or bisect left in progress.
+    pub fn repo_operation(&self, repo_path: &Path) -> Result<Option<RepoOperation>> {
+        self.git.validate_repo(repo_path)?;
+        Ok(detect_repo_operation(&self.git_dir(repo_path)?))
+    }
 
-        if query.all_refs {
-            args.push("--all".to_string());
+    // Continues, aborts or skips the operation in progress and returns what is
+    // left of it. A step that fails without moving the operation on (e.g.
+    // continuing with unresolved conflicts) is an error.
+    pub fn control_operation(
+        &self,
+        repo_path: &Path,
+        control: RepoOperationControl,
+    ) -> Result<Option<RepoOperation>> {
+        let Some(before) = self.repo_operation(repo_path)? else {
+            return Err(GitLgError::State(format!(
+                "nothing to {}: no operation in progress",
+                control.label()
+            )));
+        };
+        let args = operation_control_args(before.kind, control)?;
+        let out = self.rebase_runner().exec(repo_path, &args, true)?;
+        let after = self.repo_operation(repo_path)?;
+        if out.exit_code != Some(0) && after.as_ref().is_none_or(|after| *after == before) {
+            return Err(GitLgError::GitCommandFailed {
+                program: self.git.git_binary().to_string(),
+                args,
+                exit_code: out.exit_code,
+                stderr: out.stderr,
+                stdout: out.stdout,
+            });
         }
-        if query.include_stash_ref && self.has_stash_ref(repo_path)? {
-            args.push("refs/stash".to_string());
+        if after.is_none() {
+            self.clear_rebase_plan(repo_path)?;
         }
-        args.extend(query.additional_args.clone());
-        self.git.exec(repo_path, &args, false)
+        Ok(after)
     }
 
-    fn has_stash_ref(&self, repo_path: &Path) -> Result<bool> {
-        let out = self.git.exec(
+    // Starts bisecting between two graph rows: the older one is marked good,
+    // the newer one bad, and git checks out the first commit to test.
+    pub fn start_bisect(
+        &self,
+        repo_path: &Path,
+        first: &GraphRow,
+        second: &GraphRow,
+    ) -> Result<BisectStatus> {
+        if let Some(operation) = self.repo_operation(repo_path)? {
+            return Err(GitLgError::State(format!(
+                "cannot start a bisect: {} in progress",
+                operation.summary()
+            )));
+        }
+        if first.hash == UNCOMMITTED_HASH || second.hash == UNCOMMITTED_HASH {
+            return Err(GitLgError::State(
+                "cannot bisect the uncommitted changes row".to_string(),
+            ));
+        }
+        let (good, bad) = if self.is_ancestor(repo_path, &first.hash, &second.hash)? {
+            (&first.hash, &second.hash)
+        } else if self.is_ancestor(repo_path, &second.hash, &first.hash)? {
+            (&second.hash, &first.hash)
+        } else {
+            return Err(GitLgError::State(format!(
+                "{} and {} are not on one line of history",
+                first.short_hash, second.short_hash
+            )));
+        };
+        self.git.exec(
             repo_path,
             &[
-                "show-ref".to_string(),
-                "--verify".to_string(),
-                "--quiet".to_string(),
-                "refs/stash".to_string(),
+                "bisect".to_string(),
+                "start".to_string(),
+                bad.clone(),
+                good.clone(),
             ],
-            true,
+            false,
         )?;
-        Ok(out.exit_code == Some(0))
+        self.require_bisect_status(repo_path)
     }
 
-    fn read_branches(&self, repo_path: &Path) -> Result<Vec<BranchInfo>> {
-        let out = self.git.exec(
+    // Marks `revision` (the checked-out commit when `None`) and moves on.
+    pub fn mark_bisect(
+        &self,
+        repo_path: &Path,
+        mark: BisectMark,
+        revision: Option<&str>,
+    ) -> Result<BisectStatus> {
+        self.require_bisect_status(repo_path)?;
+        let mut args = vec!["bisect".to_string(), mark.label().to_string()];
+        if let Some(revision) = revision {
+            args.push(self.resolve_commit(repo_path, revision)?);
+        }
+        self.git.exec(repo_path, &args, false)?;
+        self.require_bisect_status(repo_path)
+    }
+
+    // `git bisect run` with `command` as a shell script: exit 0 is good,
+    // 125 skip, 1-127 bad, and anything higher stops the run.
+    pub fn run_bisect(&self, repo_path: &Path, command: &str) -> Result<BisectStatus> {
+        self.require_bisect_status(repo_path)?;
+        if command.trim().is_empty() {
+            return Err(GitLgError::State("bisect run needs a command".to_string()));
+        }
+        self.git.exec(
             repo_path,
             &[
-                "branch".to_string(),
-                "--list".to_string(),
-                "--all".to_string(),
-                "--sort=-committerdate".to_string(),
-                format!("--format=%(upstream:remotename){FIELD_SEP}%(refname)"),
+                "bisect".to_string(),
+                "run".to_string(),
+                "sh".to_string(),
+                "-c".to_string(),
+                command.to_string(),
             ],
             false,
         )?;
-        let branches = out
-            .stdout
-            .lines()
-            .filter_map(|line| {
-                let trimmed = line.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                let mut parts = trimmed.splitn(2, FIELD_SEP);
-                let remote_name = parts.next().map(str::trim).unwrap_or_default();
-                let full_ref = parts.next().map(str::trim).unwrap_or_default();
-                if full_ref.is_empty() {
-                    return None;
-                }
-                let is_remote = full_ref.starts_with("refs/remotes/");
-                let name = full_ref
-                    .strip_prefix("refs/heads/")
-                    .or_else(|| full_ref.strip_prefix("refs/remotes/"))
-                    .unwrap_or(full_ref)
-                    .to_string();
-                Some(BranchInfo {
-                    name,
-                    full_ref: full_ref.to_string(),
-                    is_remote,
-                    remote_name: if remote_name.is_empty() {
-                        None
-                    } else {
-                        Some(remote_name.to_string())
-                    },
-                })
-            })
-            .collect();
-        Ok(branches)
+        self.require_bisect_status(repo_path)
     }
 
-    fn lookup_dynamic_placeholder(
-        &self,
-        repo_path: &Path,
-        placeholder: &str,
-    ) -> Result<Option<String>> {
+    // Ends the bisect and checks out where it started, whatever other
+    // operation is in progress alongside it.
+    pub fn reset_bisect(&self, repo_path: &Path) -> Result<()> {
+        self.require_bisect_status(repo_path)?;
+        self.git.exec(
+            repo_path,
+            &["bisect".to_string(), "reset".to_string()],
+            false,
+        )?;
+        Ok(())
+    }
+
+    // `None` unless a bisect is in progress.
+    pub fn bisect_status(&self, repo_path: &Path) -> Result<Option<BisectStatus>> {
+        self.git.validate_repo(repo_path)?;
+        if !self.git_dir(repo_path)?.join("BISECT_LOG").exists() {
+            return Ok(None);
+        }
+        let refs = self.git.exec(repo_path, &bisect_refs_args(), false)?;
+        let mut status = parse_bisect_refs(&refs.stdout)?;
+        status.current = Some(self.resolve_commit(repo_path, "HEAD")?);
+        if let Some(args) = bisect_candidates_args(&status) {
+            let out = self.git.exec(repo_path, &args, false)?;
+            apply_bisect_candidates(&mut status, &out.stdout);
+        }
+        Ok(Some(status))
+    }
+
+    fn require_bisect_status(&self, repo_path: &Path) -> Result<BisectStatus> {
+        self.bisect_status(repo_path)?
+            .ok_or_else(|| GitLgError::State("no bisect in progress".to_string()))
+    }
+
+    fn is_ancestor(&self, repo_path: &Path, ancestor: &str, descendant: &str) -> Result<bool> {
+        let args = vec![
+            "merge-base".to_string(),
+            "--is-ancestor".to_string(),
+            ancestor.to_string(),
+            descendant.to_string(),
+        ];
+        let out = self.git.exec(repo_path, &args, true)?;
+        match out.exit_code {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(GitLgError::GitCommandFailed {
+                program: self.git.git_binary().to_string(),
+                args,
+                exit_code: out.exit_code,
+                stderr: out.stderr,
+                stdout: out.stdout,
+            }),
+        }
+    }
+
+    pub fn ref_snapshot(&self, repo_path: &Path) -> Result<RefSnapshot> {
+        self.git.validate_repo(repo_path)?;
+        let refs = self.git.exec(repo_path, &ref_snapshot_args(), false)?;
+        // Both exit 1 without output: a detached HEAD, an unborn branch.
+        let head = self.git.exec(
+            repo_path,
+            &[
+                "symbolic-ref".to_string(),
+                "-q".to_string(),
+                "HEAD".to_string(),
+            ],
+            true,
+        )?;
+        let head_oid = self.git.exec(
+            repo_path,
+            &[
+                "rev-parse".to_string(),
+                "-q".to_string(),
+                "--verify".to_string(),
+                "HEAD".to_string(),
+            ],
+            true,
+        )?;
+        let trimmed = |out: GitOutput| {
+            Some(out.stdout.trim().to_string())
+                .filter(|value| out.exit_code == Some(0) && !value.is_empty())
+        };
+        parse_ref_snapshot(&refs.stdout, trimmed(head), trimmed(head_oid))
+    }
+
+    // Plans undoing the latest logged action in this repository that moved
+    // a ref; `None` when there is nothing left to undo.
+    pub fn plan_undo_last(&self, repo_path: &Path) -> Result<Option<UndoPlan>> {
+        let log = self.require_operation_log()?;
+        let Some(record) = log.last_undoable(&self.git_dir(repo_path)?)? else {
+            return Ok(None);
+        };
+        plan_undo(&record, &self.ref_snapshot(repo_path)?).map(Some)
+    }
+
+    // Restores the refs and HEAD from before `plan.operation`. When that moves
+    // the working tree, tracked files must be clean first.
+    pub fn apply_undo(&self, repo_path: &Path, plan: &UndoPlan) -> Result<()> {
+        let log = self.require_operation_log()?;
+        let current = self.ref_snapshot(repo_path)?;
+        let plan = plan_undo(&plan.operation, &current)?;
+        if plan.affects_worktree {
+            let status = self.working_tree_status(repo_path)?;
+            if status.staged().next().is_some() || status.unstaged().next().is_some() {
+                return Err(GitLgError::State(
+                    "undo moves the working tree; commit or stash local changes first".to_string(),
+                ));
+            }
+        }
+        // The working tree moves first, while no ref has changed yet: it is
+        // the step that can fail (untracked files in the way), and a failure
+        // leaves the undo intact for a retry. All refs then move in one
+        // `update-ref` transaction.
+        if let Some(target) = &plan.checkout {
+            let branch = target.starts_with("refs/heads/");
+            let oid = if branch {
+                plan.ref_updates
+                    .iter()
+                    .find(|update| update.name == *target)
+                    .map_or_else(
+                        || current.refs.get(target).cloned(),
+                        |update| update.to.clone(),
+                    )
+                    .ok_or_else(|| GitLgError::State(format!("{target} no longer exists")))?
+            } else {
+                target.clone()
+            };
+            self.switch(repo_path, &["--detach", &oid])?;
+            if let Err(err) = self.update_refs(repo_path, &plan.ref_updates) {
+                let _ = self.switch_back(repo_path, &current);
+                return Err(err);
+            }
+            if branch {
+                self.git.exec(
+                    repo_path,
+                    &[
+                        "symbolic-ref".to_string(),
+                        "HEAD".to_string(),
+                        target.clone(),
+                    ],
+                    false,
+                )?;
+            }
+        } else if let Some(update) = plan
+            .ref_updates
+            .iter()
+            .find(|update| current.head.as_deref() == Some(update.name.as_str()))
+        {
+            // The checked-out branch moves with the working tree.
+            let target = update.to.as_ref().ok_or_else(|| {
+                GitLgError::State(format!("cannot delete the checked-out {}", update.name))
+            })?;
+            self.reset_keep(repo_path, target)?;
+            let others = plan
+                .ref_updates
+                .iter()
+                .filter(|other| other.name != update.name)
+                .cloned()
+                .collect::<Vec<_>>();
+            if let Err(err) = self.update_refs(repo_path, &others) {
+                if let Some(from) = &update.from {
+                    let _ = self.reset_keep(repo_path, from);
+                }
+                return Err(err);
+            }
+        } else {
+            self.update_refs(repo_path, &plan.ref_updates)?;
+        }
+        log.mark_undone(plan.operation.id)
+    }
+
+    fn switch(&self, repo_path: &Path, args: &[&str]) -> Result<()> {
+        let mut command = vec!["switch".to_string()];
+        command.extend(args.iter().map(ToString::to_string));
+        self.git.exec(repo_path, &command, false)?;
+        Ok(())
+    }
+
+    // Best-effort return to where HEAD was before a failed undo.
+    fn switch_back(&self, repo_path: &Path, snapshot: &RefSnapshot) -> Result<()> {
+        match (&snapshot.head, &snapshot.head_oid) {
+            (Some(head), _) => self.switch(
+                repo_path,
+                &[head.strip_prefix("refs/heads/").unwrap_or(head)],
+            ),
+            (None, Some(oid)) => self.switch(repo_path, &["--detach", oid]),
+            (None, None) => Ok(()),
+        }
+    }
+
+    fn reset_keep(&self, repo_path: &Path, target: &str) -> Result<()> {
+        self.git.exec(
+            repo_path,
+            &[
+                "reset".to_string(),
+                "--keep".to_string(),
+                target.to_string(),
+            ],
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn update_refs(&self, repo_path: &Path, updates: &[RefUpdate]) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        self.git.exec_with_input(
+            repo_path,
+            &["update-ref".to_string(), "--stdin".to_string()],
+            &update_ref_script(updates),
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn require_operation_log(&self) -> Result<&OperationLog> {
+        self.operation_log
+            .as_ref()
+            .ok_or_else(|| GitLgError::State("no operation log configured".to_string()))
+    }
+
+    // A failed step that left no rebase in progress (e.g. a dirty worktree
+    // refusing to start) is an error; anything else is a state to report.
+    fn finish_rebase_step(
+        &self,
+        repo_path: &Path,
+        args: &[String],
+        out: GitOutput,
+    ) -> Result<RebaseStatus> {
+        let status = self.rebase_status(repo_path)?;
+        if status.state != RebaseState::Completed {
+            return Ok(status);
+        }
+        if out.exit_code != Some(0) {
+            return Err(GitLgError::GitCommandFailed {
+                program: self.git.git_binary().to_string(),
+                args: args.to_vec(),
+                exit_code: out.exit_code,
+                stderr: out.stderr,
+                stdout: out.stdout,
+            });
+        }
+        self.clear_rebase_plan(repo_path)?;
+        Ok(status)
+    }
+
+    // Never opens an editor: `:` keeps whatever message git prepared.
+    fn rebase_runner(&self) -> GitRunner {
+        self.git.clone().with_env("GIT_EDITOR", ":")
+    }
+
+    fn clear_rebase_plan(&self, repo_path: &Path) -> Result<()> {
+        let plan_dir = self.git_dir(repo_path)?.join(REBASE_PLAN_DIR);
+        if plan_dir.exists() {
+            fs::remove_dir_all(&plan_dir)
+                .map_err(|source| GitLgError::io("removing rebase plan", source))?;
+        }
+        Ok(())
+    }
+
+    fn git_dir(&self, repo_path: &Path) -> Result<PathBuf> {
+        let out = self.git.exec(
+            repo_path,
+            &["rev-parse".to_string(), "--absolute-git-dir".to_string()],
+            false,
+        )?;
+        Ok(PathBuf::from(out.stdout.trim()))
+    }
+
+    pub fn file_history(&self, repo_path: &Path, file: &Path) -> Result<Vec<FileHistoryEntry>> {
+        self.git.validate_repo(repo_path)?;
+        let repo = normalize_repo_path(repo_path);
+        // Accepts paths as listed by `commit_file_changes`, renames included.
+        let repo_file = normalize_numstat_path(
+            &normalize_repo_file_input(&repo, file)
+                .to_string_lossy()
+                .replace('\\', "/"),
+        );
+        let pathspec = vec!["--follow".to_string(), "--".to_string(), repo_file.clone()];
+        let query = GraphQuery {
+            all_refs: false,
+            include_stash_ref: false,
+            additional_args: pathspec.clone(),
+            ..GraphQuery::default()
+        };
+        let log_output = self.run_log(&repo, &query)?;
+        let commits = parse_git_log_records(&log_output.stdout)?;
+        // `--follow` skips unrelated commits without rewriting parents, so the
+        // history is drawn as one chain in log order; rows keep real parents.
+        let older = commits
+            .iter()
+            .skip(1)
+            .map(|commit| Some(commit.hash.clone()))
+            .chain([None])
+            .collect::<Vec<_>>();
+
+        let mut args = vec![
+            "-c".to_string(),
+            "core.quotePath=false".to_string(),
+            "log".to_string(),
+            "--name-status".to_string(),
+            "--no-color".to_string(),
+            "--format=%x1e%H".to_string(),
+        ];
+        args.extend(pathspec);
+        let names = parse_follow_name_status(&self.git.exec(&repo, &args, false)?.stdout);
+
+        let mut builder = GraphBuilder::new();
+        let mut current_path = repo_file;
+        Ok(commits
+            .into_iter()
+            .zip(older)
+            .map(|(commit, older)| {
+                let (path, previous_path) = match names.get(commit.hash.as_str()) {
+                    Some((path, previous_path)) => (path.clone(), previous_path.clone()),
+                    // Merges list no files; they keep the newer commit's name.
+                    None => (current_path.clone(), None),
+                };
+                current_path = previous_path.clone().unwrap_or_else(|| path.clone());
+                FileHistoryEntry {
+                    path,
+                    previous_path,
+                    row: builder.push_with_layout_parents(commit, older.into_iter().collect()),
+                }
+            })
+            .collect())
+    }
+
+    pub fn commit_file_changes(
+        &self,
+        repo_path: &Path,
+        commit_hash: &str,
+    ) -> Result<Vec<FileChange>> {
+        self.git.validate_repo(repo_path)?;
+        if commit_hash == UNCOMMITTED_HASH {
+            return self.uncommitted_file_changes(repo_path);
+        }
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "-c".to_string(),
+                "color.ui=never".to_string(),
+                "-c".to_string(),
+                "core.quotePath=false".to_string(),
+                "show".to_string(),
+                "--numstat".to_string(),
+                "--no-color".to_string(),
+                "--no-ext-diff".to_string(),
+                "--format=".to_string(),
+                "--find-renames".to_string(),
+                "--find-copies".to_string(),
+                commit_hash.to_string(),
+            ],
+            false,
+        )?;
+        Ok(parse_numstat(&out.stdout))
+    }
+
+    pub fn commit_file_patch(
+        &self,
+        repo_path: &Path,
+        commit_hash: &str,
+        file_path: &str,
+        context_lines: usize,
+    ) -> Result<String> {
+        self.git.validate_repo(repo_path)?;
+        if commit_hash == UNCOMMITTED_HASH {
+            return self.uncommitted_file_patch(repo_path, file_path, context_lines);
+        }
+        let normalized_path = normalize_numstat_path(file_path);
+        let mut candidate_paths = vec![file_path.trim().to_string()];
+        if normalized_path != file_path.trim() {
+            candidate_paths.push(normalized_path);
+        }
+
+        for candidate in candidate_paths {
+            if candidate.is_empty() {
+                continue;
+            }
+            if let Some(patch) = self.try_commit_file_patch(
+                repo_path,
+                commit_hash,
+                &candidate,
+                context_lines,
+                false,
+            )? {
+                return Ok(patch);
+            }
+            if let Some(patch) =
+                self.try_commit_file_patch(repo_path, commit_hash, &candidate, context_lines, true)?
+            {
+                return Ok(patch);
+            }
+        }
+
+        Ok(String::new())
+    }
+
+    fn try_commit_file_patch(
+        &self,
+        repo_path: &Path,
+        commit_hash: &str,
+        file_path: &str,
+        context_lines: usize,
+        split_merge_parents: bool,
+    ) -> Result<Option<String>> {
+        let mut args = vec![
+            "-c".to_string(),
+            "color.ui=never".to_string(),
+            "-c".to_string(),
+            "core.quotePath=false".to_string(),
+            "show".to_string(),
+            "--patch".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            "--format=".to_string(),
+            "--find-renames".to_string(),
+            "--find-copies".to_string(),
+            format!("--unified={context_lines}"),
+        ];
+        if split_merge_parents {
+            args.push("-m".to_string());
+        }
+        args.push(commit_hash.to_string());
+        args.push("--".to_string());
+        args.push(file_path.to_string());
+
+        let out = self.git.exec(repo_path, &args, false)?;
+        if out.stdout.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(out.stdout))
+    }
+
+    pub fn compare_revisions(
+        &self,
+        repo_path: &Path,
+        left: &str,
+        right: &str,
+    ) -> Result<RevisionComparison> {
+        self.git.validate_repo(repo_path)?;
+        let left_hash = self.resolve_commit(repo_path, left)?;
+        let right_hash = self.resolve_commit(repo_path, right)?;
+        let base_out = self.git.exec(
+            repo_path,
+            &[
+                "merge-base".to_string(),
+                left_hash.clone(),
+                right_hash.clone(),
+            ],
+            true,
+        )?;
+        let merge_base = (base_out.exit_code == Some(0))
+            .then(|| base_out.stdout.trim().to_string())
+            .filter(|hash| !hash.is_empty());
+        let only_in_left = self.range_rows(repo_path, &format!("{right_hash}..{left_hash}"))?;
+        let only_in_right = self.range_rows(repo_path, &format!("{left_hash}..{right_hash}"))?;
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "-c".to_string(),
+                "core.quotePath=false".to_string(),
+                "diff".to_string(),
+                "--numstat".to_string(),
+                "--no-color".to_string(),
+                "--no-ext-diff".to_string(),
+                "--find-renames".to_string(),
+                "--find-copies".to_string(),
+                left_hash.clone(),
+                right_hash.clone(),
+            ],
+            false,
+        )?;
+        Ok(RevisionComparison {
+            left: left.to_string(),
+            right: right.to_string(),
+            left_hash,
+            right_hash,
+            merge_base,
+            only_in_left,
+            only_in_right,
+            files: parse_numstat(&out.stdout),
+            patches: Vec::new(),
+        })
+    }
+
+    pub fn compare_file_patch(
+        &self,
+        repo_path: &Path,
+        left: &str,
+        right: &str,
+        file_path: &str,
+        context_lines: usize,
+    ) -> Result<String> {
+        self.git.validate_repo(repo_path)?;
+        self.diff_file_patch(repo_path, &[], &[left, right], file_path, context_lines)
+    }
+
+    // The unstaged (index vs worktree) or staged (HEAD vs index) diff of one
+    // file. Untracked files show up as unstaged additions.
+    pub fn working_tree_file_patch(
+        &self,
+        repo_path: &Path,
+        file_path: &str,
+        side: WorktreeDiffSide,
+        context_lines: usize,
+    ) -> Result<String> {
+        self.git.validate_repo(repo_path)?;
+        match side {
+            WorktreeDiffSide::Staged => {
+                self.diff_file_patch(repo_path, &["--cached"], &[], file_path, context_lines)
+            }
+            WorktreeDiffSide::Unstaged => {
+                let patch = self.diff_file_patch(repo_path, &[], &[], file_path, context_lines)?;
+                if !patch.is_empty() {
+                    return Ok(patch);
+                }
+                self.untracked_file_patch(repo_path, file_path, context_lines)
+            }
+        }
+    }
+
+    // Stages, unstages or discards one hunk (or some of its lines) of
+    // `working_tree_file_patch(.., action.side(), HUNK_CONTEXT_LINES)`.
+    pub fn apply_hunk(
+        &self,
+        repo_path: &Path,
+        file_path: &str,
+        action: HunkAction,
+        selection: &HunkSelection,
+    ) -> Result<()> {
+        let file_patch =
+            self.working_tree_file_patch(repo_path, file_path, action.side(), HUNK_CONTEXT_LINES)?;
+        if file_patch.trim().is_empty() {
+            return Err(GitLgError::State(format!(
+                "{file_path} has no {} changes",
+                action.side().label()
+            )));
+        }
+        if action == HunkAction::Discard
+            && file_patch
+                .lines()
+                .take_while(|line| !line.starts_with("@@"))
+                .any(|line| line.starts_with("new file mode"))
+        {
+            return Err(GitLgError::State(format!(
+                "cannot discard hunks of untracked file {file_path}"
+            )));
+        }
+        let reverse = action != HunkAction::Stage;
+        let patch = select_hunk_patch(&file_patch, selection, reverse)?;
+        self.git
+            .exec_with_input(repo_path, &hunk_apply_args(action), &patch, false)?;
+        Ok(())
+    }
+
+    fn diff_file_patch(
+        &self,
+        repo_path: &Path,
+        options: &[&str],
+        revisions: &[&str],
+        file_path: &str,
+        context_lines: usize,
+    ) -> Result<String> {
+        let normalized_path = normalize_numstat_path(file_path);
+        let mut args = vec![
+            "-c".to_string(),
+            "color.ui=never".to_string(),
+            "-c".to_string(),
+            "core.quotePath=false".to_string(),
+            "diff".to_string(),
+            "--patch".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            "--find-renames".to_string(),
+            "--find-copies".to_string(),
+            format!("--unified={context_lines}"),
+        ];
+        args.extend(options.iter().map(ToString::to_string));
+        args.push("--end-of-options".to_string());
+        args.extend(revisions.iter().map(ToString::to_string));
+        args.push("--".to_string());
+        args.push(normalized_path);
+        // A rename needs both sides in the pathspec for git to pair them up.
+        if let Some(old_path) = numstat_old_path(file_path) {
+            args.push(old_path);
+        }
+        let out = self.git.exec(repo_path, &args, false)?;
+        Ok(out.stdout)
+    }
+
+    // Staged and unstaged changes against HEAD, plus untracked files.
+    fn uncommitted_file_changes(&self, repo_path: &Path) -> Result<Vec<FileChange>> {
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "-c".to_string(),
+                "core.quotePath=false".to_string(),
+                "diff".to_string(),
+                "--numstat".to_string(),
+                "--no-color".to_string(),
+                "--no-ext-diff".to_string(),
+                "--find-renames".to_string(),
+                "HEAD".to_string(),
+            ],
+            false,
+        )?;
+        let mut files = parse_numstat(&out.stdout);
+        let repo = normalize_repo_path(repo_path);
+        for entry in self.working_tree_status(repo_path)?.untracked() {
+            // Untracked files count as fully added; binary files have no counts.
+            let added = fs::read(repo.join(&entry.path))
+                .ok()
+                .filter(|bytes| !bytes.contains(&0))
+                .map(|bytes| {
+                    let newlines = bytes.iter().filter(|byte| **byte == b'\n').count();
+                    let unterminated = bytes.last().is_some_and(|byte| *byte != b'\n');
+                    (newlines + usize::from(unterminated)) as u32
+                });
+            files.push(FileChange {
+                path: entry.path.clone(),
+                added,
+                removed: added.map(|_| 0),
+            });
+        }
+        Ok(files)
+    }
+
+    fn uncommitted_file_patch(
+        &self,
+        repo_path: &Path,
+        file_path: &str,
+        context_lines: usize,
+    ) -> Result<String> {
+        let patch = self.diff_file_patch(repo_path, &[], &["HEAD"], file_path, context_lines)?;
+        if !patch.is_empty() {
+            return Ok(patch);
+        }
+        self.untracked_file_patch(repo_path, file_path, context_lines)
+    }
+
+    // The whole file as an addition, or empty when `file_path` is not untracked.
+    fn untracked_file_patch(
+        &self,
+        repo_path: &Path,
+        file_path: &str,
+        context_lines: usize,
+    ) -> Result<String> {
+        let untracked = self.git.exec(
+            repo_path,
+            &[
+                "ls-files".to_string(),
+                "--others".to_string(),
+                "--exclude-standard".to_string(),
+                "--".to_string(),
+                file_path.to_string(),
+            ],
+            false,
+        )?;
+        if untracked.stdout.trim().is_empty() {
+            return Ok(String::new());
+        }
+        // `--no-index` exits with 1 when the files differ.
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "-c".to_string(),
+                "core.quotePath=false".to_string(),
+                "diff".to_string(),
+                "--no-index".to_string(),
+                "--patch".to_string(),
+                "--no-color".to_string(),
+                "--no-ext-diff".to_string(),
+                format!("--unified={context_lines}"),
+                "--".to_string(),
+                "/dev/null".to_string(),
+                file_path.to_string(),
+            ],
+            true,
+        )?;
+        Ok(out.stdout)
+    }
+
+    pub fn compare_patches(
+        &self,
+        repo_path: &Path,
+        comparison: &RevisionComparison,
+        context_lines: usize,
+    ) -> Result<Vec<FilePatch>> {
+        comparison
+            .files
+            .iter()
+            .map(|file| {
+                Ok(FilePatch {
+                    path: file.path.clone(),
+                    patch: self.compare_file_patch(
+                        repo_path,
+                        &comparison.left_hash,
+                        &comparison.right_hash,
+                        &file.path,
+                        context_lines,
+                    )?,
+                })
+            })
+            .collect()
+    }
+
+    fn resolve_commit(&self, repo_path: &Path, revision: &str) -> Result<String> {
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "rev-parse".to_string(),
+                "--verify".to_string(),
+                "--end-of-options".to_string(),
+                format!("{revision}^{{commit}}"),
+            ],
+            false,
+        )?;
+        Ok(out.stdout.trim().to_string())
+    }
+
+    fn range_rows(&self, repo_path: &Path, range: &str) -> Result<Vec<GraphRow>> {
+        let query = GraphQuery {
+            all_refs: false,
+            include_stash_ref: false,
+            additional_args: vec![range.to_string()],
+            ..GraphQuery::default()
+        };
+        let log_output = self.run_log(repo_path, &query)?;
+        let mut builder = GraphBuilder::new();
+        Ok(parse_git_log_records(&log_output.stdout)?
+            .into_iter()
+            .map(|commit| builder.push(commit))
+            .collect())
+    }
+
+    // Laid out before the log so the row's edge to HEAD claims HEAD's lane.
+    fn uncommitted_row(
+        &self,
+        repo_path: &Path,
+        query: &GraphQuery,
+        builder: &mut GraphBuilder,
+    ) -> Result<Option<GraphRow>> {
+        if !query.include_uncommitted || query.skip > 0 {
+            return Ok(None);
+        }
+        let status = self.working_tree_status(repo_path)?;
+        let Some(head) = status.branch.oid.clone().filter(|_| !status.is_clean()) else {
+            return Ok(None);
+        };
+        let now = current_unix_timestamp();
+        // HEAD is decorated, so the row never waits on chain collapse.
+        Ok(Some(builder.push(RawCommit {
+            hash: UNCOMMITTED_HASH.to_string(),
+            short_hash: "*".to_string(),
+            parents: vec![head],
+            author_name: String::new(),
+            author_email: String::new(),
+            authored_unix: now,
+            committed_unix: now,
+            committer_name: String::new(),
+            committer_email: String::new(),
+            author_tz_offset_minutes: 0,
+            committer_tz_offset_minutes: 0,
+            refs: Vec::new(),
+            subject: format!("Uncommitted changes ({})", status.summary()),
+            body: String::new(),
+            notes: String::new(),
+            signature: None,
+        })))
+    }
+
+    fn graph_builder(&self, query: &GraphQuery) -> GraphBuilder {
+        let builder = GraphBuilder::for_mode(query.mode);
+        if query.mode != GraphMode::CollapseLinear {
+            return builder;
+        }
+        // Child counts must include the skipped commits, so `log_args` reads
+        // from the top of history and the collapse passes over them itself.
+        builder.with_chain_collapse(ChainCollapse::new(CHAIN_COLLAPSE_LOOKAHEAD, query.skip))
+    }
+
+    fn run_log(&self, repo_path: &Path, query: &GraphQuery) -> Result<GitOutput> {
+        let args = self.log_args(repo_path, query)?;
+        self.git.exec(repo_path, &args, false)
+    }
+
+    fn log_args(&self, repo_path: &Path, query: &GraphQuery) -> Result<Vec<String>> {
+        let mut args = vec![
+            "-c".to_string(),
+            "color.ui=never".to_string(),
+            "log".to_string(),
+            "--date-order".to_string(),
+            "--topo-order".to_string(),
+            "--decorate=full".to_string(),
+            "--color=never".to_string(),
+            log_format(!query.notes_refs.is_empty(), query.verify_signatures),
+            "--no-show-signature".to_string(),
+            "--no-notes".to_string(),
+        ];
+        args.extend(
+            query
+                .notes_refs
+                .iter()
+                .map(|notes_ref| format!("--notes={notes_ref}")),
+        );
+        // See `graph_builder` for why collapsing does its own skipping.
+        let (limit, skip) = match query.mode {
+            GraphMode::CollapseLinear => (query.limit.saturating_add(query.skip), 0),
+            _ => (query.limit, query.skip),
+        };
+        args.extend([
+            "-n".to_string(),
+            limit.to_string(),
+            "--skip".to_string(),
+            skip.to_string(),
+        ]);
+
+        match query.mode {
+            GraphMode::Full | GraphMode::CollapseLinear => {}
+            GraphMode::FirstParent => args.push("--first-parent".to_string()),
+            GraphMode::DecoratedOnly => args.push("--simplify-by-decoration".to_string()),
+        }
+        if query.all_refs {
+            // Notes refs point at bookkeeping commits, not project history.
+            args.push("--exclude=refs/notes/*".to_string());
+            args.push("--all".to_string());
+        }
+        if query.include_stash_ref && self.has_stash_ref(repo_path)? {
+            args.push("refs/stash".to_string());
+        }
+        args.extend(query.additional_args.clone());
+        Ok(args)
+    }
+
+    fn has_stash_ref(&self, repo_path: &Path) -> Result<bool> {
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "show-ref".to_string(),
+                "--verify".to_string(),
+                "--quiet".to_string(),
+                "refs/stash".to_string(),
+            ],
+            true,
+        )?;
+        Ok(out.exit_code == Some(0))
+    }
+
+    fn read_branches(&self, repo_path: &Path) -> Result<Vec<BranchInfo>> {
+        let out = self.git.exec(
+            repo_path,
+            &[
+                "branch".to_string(),
+                "--list".to_string(),
+                "--all".to_string(),
+                "--sort=-committerdate".to_string(),
+                BRANCH_LIST_FORMAT.to_string(),
+            ],
+            false,
+        )?;
+        Ok(parse_branch_list(&out.stdout))
+    }
+
+    fn lookup_dynamic_placeholder(
+        &self,
+        repo_path: &Path,
+        placeholder: &str,
+    ) -> Result<Option<String>> {
         if let Some(key) = placeholder.strip_prefix("GIT_CONFIG:") {
             let out = self.git.exec(
                 repo_path,
@@ -613,6 +1711,61 @@ fn normalize_repo_file_input(repo_root: &Path, file: &Path) -> PathBuf {
     file.to_path_buf()
 }
 
+// Parses `git log --follow --name-status --format=%x1e%H` into
+// `hash -> (path, previous path for renames and copies)`.
+fn parse_follow_name_status(stdout: &str) -> HashMap<String, (String, Option<String>)> {
+    let mut names = HashMap::new();
+    for record in stdout.split('\u{001e}') {
+        let mut lines = record
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let Some(hash) = lines.next() else {
+            continue;
+        };
+        let Some(status_line) = lines.next() else {
+            continue;
+        };
+        let fields = status_line.split('\t').collect::<Vec<_>>();
+        let entry = match fields.as_slice() {
+            [status, old, new] if status.starts_with(['R', 'C']) => {
+                (new.to_string(), Some(old.to_string()))
+            }
+            [_, path, ..] => (path.to_string(), None),
+            _ => continue,
+        };
+        names.insert(hash.to_string(), entry);
+    }
+    names
+}
+
+fn parse_numstat(stdout: &str) -> Vec<FileChange> {
+    let mut files = Vec::new();
+    for line in stdout.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let mut parts = trimmed.splitn(3, '\t');
+        let Some(added_raw) = parts.next() else {
+            continue;
+        };
+        let Some(removed_raw) = parts.next() else {
+            continue;
+        };
+        let Some(path) = parts.next() else {
+            continue;
+        };
+
+        files.push(FileChange {
+            path: path.to_string(),
+            added: parse_numstat_value(added_raw),
+            removed: parse_numstat_value(removed_raw),
+        });
+    }
+    files
+}
+
 fn parse_numstat_value(raw: &str) -> Option<u32> {
     if raw == "-" {
         return None;
@@ -621,6 +1774,15 @@ fn parse_numstat_value(raw: &str) -> Option<u32> {
 }
 
 fn normalize_numstat_path(raw: &str) -> String {
+    numstat_path_side(raw, true)
+}
+
+// The pre-rename path of an `old => new` numstat entry, if it is a rename.
+fn numstat_old_path(raw: &str) -> Option<String> {
+    raw.contains(" => ").then(|| numstat_path_side(raw, false))
+}
+
+fn numstat_path_side(raw: &str, new_side: bool) -> String {
     let mut path = raw.trim().trim_matches('"').to_string();
     if path.is_empty() {
         return path;
@@ -636,8 +1798,8 @@ fn normalize_numstat_path(raw: &str) -> String {
             {
                 let end = i + 1 + close;
                 let inner = chars[i + 1..end].iter().collect::<String>();
-                if let Some((_, rhs)) = inner.split_once(" => ") {
-                    out.push_str(rhs.trim());
+                if let Some((lhs, rhs)) = inner.split_once(" => ") {
+                    out.push_str(if new_side { rhs.trim() } else { lhs.trim() });
                     i = end + 1;
                     continue;
                 }
@@ -648,26 +1810,36 @@ fn normalize_numstat_path(raw: &str) -> String {
         path = out;
     }
 
-    if let Some((_, rhs)) = path.rsplit_once(" => ") {
-        path = rhs.trim().to_string();
+    if let Some((lhs, rhs)) = path.rsplit_once(" => ") {
+        path = if new_side { rhs } else { lhs }.trim().to_string();
     }
 
-    path
+    // `{old => }` style renames leave an empty component behind.
+    path.replace("//", "/").trim_start_matches('/').to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::{HashMap, HashSet};
     use std::fs;
+    use std::path::Path;
     use std::process::Command;
 
     use tempfile::TempDir;
 
     use crate::actions::{ActionContext, ActionRequest};
-    use crate::models::{CommitSearchQuery, GraphQuery};
+    use crate::models::{
+        BisectMark, BlameOptions, CommitOptions, CommitSearchQuery, GraphMode, GraphQuery,
+        GraphRow, HunkAction, HunkSelection, OperationRecord, RebaseState, RebaseTodoAction,
+        RepoOperationControl, RepoOperationKind, SignatureStatus, UNCOMMITTED_HASH,
+        WorktreeDiffSide,
+    };
+    use crate::oplog::OperationLog;
+    use crate::rebase::plan_rebase;
 
     use super::GitLgService;
     use super::GitRunner;
+    use super::REBASE_PLAN_DIR;
 
     fn has_git() -> bool {
         Command::new("git")
@@ -732,8 +1904,434 @@ mod tests {
         let graph = service
             .graph(tmp.path(), &GraphQuery::default())
             .expect("graph builds");
-        assert!(!graph.commits.is_empty());
-        assert_eq!(graph.commits[0].subject, "init");
+        assert!(!graph.commits.is_empty());
+        assert_eq!(graph.commits[0].subject, "init");
+    }
+
+    #[test]
+    fn streams_graph_in_batches() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        for idx in 0..4 {
+            commit_file(&tmp, "a.txt", &format!("{idx}\n"), &format!("change {idx}"));
+        }
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let full = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph builds");
+        let (first_page, mut stream) = service
+            .graph_paged(tmp.path(), &GraphQuery::default(), 2)
+            .expect("paged graph");
+        assert_eq!(first_page.commits.len(), 2);
+        assert!(!stream.is_finished());
+
+        let mut rows = first_page.commits;
+        for batch in stream.by_ref() {
+            let batch = batch.expect("batch");
+            assert!(batch.len() <= 2);
+            rows.extend(batch);
+        }
+        assert!(stream.is_finished());
+        assert_eq!(rows, full.commits);
+    }
+
+    #[test]
+    fn graph_modes_rewrite_parents_onto_visible_rows() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git command");
+        };
+        commit_file(&tmp, "a.txt", "1\n", "c1");
+        git(&["checkout", "-b", "feature"]);
+        commit_file(&tmp, "f.txt", "1\n", "f1");
+        commit_file(&tmp, "f.txt", "2\n", "f2");
+        git(&["checkout", "-"]);
+        commit_file(&tmp, "a.txt", "2\n", "c2");
+        git(&[
+            "merge",
+            "--no-ff",
+            "-m",
+            "Merge branch 'feature'",
+            "feature",
+        ]);
+        commit_file(&tmp, "a.txt", "3\n", "c3");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let load = |mode| {
+            let query = GraphQuery {
+                mode,
+                ..GraphQuery::default()
+            };
+            let graph = service.graph(tmp.path(), &query).expect("graph builds");
+            let hashes = graph
+                .commits
+                .iter()
+                .map(|row| row.hash.clone())
+                .collect::<HashSet<_>>();
+            for row in &graph.commits {
+                assert!(
+                    row.parents.iter().all(|parent| hashes.contains(parent)),
+                    "{mode:?}: {} points outside the graph",
+                    row.subject
+                );
+            }
+            let (_, mut stream) = service
+                .graph_paged(tmp.path(), &query, 2)
+                .expect("paged graph");
+            assert_eq!(
+                stream.collect_remaining().expect("remaining").len() + 2,
+                graph.commits.len()
+            );
+            graph
+                .commits
+                .into_iter()
+                .map(|row| (row.subject, row.parents.len(), row.collapsed_commits))
+                .collect::<Vec<_>>()
+        };
+
+        let first_parent = load(GraphMode::FirstParent);
+        assert!(first_parent.iter().all(|(_, parents, _)| *parents <= 1));
+        assert!(
+            first_parent
+                .iter()
+                .any(|(subject, parents, _)| subject.starts_with("Merge") && *parents == 1)
+        );
+
+        let decorated = load(GraphMode::DecoratedOnly);
+        assert!(decorated.len() < first_parent.len());
+
+        let collapsed = load(GraphMode::CollapseLinear);
+        assert_eq!(
+            collapsed,
+            vec![
+                ("c3".to_string(), 1, 0),
+                ("Merge branch 'feature'".to_string(), 2, 1),
+                ("f2".to_string(), 1, 1),
+                ("c1".to_string(), 1, 0),
+                ("init".to_string(), 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn loads_notes_from_selected_refs() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        Command::new("git")
+            .args(["notes", "--ref", "ci", "add", "-m", "ci: passed", "HEAD"])
+            .current_dir(tmp.path())
+            .output()
+            .expect("git notes add");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let without_notes = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph builds");
+        assert_eq!(without_notes.commits[0].notes, "");
+
+        let with_notes = service
+            .graph(
+                tmp.path(),
+                &GraphQuery {
+                    notes_refs: vec!["ci".to_string()],
+                    ..GraphQuery::default()
+                },
+            )
+            .expect("graph builds");
+        assert_eq!(with_notes.commits.len(), 1);
+        assert_eq!(with_notes.commits[0].notes, "ci: passed");
+        assert_eq!(with_notes.commits[0].subject, "init");
+    }
+
+    #[test]
+    fn verifies_ssh_signatures_when_requested() {
+        let has_ssh_keygen = Command::new("ssh-keygen").arg("-?").output().is_ok();
+        if !has_git() || !has_ssh_keygen {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        let keys = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let key_path = keys.path().join("id_ed25519");
+        let keygen = Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+            .arg(&key_path)
+            .output()
+            .expect("ssh-keygen");
+        assert!(keygen.status.success());
+        let public_key =
+            fs::read_to_string(key_path.with_extension("pub")).expect("read public key");
+        let allowed_signers = keys.path().join("allowed_signers");
+        fs::write(
+            &allowed_signers,
+            format!("test@example.com {}", public_key.trim()),
+        )
+        .expect("write allowed signers");
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+            assert!(
+                out.status.success(),
+                "{}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+        };
+        git(&["config", "gpg.format", "ssh"]);
+        git(&[
+            "config",
+            "user.signingkey",
+            key_path.to_str().expect("utf-8 path"),
+        ]);
+        git(&[
+            "config",
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().expect("utf-8 path"),
+        ]);
+        fs::write(tmp.path().join("signed.txt"), "signed\n").expect("write file");
+        git(&["add", "signed.txt"]);
+        git(&["commit", "-S", "-m", "signed change"]);
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let unverified = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph builds");
+        assert!(unverified.commits.iter().all(|row| row.signature.is_none()));
+
+        let verified = service
+            .graph(
+                tmp.path(),
+                &GraphQuery {
+                    verify_signatures: true,
+                    ..GraphQuery::default()
+                },
+            )
+            .expect("graph builds");
+        let signed = verified
+            .commits
+            .iter()
+            .find(|row| row.subject == "signed change")
+            .and_then(|row| row.signature.clone())
+            .expect("signed commit has signature info");
+        assert_eq!(signed.status, SignatureStatus::Good);
+        assert_eq!(signed.signer, "test@example.com");
+        assert!(!signed.key_id.is_empty());
+        let unsigned = verified
+            .commits
+            .iter()
+            .find(|row| row.subject == "init")
+            .and_then(|row| row.signature.clone())
+            .expect("unsigned commit has signature info");
+        assert_eq!(unsigned.status, SignatureStatus::None);
+    }
+
+    #[test]
+    fn compares_two_revisions() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+            assert!(
+                out.status.success(),
+                "{}",
+                String::from_utf8_lossy(&out.stderr)
+            );
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        let base = git(&["rev-parse", "HEAD"]);
+        git(&["checkout", "-q", "-b", "feature"]);
+        commit_file(&tmp, "b.txt", "feature\n", "feature change");
+        git(&["checkout", "-q", "-"]);
+        commit_file(&tmp, "a.txt", "main\n", "main change");
+        let main = git(&["rev-parse", "--abbrev-ref", "HEAD"]);
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let comparison = service
+            .compare_revisions(tmp.path(), &main, "feature")
+            .expect("compare");
+        assert_eq!(comparison.merge_base.as_deref(), Some(base.as_str()));
+        let subjects = |rows: &[crate::models::GraphRow]| {
+            rows.iter()
+                .map(|row| row.subject.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(subjects(&comparison.only_in_left), vec!["main change"]);
+        assert_eq!(subjects(&comparison.only_in_right), vec!["feature change"]);
+        let mut paths = comparison
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+        assert_eq!(paths, vec!["a.txt", "b.txt"]);
+
+        let patches = service
+            .compare_patches(tmp.path(), &comparison, 3)
+            .expect("patches");
+        let b_patch = patches
+            .iter()
+            .find(|patch| patch.path == "b.txt")
+            .expect("b.txt patch");
+        assert!(b_patch.patch.contains("+feature"));
+        assert!(
+            service
+                .compare_revisions(tmp.path(), "no-such-ref", "feature")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn follows_file_history_across_renames() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        commit_file(&tmp, "a.txt", "init\nsecond line\n", "edit a");
+        commit_file(&tmp, "other.txt", "unrelated\n", "unrelated change");
+        let mv = Command::new("git")
+            .args(["mv", "a.txt", "b.txt"])
+            .current_dir(tmp.path())
+            .output()
+            .expect("git mv");
+        assert!(mv.status.success());
+        Command::new("git")
+            .args(["commit", "-q", "-m", "rename a to b"])
+            .current_dir(tmp.path())
+            .output()
+            .expect("git commit");
+        commit_file(&tmp, "b.txt", "init\nsecond line\nthird\n", "edit b");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let history = service
+            .file_history(tmp.path(), Path::new("b.txt"))
+            .expect("file history");
+        let summary = history
+            .iter()
+            .map(|entry| {
+                (
+                    entry.row.subject.as_str(),
+                    entry.path.as_str(),
+                    entry.previous_path.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("edit b", "b.txt", None),
+                ("rename a to b", "b.txt", Some("a.txt")),
+                ("edit a", "a.txt", None),
+                ("init", "a.txt", None),
+            ]
+        );
+        assert!(history.iter().all(|entry| entry.row.lane == 0));
+        assert_eq!(history[0].row.parents, vec![history[1].row.hash.clone()]);
+        // The rename sits on the unrelated commit, which the history skips.
+        assert_eq!(history[1].row.parents.len(), 1);
+        assert_ne!(history[1].row.parents, vec![history[2].row.hash.clone()]);
+        assert!(history[3].row.parents.is_empty());
+    }
+
+    #[test]
+    fn tracks_line_range_history_with_hunks() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        commit_file(&tmp, "a.txt", "init\nfn f() {\n    1\n}\n", "add f");
+        commit_file(&tmp, "other.txt", "unrelated\n", "unrelated change");
+        commit_file(&tmp, "a.txt", "init\nfn f() {\n    2\n}\n", "change f");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let history = service
+            .line_history(tmp.path(), Path::new("a.txt"), 2, 4)
+            .expect("line history");
+        let subjects = history
+            .iter()
+            .map(|entry| entry.row.subject.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(subjects[..2], ["change f", "add f"]);
+        assert!(!subjects.contains(&"unrelated change"));
+        assert!(history.iter().all(|entry| entry.path == "a.txt"));
+        let hunk = &history[0].hunks[0];
+        assert_eq!((hunk.new_start, hunk.new_lines), (2, 3));
+        assert!(hunk.lines.contains(&"-    1".to_string()));
+        assert!(hunk.lines.contains(&"+    2".to_string()));
+        // "change f" keeps the unrelated commit as its parent.
+        assert_eq!(history[0].row.parents.len(), 1);
+        assert_ne!(history[0].row.parents, vec![history[1].row.hash.clone()]);
+        assert!(history.iter().all(|entry| entry.row.lane == 0));
+    }
+
+    #[test]
+    fn reports_upstream_tracking_for_local_branches() {
+        if !has_git() {
+            return;
+        }
+        let origin = TempDir::new().expect("tempdir");
+        init_repo(&origin);
+        let tmp = TempDir::new().expect("tempdir");
+        Command::new("git")
+            .arg("clone")
+            .arg(origin.path())
+            .arg(tmp.path())
+            .output()
+            .expect("git clone");
+        init_repo(&tmp);
+        commit_file(&tmp, "b.txt", "local\n", "local change");
+        commit_file(&origin, "a.txt", "upstream\n", "upstream change");
+        Command::new("git")
+            .args(["fetch", "origin"])
+            .current_dir(tmp.path())
+            .output()
+            .expect("git fetch");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let graph = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph builds");
+        let local = graph
+            .branches
+            .iter()
+            .find(|branch| !branch.is_remote)
+            .expect("local branch");
+        assert_eq!(local.remote_name.as_deref(), Some("origin"));
+        assert!(local.upstream.is_some());
+        assert_eq!((local.ahead, local.behind), (1, 1));
+        assert!(!local.upstream_gone);
+        let local_tip = graph
+            .commits
+            .iter()
+            .find(|row| row.subject == "local change")
+            .expect("local commit");
+        assert_eq!(local.tip_hash, local_tip.hash);
+        assert!(local.last_commit_unix > 0);
     }
 
     #[test]
@@ -772,6 +2370,475 @@ mod tests {
         assert_eq!(blame.author_name, "Test");
     }
 
+    #[test]
+    fn blames_whole_file_with_ignore_revs_and_copy_detection() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let head = || {
+            let out = Command::new("git")
+                .args(["rev-parse", "HEAD"])
+                .current_dir(tmp.path())
+                .output()
+                .expect("git rev-parse");
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        let body = "let first_value = compute_first_value();\nlet second_value = compute_second_value();\n";
+        commit_file(&tmp, "a.txt", &format!("init\n{body}"), "add values");
+        let added = head();
+        commit_file(&tmp, "a.txt", &format!("init \n{body}"), "reformat");
+        let reformat = head();
+        let service = GitLgService::with_default_actions(GitRunner::default());
+
+        let blame = service
+            .blame_file(tmp.path(), Path::new("a.txt"), &BlameOptions::default())
+            .expect("blame file");
+        assert_eq!(blame.lines.len(), 3);
+        assert_eq!(blame.lines[0].commit_hash, reformat);
+        assert_eq!(blame.lines[0].content, "init ");
+        assert_eq!(blame.lines[2].commit_hash, added);
+        assert_eq!(blame.lines[2].line, 3);
+        let added_group = blame
+            .commits
+            .iter()
+            .find(|commit| commit.hash == added)
+            .expect("grouped commit");
+        assert_eq!(added_group.line_count, 2);
+        assert_eq!(added_group.summary, "add values");
+
+        let ignoring = service
+            .blame_file(
+                tmp.path(),
+                Path::new("a.txt"),
+                &BlameOptions {
+                    ignore_revs: vec![reformat.clone()],
+                    ..BlameOptions::default()
+                },
+            )
+            .expect("blame ignoring reformat");
+        assert!(
+            ignoring
+                .lines
+                .iter()
+                .all(|line| line.commit_hash != reformat)
+        );
+
+        fs::write(tmp.path().join("a.txt"), "init \n").expect("write a.txt");
+        Command::new("git")
+            .args(["add", "a.txt"])
+            .current_dir(tmp.path())
+            .output()
+            .expect("git add a.txt");
+        commit_file(&tmp, "b.txt", body, "move values to b");
+        let copies = service
+            .blame_file(
+                tmp.path(),
+                Path::new("b.txt"),
+                &BlameOptions {
+                    detect_copies: true,
+                    ..BlameOptions::default()
+                },
+            )
+            .expect("blame with copy detection");
+        assert!(copies.lines.iter().all(|line| line.commit_hash == added));
+        assert_eq!(copies.lines[0].original_path, "a.txt");
+        assert_eq!(copies.lines[0].original_line, 2);
+    }
+
+    #[test]
+    fn reads_working_tree_status() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+            assert!(out.status.success(), "git {args:?} failed");
+        };
+        commit_file(&tmp, "keep.txt", "keep\n", "add keep");
+        git(&["mv", "keep.txt", "kept.txt"]);
+        fs::write(tmp.path().join("a.txt"), "changed\n").expect("write a.txt");
+        fs::write(tmp.path().join("new file.txt"), "new\n").expect("write new file");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let status = service
+            .working_tree_status(tmp.path())
+            .expect("working tree status");
+        assert!(status.branch.oid.is_some());
+        assert!(status.branch.head.is_some());
+        let lines = status
+            .entries
+            .iter()
+            .map(|entry| entry.short_line())
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            lines,
+            HashSet::from([
+                " M a.txt".to_string(),
+                "R  keep.txt -> kept.txt".to_string(),
+                "?? new file.txt".to_string(),
+            ])
+        );
+        assert_eq!(status.summary(), "1 staged, 1 unstaged, 1 untracked");
+    }
+
+    #[test]
+    fn graph_includes_uncommitted_row_with_files_and_patches() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        fs::write(tmp.path().join("a.txt"), "a\nchanged\n").expect("write a.txt");
+        fs::write(tmp.path().join("new.txt"), "one\ntwo\n").expect("write new.txt");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let head = service
+            .resolve_commit(tmp.path(), "HEAD")
+            .expect("resolve HEAD");
+        let query = GraphQuery {
+            include_uncommitted: true,
+            ..GraphQuery::default()
+        };
+        let graph = service.graph(tmp.path(), &query).expect("graph");
+        let first = &graph.commits[0];
+        assert!(first.is_uncommitted());
+        assert_eq!(first.parents, vec![head.clone()]);
+        assert_eq!(first.lane, graph.commits[1].lane);
+        assert_eq!(graph.commits[1].hash, head);
+        assert!(first.subject.contains("1 unstaged, 1 untracked"));
+
+        let (paged, _) = service
+            .graph_paged(tmp.path(), &query, 10)
+            .expect("paged graph");
+        assert!(paged.commits[0].is_uncommitted());
+        let plain = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("plain graph");
+        assert!(!plain.commits.iter().any(GraphRow::is_uncommitted));
+
+        let files = service
+            .commit_file_changes(tmp.path(), UNCOMMITTED_HASH)
+            .expect("uncommitted files");
+        let counts = files
+            .iter()
+            .map(|file| (file.path.as_str(), (file.added, file.removed)))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(counts["a.txt"], (Some(1), Some(0)));
+        assert_eq!(counts["new.txt"], (Some(2), Some(0)));
+
+        let patch = service
+            .commit_file_patch(tmp.path(), UNCOMMITTED_HASH, "a.txt", 3)
+            .expect("modified patch");
+        assert!(patch.contains("+changed"));
+        let patch = service
+            .commit_file_patch(tmp.path(), UNCOMMITTED_HASH, "new.txt", 3)
+            .expect("untracked patch");
+        assert!(patch.contains("new file mode"));
+        assert!(patch.contains("+two"));
+
+        fs::remove_file(tmp.path().join("new.txt")).expect("remove new.txt");
+        fs::write(tmp.path().join("a.txt"), "a\n").expect("restore a.txt");
+        let clean = service.graph(tmp.path(), &query).expect("clean graph");
+        assert!(!clean.commits[0].is_uncommitted());
+    }
+
+    #[test]
+    fn commits_staged_changes_with_amend_fixup_and_signoff() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+            assert!(out.status.success(), "git {args:?} failed");
+            String::from_utf8_lossy(&out.stdout).to_string()
+        };
+        fs::write(tmp.path().join("a.txt"), "a\nstaged\n").expect("write a.txt");
+        fs::write(tmp.path().join("b.txt"), "unstaged\n").expect("write b.txt");
+        git(&["add", "a.txt"]);
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let head = service
+            .resolve_commit(tmp.path(), "HEAD")
+            .expect("resolve HEAD");
+        let row = service
+            .commit(
+                tmp.path(),
+                &CommitOptions {
+                    message: "Stage only a\n\nWith a body".to_string(),
+                    signoff: true,
+                    author: Some("Ann <ann@example.com>".to_string()),
+                    ..CommitOptions::default()
+                },
+            )
+            .expect("commit staged");
+        assert_eq!(row.subject, "Stage only a");
+        assert_eq!(row.parents, vec![head]);
+        assert_eq!(row.author_email, "ann@example.com");
+        assert!(row.refs.iter().any(|r| r.name.ends_with("HEAD")));
+        assert!(git(&["log", "-1", "--format=%b"]).contains("Signed-off-by:"));
+        assert_eq!(git(&["status", "--porcelain"]), "?? b.txt\n");
+
+        let empty = service.commit(
+            tmp.path(),
+            &CommitOptions {
+                message: "nothing".to_string(),
+                ..CommitOptions::default()
+            },
+        );
+        assert!(empty.is_err());
+
+        let amended = service
+            .commit(
+                tmp.path(),
+                &CommitOptions {
+                    message: "Stage a and b".to_string(),
+                    stage_all: true,
+                    amend: true,
+                    ..CommitOptions::default()
+                },
+            )
+            .expect("amend");
+        assert_eq!(amended.subject, "Stage a and b");
+        assert_eq!(amended.parents, row.parents);
+        assert_eq!(git(&["status", "--porcelain"]), "");
+
+        fs::write(tmp.path().join("b.txt"), "fixed\n").expect("edit b.txt");
+        let fixup = service
+            .commit(
+                tmp.path(),
+                &CommitOptions {
+                    stage_all: true,
+                    fixup: Some(amended.hash.clone()),
+                    ..CommitOptions::default()
+                },
+            )
+            .expect("fixup");
+        assert_eq!(fixup.subject, "fixup! Stage a and b");
+        assert_eq!(fixup.parents, vec![amended.hash]);
+    }
+
+    #[test]
+    fn runs_rebase_plans_and_reports_stops() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        for n in 1..=4 {
+            commit_file(&tmp, &format!("f{n}.txt"), "x\n", &format!("c{n}"));
+        }
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let subjects = |service: &GitLgService| {
+            service
+                .graph(tmp.path(), &GraphQuery::default())
+                .expect("graph")
+                .commits
+                .into_iter()
+                .map(|row| row.subject)
+                .collect::<Vec<_>>()
+        };
+        let rows = service
+            .graph(
+                tmp.path(),
+                &GraphQuery {
+                    limit: 4,
+                    ..GraphQuery::default()
+                },
+            )
+            .expect("graph")
+            .commits;
+        let mut plan = plan_rebase(&rows).expect("plan");
+        plan.items[0].action = RebaseTodoAction::Reword;
+        plan.items[0].message = Some("First file".to_string());
+        plan.items[2].action = RebaseTodoAction::Squash;
+        plan.items[2].message = Some("Second and third\n\n#42 stays in the body".to_string());
+        plan.items[3].action = RebaseTodoAction::Drop;
+
+        fs::write(tmp.path().join("f1.txt"), "dirty\n").expect("dirty f1");
+        assert!(service.run_rebase(tmp.path(), &plan).is_err());
+        fs::write(tmp.path().join("f1.txt"), "x\n").expect("restore f1");
+
+        let status = service.run_rebase(tmp.path(), &plan).expect("rebase");
+        assert_eq!(status.state, RebaseState::Completed);
+        assert_eq!(
+            subjects(&service),
+            vec!["Second and third", "First file", "init"]
+        );
+        let body = Command::new("git")
+            .args(["log", "-1", "--format=%b"])
+            .current_dir(tmp.path())
+            .output()
+            .expect("git log");
+        assert_eq!(
+            String::from_utf8_lossy(&body.stdout).trim(),
+            "#42 stays in the body"
+        );
+        assert!(tmp.path().join("f3.txt").exists());
+        assert!(!tmp.path().join("f4.txt").exists());
+        assert!(!tmp.path().join(".git").join(REBASE_PLAN_DIR).exists());
+
+        // Swapping two edits of the same file conflicts.
+        commit_file(&tmp, "x.txt", "1\n", "x1");
+        commit_file(&tmp, "x.txt", "2\n", "x2");
+        let rows = service
+            .graph(
+                tmp.path(),
+                &GraphQuery {
+                    limit: 2,
+                    ..GraphQuery::default()
+                },
+            )
+            .expect("graph")
+            .commits;
+        let head = rows[0].hash.clone();
+        let mut plan = plan_rebase(&rows).expect("plan");
+        plan.items.swap(0, 1);
+        let status = service.run_rebase(tmp.path(), &plan).expect("rebase");
+        assert_eq!(status.state, RebaseState::Conflicted);
+        assert_eq!(status.conflicted_files, vec!["x.txt".to_string()]);
+        assert_eq!((status.done, status.total), (1, 2));
+        service.abort_rebase(tmp.path()).expect("abort");
+        let status = service.rebase_status(tmp.path()).expect("status");
+        assert_eq!(status.state, RebaseState::Completed);
+        assert_eq!(status.head, head);
+
+        let mut plan = plan_rebase(&rows[..1]).expect("plan");
+        plan.items[0].action = RebaseTodoAction::Edit;
+        let status = service.run_rebase(tmp.path(), &plan).expect("rebase");
+        assert_eq!(status.state, RebaseState::Stopped);
+        assert!(status.stopped_at.is_some());
+        let status = service.continue_rebase(tmp.path()).expect("continue");
+        assert_eq!(status.state, RebaseState::Completed);
+        assert_eq!(status.head, head);
+    }
+
+    #[test]
+    fn stages_unstages_and_discards_hunks() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let original = (1..=20).map(|n| format!("line {n}\n")).collect::<String>();
+        commit_file(&tmp, "f.txt", &original, "add f");
+        let edited = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\nline 18b\n");
+        fs::write(tmp.path().join("f.txt"), &edited).expect("edit f.txt");
+        fs::write(tmp.path().join("new.txt"), "n1\nn2\n").expect("write new.txt");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let staged = |service: &GitLgService| {
+            service
+                .working_tree_file_patch(tmp.path(), "f.txt", WorktreeDiffSide::Staged, 3)
+                .expect("staged patch")
+        };
+        let unstaged = service
+            .working_tree_file_patch(tmp.path(), "f.txt", WorktreeDiffSide::Unstaged, 3)
+            .expect("unstaged patch");
+        assert_eq!(unstaged.matches("\n@@ ").count(), 2);
+
+        // Only `+line 18b` of the second hunk.
+        service
+            .apply_hunk(
+                tmp.path(),
+                "f.txt",
+                HunkAction::Stage,
+                &HunkSelection {
+                    hunk: 1,
+                    lines: Some((5, 5)),
+                },
+            )
+            .expect("stage lines");
+        let patch = staged(&service);
+        assert!(patch.contains("+line 18b"));
+        assert!(!patch.contains("-line 18\n") && !patch.contains("line two"));
+
+        service
+            .apply_hunk(
+                tmp.path(),
+                "f.txt",
+                HunkAction::Stage,
+                &HunkSelection {
+                    hunk: 0,
+                    lines: None,
+                },
+            )
+            .expect("stage hunk");
+        assert!(staged(&service).contains("+line two"));
+        service
+            .apply_hunk(
+                tmp.path(),
+                "f.txt",
+                HunkAction::Unstage,
+                &HunkSelection {
+                    hunk: 1,
+                    lines: None,
+                },
+            )
+            .expect("unstage hunk");
+        let patch = staged(&service);
+        assert!(patch.contains("+line two") && !patch.contains("line 18b"));
+
+        // Discarding the remaining unstaged hunk keeps the staged change.
+        let remaining = service
+            .working_tree_file_patch(tmp.path(), "f.txt", WorktreeDiffSide::Unstaged, 3)
+            .expect("remaining patch");
+        assert_eq!(remaining.matches("\n@@ ").count(), 1);
+        service
+            .apply_hunk(
+                tmp.path(),
+                "f.txt",
+                HunkAction::Discard,
+                &HunkSelection {
+                    hunk: 0,
+                    lines: None,
+                },
+            )
+            .expect("discard hunk");
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("f.txt")).expect("read f.txt"),
+            original.replace("line 2\n", "line two\n")
+        );
+
+        let whole = HunkSelection {
+            hunk: 0,
+            lines: None,
+        };
+        assert!(
+            service
+                .apply_hunk(tmp.path(), "new.txt", HunkAction::Discard, &whole)
+                .is_err()
+        );
+        service
+            .apply_hunk(tmp.path(), "new.txt", HunkAction::Stage, &whole)
+            .expect("stage untracked file");
+        let status = service.working_tree_status(tmp.path()).expect("status");
+        let lines = status
+            .entries
+            .iter()
+            .map(|entry| entry.short_line())
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            lines,
+            HashSet::from(["M  f.txt".to_string(), "A  new.txt".to_string()])
+        );
+    }
+
     #[test]
     fn can_search_file_contents_in_history() {
         if !has_git() {
@@ -866,5 +2933,381 @@ mod tests {
             super::normalize_numstat_path("\"src/{old => new}/mod.rs\""),
             "src/new/mod.rs"
         );
+        assert_eq!(
+            super::numstat_old_path("src/{old => new}/mod.rs").as_deref(),
+            Some("src/old/mod.rs")
+        );
+        assert_eq!(
+            super::numstat_old_path("{ => nested}/mod.rs").as_deref(),
+            Some("mod.rs")
+        );
+        assert_eq!(super::numstat_old_path("README.md"), None);
+    }
+
+    #[test]
+    fn previews_merges_without_touching_the_worktree() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+        };
+        git(&["branch", "-M", "main"]);
+        git(&["checkout", "-b", "other"]);
+        commit_file(&tmp, "a.txt", "theirs\n", "theirs");
+        commit_file(&tmp, "b.txt", "b\n", "add b");
+        git(&["checkout", "-b", "side", "main"]);
+        commit_file(&tmp, "c.txt", "c\n", "add c");
+        git(&["checkout", "main"]);
+        commit_file(&tmp, "a.txt", "ours\n", "ours");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let preview = service
+            .preview_merge(tmp.path(), "main", "other")
+            .expect("preview");
+        assert!(!preview.clean);
+        assert_eq!(preview.conflicts.len(), 1);
+        assert_eq!(preview.conflicts[0].path, "a.txt");
+        assert_eq!(preview.conflicts[0].kinds, vec!["contents"]);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("a.txt")).expect("read a"),
+            "ours\n"
+        );
+        assert!(!tmp.path().join("b.txt").exists());
+
+        let clean = service
+            .preview_merge(tmp.path(), "main", "side")
+            .expect("clean preview");
+        assert!(clean.clean);
+        assert!(clean.conflicts.is_empty());
+        assert!(
+            service
+                .preview_merge(tmp.path(), "main", "missing")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn detects_and_controls_operations_in_progress() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git")
+        };
+        git(&["branch", "-M", "main"]);
+        git(&["checkout", "-b", "other"]);
+        commit_file(&tmp, "a.txt", "theirs\n", "theirs");
+        git(&["checkout", "main"]);
+        commit_file(&tmp, "a.txt", "ours\n", "ours");
+
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        assert_eq!(service.repo_operation(tmp.path()).expect("operation"), None);
+        assert!(
+            service
+                .control_operation(tmp.path(), RepoOperationControl::Continue)
+                .is_err()
+        );
+
+        git(&["merge", "other"]);
+        let graph = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph");
+        let merge = graph.operation.expect("merge in progress");
+        assert_eq!(merge.kind, RepoOperationKind::Merge);
+        assert!(
+            service
+                .control_operation(tmp.path(), RepoOperationControl::Continue)
+                .is_err()
+        );
+        assert!(
+            service
+                .control_operation(tmp.path(), RepoOperationControl::Skip)
+                .is_err()
+        );
+        let after = service
+            .control_operation(tmp.path(), RepoOperationControl::Abort)
+            .expect("abort merge");
+        assert_eq!(after, None);
+
+        git(&["cherry-pick", "other"]);
+        let pick = service
+            .repo_operation(tmp.path())
+            .expect("operation")
+            .expect("cherry-pick in progress");
+        assert_eq!(pick.kind, RepoOperationKind::CherryPick);
+        fs::write(tmp.path().join("a.txt"), "both\n").expect("resolve");
+        git(&["add", "a.txt"]);
+        let after = service
+            .control_operation(tmp.path(), RepoOperationControl::Continue)
+            .expect("continue cherry-pick");
+        assert_eq!(after, None);
+        let subject = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph")
+            .commits[0]
+            .subject
+            .clone();
+        assert_eq!(subject, "theirs");
+    }
+
+    #[test]
+    fn bisects_between_two_rows() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        init_repo(&tmp);
+        for n in 1..=6 {
+            let file = if n == 4 { "bug.txt" } else { "f.txt" };
+            commit_file(&tmp, file, &format!("{n}\n"), &format!("c{n}"));
+        }
+        let service = GitLgService::with_default_actions(GitRunner::default());
+        let rows = service
+            .graph(tmp.path(), &GraphQuery::default())
+            .expect("graph")
+            .commits;
+        let row = |subject: &str| {
+            rows.iter()
+                .find(|row| row.subject == subject)
+                .expect("row")
+                .clone()
+        };
+        assert_eq!(service.bisect_status(tmp.path()).expect("status"), None);
+        assert!(
+            service
+                .mark_bisect(tmp.path(), BisectMark::Good, None)
+                .is_err()
+        );
+
+        // Either order works; the older row is the good end.
+        let status = service
+            .start_bisect(tmp.path(), &row("init"), &row("c6"))
+            .expect("start");
+        assert_eq!(status.bad.as_deref(), Some(row("c6").hash.as_str()));
+        assert_eq!(status.good, vec![row("init").hash]);
+        assert_eq!(status.candidates.len(), 6);
+        assert_eq!(status.steps_left, 3);
+        assert_eq!(
+            service
+                .repo_operation(tmp.path())
+                .expect("operation")
+                .map(|operation| operation.kind),
+            Some(RepoOperationKind::Bisect)
+        );
+
+        let status = service
+            .mark_bisect(tmp.path(), BisectMark::Bad, Some(&row("c5").hash))
+            .expect("mark bad");
+        assert_eq!(status.candidates.len(), 5);
+        let status = service
+            .run_bisect(tmp.path(), "test ! -e bug.txt")
+            .expect("bisect run");
+        assert_eq!(status.first_bad.as_deref(), Some(row("c4").hash.as_str()));
+        assert_eq!(status.steps_left, 0);
+
+        service.reset_bisect(tmp.path()).expect("bisect reset");
+        assert_eq!(service.bisect_status(tmp.path()).expect("status"), None);
+        assert!(service.reset_bisect(tmp.path()).is_err());
+    }
+
+    #[test]
+    fn logs_actions_and_undoes_them() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        let state_dir = TempDir::new().expect("state dir");
+        init_repo(&tmp);
+        commit_file(&tmp, "a.txt", "two\n", "second");
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&["branch", "-M", "main"]);
+        let init = git(&["rev-parse", "HEAD~1"]);
+        let second = git(&["rev-parse", "HEAD"]);
+
+        let plain = GitLgService::with_default_actions(GitRunner::default());
+        assert!(plain.plan_undo_last(tmp.path()).is_err());
+        let log = OperationLog::next_to_state(&state_dir.path().join("state.json"));
+        let service = plain.with_operation_log(log.clone());
+        let run = |template_id: &str, params: &[(&str, &str)], options: &[&str]| {
+            service
+                .execute_action(
+                    tmp.path(),
+                    ActionRequest {
+                        template_id: template_id.to_string(),
+                        params: params
+                            .iter()
+                            .map(|(k, v)| (k.to_string(), v.to_string()))
+                            .collect(),
+                        enabled_options: options.iter().map(ToString::to_string).collect(),
+                        context: ActionContext {
+                            commit_hash: Some(init.clone()),
+                            ..ActionContext::default()
+                        },
+                    },
+                    "origin",
+                )
+                .expect("run action");
+        };
+        assert_eq!(service.plan_undo_last(tmp.path()).expect("plan"), None);
+
+        // `refs/stash` is not snapshotted, so a stash leaves nothing to undo.
+        fs::write(tmp.path().join("a.txt"), "stashed\n").expect("dirty");
+        run("global:1:git-stash-include-untracked", &[], &[]);
+        assert_ne!(git(&["stash", "list"]), "");
+        assert_eq!(service.plan_undo_last(tmp.path()).expect("plan"), None);
+        git(&["stash", "drop"]);
+
+        run("commit:2:create-branch", &[("1", "topic")], &[]);
+        run("commit:7:reset", &[], &["--hard"]);
+        assert_eq!(log.load().expect("load").len(), 3);
+        assert_eq!(git(&["rev-parse", "HEAD"]), init);
+
+        let plan = service
+            .plan_undo_last(tmp.path())
+            .expect("plan")
+            .expect("reset to undo");
+        assert!(plan.operation.command_line.contains("--hard"));
+        assert!(plan.affects_worktree);
+        fs::write(tmp.path().join("a.txt"), "dirty\n").expect("dirty");
+        assert!(service.apply_undo(tmp.path(), &plan).is_err());
+        git(&["checkout", "--", "a.txt"]);
+        service.apply_undo(tmp.path(), &plan).expect("undo reset");
+        assert_eq!(git(&["rev-parse", "HEAD"]), second);
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("a.txt")).expect("read a"),
+            "two\n"
+        );
+
+        let plan = service
+            .plan_undo_last(tmp.path())
+            .expect("plan")
+            .expect("branch to undo");
+        assert!(!plan.affects_worktree);
+        service.apply_undo(tmp.path(), &plan).expect("undo branch");
+        assert_eq!(git(&["branch", "--list", "topic"]), "");
+        assert_eq!(service.plan_undo_last(tmp.path()).expect("plan"), None);
+
+        run("commit:1:checkout", &[], &[]);
+        assert_eq!(git(&["rev-parse", "HEAD"]), init);
+        let plan = service
+            .plan_undo_last(tmp.path())
+            .expect("plan")
+            .expect("checkout to undo");
+        assert_eq!(plan.checkout.as_deref(), Some("refs/heads/main"));
+        service
+            .apply_undo(tmp.path(), &plan)
+            .expect("undo checkout");
+        assert_eq!(git(&["symbolic-ref", "HEAD"]), "refs/heads/main");
+        assert_eq!(git(&["rev-parse", "HEAD"]), second);
+
+        // A log that cannot be written does not fail the action it records.
+        let broken = GitLgService::with_default_actions(GitRunner::default())
+            .with_operation_log(OperationLog::at(state_dir.path().to_path_buf()));
+        let result = broken
+            .execute_action(
+                tmp.path(),
+                ActionRequest {
+                    template_id: "commit:2:create-branch".to_string(),
+                    params: HashMap::from([("1".to_string(), "logless".to_string())]),
+                    enabled_options: HashSet::new(),
+                    context: ActionContext {
+                        commit_hash: Some(init.clone()),
+                        ..ActionContext::default()
+                    },
+                },
+                "origin",
+            )
+            .expect("run action");
+        assert!(result.log_warning.is_some());
+        assert_eq!(git(&["branch", "--list", "logless"]), "logless");
+    }
+
+    #[test]
+    fn failed_undo_checkout_leaves_refs_for_a_retry() {
+        if !has_git() {
+            return;
+        }
+        let tmp = TempDir::new().expect("tempdir");
+        let state_dir = TempDir::new().expect("state dir");
+        init_repo(&tmp);
+        let git = |args: &[&str]| {
+            let out = Command::new("git")
+                .args(args)
+                .current_dir(tmp.path())
+                .output()
+                .expect("git");
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        git(&["branch", "-M", "main"]);
+        let init = git(&["rev-parse", "HEAD"]);
+        git(&["switch", "-q", "-c", "topic"]);
+        commit_file(&tmp, "t.txt", "t\n", "on topic");
+        let topic = git(&["rev-parse", "HEAD"]);
+        git(&["switch", "-q", "main"]);
+        commit_file(&tmp, "m.txt", "m\n", "on main");
+
+        let log = OperationLog::next_to_state(&state_dir.path().join("state.json"));
+        let service = GitLgService::with_default_actions(GitRunner::default())
+            .with_operation_log(log.clone());
+        // One operation that checks out topic, moves it back to init and
+        // tags it.
+        let before = service.ref_snapshot(tmp.path()).expect("snapshot");
+        git(&["switch", "-q", "topic"]);
+        git(&["reset", "-q", "--hard", &init]);
+        git(&["tag", "v1"]);
+        log.append(OperationRecord {
+            id: 0,
+            git_dir: service.git_dir(tmp.path()).expect("git dir"),
+            action_id: "test".to_string(),
+            command_line: "switch topic && reset --hard && tag v1".to_string(),
+            executed_at_unix: 0,
+            succeeded: true,
+            before,
+            after: service.ref_snapshot(tmp.path()).expect("snapshot"),
+            undone: false,
+        })
+        .expect("append");
+
+        // An untracked file main would overwrite stops the switch back.
+        fs::write(tmp.path().join("m.txt"), "untracked\n").expect("write m");
+        let plan = service
+            .plan_undo_last(tmp.path())
+            .expect("plan")
+            .expect("reset to undo");
+        assert!(service.apply_undo(tmp.path(), &plan).is_err());
+        assert_eq!(git(&["rev-parse", "topic"]), init);
+        assert_eq!(git(&["tag", "--list"]), "v1");
+        assert_eq!(git(&["symbolic-ref", "HEAD"]), "refs/heads/topic");
+
+        fs::remove_file(tmp.path().join("m.txt")).expect("remove m");
+        let plan = service
+            .plan_undo_last(tmp.path())
+            .expect("plan")
+            .expect("still undoable");
+        service.apply_undo(tmp.path(), &plan).expect("undo");
+        assert_eq!(git(&["rev-parse", "topic"]), topic);
+        assert_eq!(git(&["tag", "--list"]), "");
+        assert_eq!(git(&["symbolic-ref", "HEAD"]), "refs/heads/main");
+        assert_eq!(service.plan_undo_last(tmp.path()).expect("plan"), None);
     }
 }
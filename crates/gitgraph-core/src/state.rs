@@ -98,9 +98,11 @@ mod tests {
         let tmp = TempDir::new().expect("tempdir");
         let store = StateStore::at(tmp.path().join("state.json"));
 
-        let mut state = AppState::default();
-        state.selected_repo_path = Some(tmp.path().to_path_buf());
-        state.default_remote_name = "upstream".to_string();
+        let state = AppState {
+            selected_repo_path: Some(tmp.path().to_path_buf()),
+            default_remote_name: "upstream".to_string(),
+            ..AppState::default()
+        };
 
         store.save(&state).expect("save state");
         let loaded = store.load().expect("load state");
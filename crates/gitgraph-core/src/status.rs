@@ -0,0 +1,172 @@
+use crate::error::{GitLgError, Result};
+use crate::models::{FileStatus, StatusBranch, StatusEntry, StatusEntryKind, WorkingTreeStatus};
+
+// Shared with the Zed extension, which runs git itself.
+pub fn status_args() -> Vec<String> {
+    vec![
+        "status".to_string(),
+        "--porcelain=v2".to_string(),
+        "--branch".to_string(),
+        "--untracked-files=all".to_string(),
+        "-z".to_string(),
+    ]
+}
+
+// Parses `git status --porcelain=v2 --branch -z`. Every record ends in NUL;
+// rename/copy records (`2`) are followed by one more NUL-terminated field
+// holding the original path.
+pub fn parse_status_porcelain_v2(stdout: &str) -> Result<WorkingTreeStatus> {
+    let mut status = WorkingTreeStatus::default();
+    let mut records = stdout.split('\0');
+    while let Some(record) = records.next() {
+        if record.is_empty() {
+            continue;
+        }
+        if let Some(header) = record.strip_prefix("# ") {
+            parse_branch_header(header, &mut status.branch);
+            continue;
+        }
+        let invalid = || GitLgError::Parse(format!("invalid status record: {record:?}"));
+        let (kind, rest) = record.split_once(' ').ok_or_else(invalid)?;
+        let entry = match kind {
+            "1" => {
+                let fields = rest.splitn(8, ' ').collect::<Vec<_>>();
+                if fields.len() != 8 {
+                    return Err(invalid());
+                }
+                changed_entry(StatusEntryKind::Changed, fields[0], fields[7], None)
+            }
+            "2" => {
+                let fields = rest.splitn(9, ' ').collect::<Vec<_>>();
+                if fields.len() != 9 {
+                    return Err(invalid());
+                }
+                let original = records.next().ok_or_else(invalid)?;
+                changed_entry(
+                    StatusEntryKind::Renamed,
+                    fields[0],
+                    fields[8],
+                    Some(original.to_string()),
+                )
+            }
+            "u" => {
+                let fields = rest.splitn(10, ' ').collect::<Vec<_>>();
+                if fields.len() != 10 {
+                    return Err(invalid());
+                }
+                changed_entry(StatusEntryKind::Conflicted, fields[0], fields[9], None)
+            }
+            "?" | "!" => StatusEntry {
+                path: rest.to_string(),
+                original_path: None,
+                kind: if kind == "?" {
+                    StatusEntryKind::Untracked
+                } else {
+                    StatusEntryKind::Ignored
+                },
+                index: FileStatus::Unmodified,
+                worktree: FileStatus::Unmodified,
+            },
+            _ => return Err(invalid()),
+        };
+        status.entries.push(entry);
+    }
+    Ok(status)
+}
+
+fn changed_entry(
+    kind: StatusEntryKind,
+    xy: &str,
+    path: &str,
+    original_path: Option<String>,
+) -> StatusEntry {
+    let mut codes = xy.chars();
+    StatusEntry {
+        path: path.to_string(),
+        original_path,
+        kind,
+        index: FileStatus::from_git_code(codes.next().unwrap_or('.')),
+        worktree: FileStatus::from_git_code(codes.next().unwrap_or('.')),
+    }
+}
+
+fn parse_branch_header(header: &str, branch: &mut StatusBranch) {
+    let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+    match key {
+        "branch.oid" => branch.oid = (value != "(initial)").then(|| value.to_string()),
+        "branch.head" => branch.head = (value != "(detached)").then(|| value.to_string()),
+        "branch.upstream" => branch.upstream = Some(value.to_string()),
+        "branch.ab" => {
+            for part in value.split_whitespace() {
+                if let Some(ahead) = part.strip_prefix('+') {
+                    branch.ahead = ahead.parse().unwrap_or(0);
+                } else if let Some(behind) = part.strip_prefix('-') {
+                    branch.behind = behind.parse().unwrap_or(0);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_status_porcelain_v2;
+    use crate::models::{FileStatus, StatusEntryKind};
+
+    #[test]
+    fn parses_branch_headers_and_entry_kinds() {
+        let stdout = [
+            "# branch.oid 1234567890abcdef1234567890abcdef12345678",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 M. N... 100644 100644 100644 aaaa bbbb src/staged file.rs",
+            "1 .M N... 100644 100644 100644 aaaa aaaa unstaged.rs",
+            "2 R. N... 100644 100644 100644 aaaa aaaa R100 new name.rs",
+            "old name.rs",
+            "u UU N... 100644 100644 100644 100644 aaaa bbbb cccc conflict.rs",
+            "? notes.txt",
+            "",
+        ]
+        .join("\0");
+        let status = parse_status_porcelain_v2(&stdout).expect("parse status");
+
+        assert_eq!(status.branch.head.as_deref(), Some("main"));
+        assert_eq!(status.branch.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.branch.ahead, status.branch.behind), (2, 1));
+        assert_eq!(status.entries.len(), 5);
+
+        assert_eq!(status.entries[0].path, "src/staged file.rs");
+        assert!(status.entries[0].is_staged() && !status.entries[0].is_unstaged());
+        assert!(status.entries[1].is_unstaged() && !status.entries[1].is_staged());
+        assert_eq!(status.entries[2].kind, StatusEntryKind::Renamed);
+        assert_eq!(status.entries[2].index, FileStatus::Renamed);
+        assert_eq!(
+            status.entries[2].original_path.as_deref(),
+            Some("old name.rs")
+        );
+        assert_eq!(
+            status.entries[2].short_line(),
+            "R  old name.rs -> new name.rs"
+        );
+        assert!(status.entries[3].is_conflicted());
+        assert_eq!(status.entries[3].short_line(), "UU conflict.rs");
+        assert!(status.entries[4].is_untracked());
+        assert_eq!(
+            status.summary(),
+            "2 staged, 1 unstaged, 1 untracked, 1 conflicted"
+        );
+    }
+
+    #[test]
+    fn handles_initial_and_detached_heads() {
+        let status =
+            parse_status_porcelain_v2("# branch.oid (initial)\0# branch.head (detached)\0")
+                .expect("parse status");
+        assert_eq!(status.branch.oid, None);
+        assert_eq!(status.branch.head, None);
+        assert!(status.is_clean());
+        assert_eq!(status.summary(), "clean");
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+use crate::error::Result;
+use crate::git::GitStream;
+use crate::log_parser::{GraphBuilder, LogRecordReader};
+use crate::models::GraphRow;
+
+#[derive(Debug)]
+pub struct GraphStream {
+    records: LogRecordReader<GitStream>,
+    builder: GraphBuilder,
+    // Rows the builder released beyond the last batch.
+    ready: VecDeque<GraphRow>,
+    batch_size: usize,
+    finished: bool,
+}
+
+impl GraphStream {
+    pub fn new(stream: GitStream, batch_size: usize) -> Self {
+        Self::with_builder(stream, batch_size, GraphBuilder::new())
+    }
+
+    pub fn with_builder(stream: GitStream, batch_size: usize, builder: GraphBuilder) -> Self {
+        Self {
+            records: LogRecordReader::new(stream),
+            builder,
+            ready: VecDeque::new(),
+            batch_size: batch_size.max(1),
+            finished: false,
+        }
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    pub fn rows_loaded(&self) -> usize {
+        self.builder.rows_built()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.ready.is_empty()
+    }
+
+    pub fn builder(&self) -> &GraphBuilder {
+        &self.builder
+    }
+
+    pub fn next_batch(&mut self, max_rows: usize) -> Result<Vec<GraphRow>> {
+        let max_rows = max_rows.max(1);
+        while !self.finished && self.ready.len() < max_rows {
+            match self.records.next_record() {
+                Ok(Some(commit)) => self.ready.extend(self.builder.accept(commit)),
+                Ok(None) => {
+                    self.finished = true;
+                    self.ready.extend(self.builder.finish());
+                    self.records.get_mut().finish()?;
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Err(err);
+                }
+            }
+        }
+        let take = max_rows.min(self.ready.len());
+        Ok(self.ready.drain(..take).collect())
+    }
+
+    pub fn collect_remaining(&mut self) -> Result<Vec<GraphRow>> {
+        let mut rows = Vec::new();
+        while !self.is_finished() {
+            rows.extend(self.next_batch(self.batch_size)?);
+        }
+        Ok(rows)
+    }
+}
+
+impl Iterator for GraphStream {
+    type Item = Result<Vec<GraphRow>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished() {
+            return None;
+        }
+        match self.next_batch(self.batch_size) {
+            Ok(rows) if rows.is_empty() => None,
+            other => Some(other),
+        }
+    }
+}
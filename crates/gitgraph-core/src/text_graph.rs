@@ -0,0 +1,250 @@
+use std::cmp::{max, min};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{GraphRow, GraphSegmentKind, SignatureStatus};
+
+const LINK_UP: u8 = 1;
+const LINK_DOWN: u8 = 2;
+const LINK_LEFT: u8 = 4;
+const LINK_RIGHT: u8 = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextGraphStyle {
+    #[default]
+    Unicode,
+    Ascii,
+}
+
+// `Line` cells are lane bodies and horizontal connectors, usually drawn dim;
+// `Node` and `Junction` (a lane crossed by a connector) are drawn bold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextGraphCellKind {
+    Blank,
+    Node,
+    Line,
+    Junction,
+}
+
+// Serialize-only: glyphs are static strings from the style's glyph set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextGraphCell {
+    pub glyph: &'static str,
+    pub kind: TextGraphCellKind,
+    pub color_group: Option<u32>,
+}
+
+// Badge shown next to a signed commit's hash, in the style's glyph set.
+pub fn signature_badge(status: SignatureStatus, style: TextGraphStyle) -> Option<&'static str> {
+    let (unicode, ascii) = match status {
+        SignatureStatus::Good => ("✔", "G"),
+        SignatureStatus::Bad => ("✘", "B"),
+        SignatureStatus::Unknown => ("?", "U"),
+        SignatureStatus::Expired => ("⌛", "X"),
+        SignatureStatus::None => return None,
+    };
+    match style {
+        TextGraphStyle::Unicode => Some(unicode),
+        TextGraphStyle::Ascii => Some(ascii),
+    }
+}
+
+// Lane cells alternate with gap cells: `lane, gap, lane, gap, ..., lane`.
+pub fn graph_cells(row: &GraphRow, style: TextGraphStyle) -> Vec<TextGraphCell> {
+    let links = LaneLinks::for_row(row);
+    let (gap_blank, gap_line) = match style {
+        TextGraphStyle::Unicode => ("  ", "──"),
+        TextGraphStyle::Ascii => (" ", "-"),
+    };
+    let mut out = Vec::with_capacity(links.links.len().saturating_mul(2));
+    for lane in 0..links.links.len() {
+        if lane > 0 {
+            out.push(match links.gaps[lane - 1] {
+                Some(color) => TextGraphCell {
+                    glyph: gap_line,
+                    kind: TextGraphCellKind::Line,
+                    color_group: Some(color),
+                },
+                None => TextGraphCell {
+                    glyph: gap_blank,
+                    kind: TextGraphCellKind::Blank,
+                    color_group: None,
+                },
+            });
+        }
+        if lane == row.lane {
+            out.push(TextGraphCell {
+                glyph: match style {
+                    TextGraphStyle::Unicode => "●",
+                    TextGraphStyle::Ascii => "o",
+                },
+                kind: TextGraphCellKind::Node,
+                color_group: Some(row.color_group),
+            });
+            continue;
+        }
+        let lane_links = links.links[lane];
+        let glyph = match style {
+            TextGraphStyle::Unicode => unicode_link_glyph(lane_links),
+            TextGraphStyle::Ascii => ascii_link_glyph(lane_links),
+        };
+        let vertical = lane_links & (LINK_UP | LINK_DOWN) != 0;
+        let horizontal = lane_links & (LINK_LEFT | LINK_RIGHT) != 0;
+        let kind = match (vertical, horizontal) {
+            (false, false) => TextGraphCellKind::Blank,
+            (true, true) => TextGraphCellKind::Junction,
+            _ => TextGraphCellKind::Line,
+        };
+        out.push(TextGraphCell {
+            glyph,
+            kind,
+            color_group: (kind != TextGraphCellKind::Blank).then_some(links.colors[lane]),
+        });
+    }
+    out
+}
+
+pub fn graph_prefix(row: &GraphRow, style: TextGraphStyle) -> String {
+    graph_cells(row, style)
+        .iter()
+        .map(|cell| cell.glyph)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+struct LaneLinks {
+    links: Vec<u8>,
+    colors: Vec<u32>,
+    gaps: Vec<Option<u32>>,
+}
+
+impl LaneLinks {
+    fn for_row(row: &GraphRow) -> Self {
+        let lane_count = row
+            .segments
+            .iter()
+            .map(|s| max(s.from_lane, s.to_lane) + 1)
+            .fold(max(row.active_lane_count, row.lane + 1), max);
+        let mut cells = Self {
+            links: vec![0; lane_count],
+            colors: vec![row.color_group; lane_count],
+            gaps: vec![None; lane_count.saturating_sub(1)],
+        };
+        for segment in &row.segments {
+            let (from, to, color) = (segment.from_lane, segment.to_lane, segment.color_group);
+            match segment.kind {
+                GraphSegmentKind::PassThrough => {
+                    cells.links[from] |= LINK_UP | LINK_DOWN;
+                    cells.colors[from] = color;
+                }
+                GraphSegmentKind::Incoming => cells.links[to] |= LINK_UP,
+                GraphSegmentKind::Outgoing => cells.links[from] |= LINK_DOWN,
+                GraphSegmentKind::Shift => cells.connect(from, LINK_UP, to, LINK_DOWN, color),
+                GraphSegmentKind::Fork => cells.connect(from, LINK_UP, to, 0, color),
+                GraphSegmentKind::Merge => cells.connect(from, 0, to, LINK_DOWN, color),
+                GraphSegmentKind::Start | GraphSegmentKind::End => {}
+            }
+        }
+        cells
+    }
+
+    fn connect(&mut self, a: usize, a_link: u8, b: usize, b_link: u8, color: u32) {
+        if a == b {
+            self.links[a] |= a_link | b_link;
+            return;
+        }
+        for lane in [a, b] {
+            if self.links[lane] & (LINK_UP | LINK_DOWN) == 0 {
+                self.colors[lane] = color;
+            }
+        }
+        let (lo, hi) = (min(a, b), max(a, b));
+        self.links[lo] |= LINK_RIGHT;
+        self.links[hi] |= LINK_LEFT;
+        self.links[a] |= a_link;
+        self.links[b] |= b_link;
+        for lane in (lo + 1)..hi {
+            if self.links[lane] & (LINK_UP | LINK_DOWN) == 0 {
+                self.colors[lane] = color;
+            }
+            self.links[lane] |= LINK_LEFT | LINK_RIGHT;
+        }
+        for gap in &mut self.gaps[lo..hi] {
+            *gap = Some(color);
+        }
+    }
+}
+
+fn unicode_link_glyph(links: u8) -> &'static str {
+    let vertical = links & (LINK_UP | LINK_DOWN);
+    let horizontal = links & (LINK_LEFT | LINK_RIGHT);
+    match (vertical, horizontal) {
+        (0, 0) => " ",
+        (_, 0) => "│",
+        (0, _) => "─",
+        (v, h) if v == LINK_UP | LINK_DOWN && h == LINK_LEFT | LINK_RIGHT => "┼",
+        (v, LINK_RIGHT) if v == LINK_UP | LINK_DOWN => "├",
+        (v, LINK_LEFT) if v == LINK_UP | LINK_DOWN => "┤",
+        (LINK_DOWN, LINK_RIGHT) => "╭",
+        (LINK_DOWN, LINK_LEFT) => "╮",
+        (LINK_UP, LINK_RIGHT) => "╰",
+        (LINK_UP, LINK_LEFT) => "╯",
+        (LINK_DOWN, _) => "┬",
+        _ => "┴",
+    }
+}
+
+fn ascii_link_glyph(links: u8) -> &'static str {
+    let vertical = links & (LINK_UP | LINK_DOWN);
+    let horizontal = links & (LINK_LEFT | LINK_RIGHT);
+    match (vertical, horizontal) {
+        (0, 0) => " ",
+        (_, 0) => "|",
+        (0, _) => "-",
+        (LINK_DOWN, LINK_LEFT) | (LINK_UP, LINK_RIGHT) => "\\",
+        (LINK_DOWN, LINK_RIGHT) | (LINK_UP, LINK_LEFT) => "/",
+        _ => "+",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::log_parser::{build_graph_rows, merge_fixture, parse_git_log_records};
+    use crate::models::GraphRow;
+
+    use super::{TextGraphCellKind, TextGraphStyle, graph_cells, graph_prefix};
+
+    fn merge_rows() -> Vec<GraphRow> {
+        build_graph_rows(parse_git_log_records(&merge_fixture()).expect("parse fixture"))
+    }
+
+    #[test]
+    fn renders_merge_and_fork_prefixes() {
+        let rows = merge_rows();
+        let ascii = rows
+            .iter()
+            .map(|row| graph_prefix(row, TextGraphStyle::Ascii))
+            .collect::<Vec<_>>();
+        assert_eq!(ascii, vec!["o-\\", "o |", "| o", "o-/"]);
+        let unicode = rows
+            .iter()
+            .map(|row| graph_prefix(row, TextGraphStyle::Unicode))
+            .collect::<Vec<_>>();
+        assert_eq!(unicode, vec!["●──╮", "●  │", "│  ●", "●──╯"]);
+    }
+
+    #[test]
+    fn cells_carry_kind_and_color_group() {
+        let rows = merge_rows();
+        let cells = graph_cells(&rows[0], TextGraphStyle::Unicode);
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0].kind, TextGraphCellKind::Node);
+        assert_eq!(cells[0].color_group, Some(rows[0].color_group));
+        assert_eq!(cells[1].kind, TextGraphCellKind::Line);
+        assert_eq!(cells[2].kind, TextGraphCellKind::Junction);
+        assert_eq!(cells[2].color_group, Some(rows[2].color_group));
+    }
+}
@@ -69,12 +69,12 @@ fn synth_log_fixture(commits: usize) -> String {
         } else {
             String::new()
         };
-        let subject = if rev % 17 == 0 {
+        let subject = if rev.is_multiple_of(17) {
             "optimize parser needle"
         } else {
             "regular commit"
         };
-        let body = if rev % 29 == 0 {
+        let body = if rev.is_multiple_of(29) {
             "touches pipeline needle"
         } else {
             "body text"
@@ -98,6 +98,14 @@ fn synth_log_fixture(commits: usize) -> String {
         out.push(FIELD_SEP);
         out.push_str("1700000000");
         out.push(FIELD_SEP);
+        out.push_str("Perf Bot");
+        out.push(FIELD_SEP);
+        out.push_str("perf@example.com");
+        out.push(FIELD_SEP);
+        out.push_str("2023-11-14 22:13:20 +0000");
+        out.push(FIELD_SEP);
+        out.push_str("2023-11-14 22:13:20 +0000");
+        out.push(FIELD_SEP);
         out.push_str(refs);
         out.push(FIELD_SEP);
         out.push_str(subject);
@@ -1,9 +1,15 @@
 use std::collections::{HashMap, HashSet};
 
-use gitgraph_core::log_parser::{build_graph_rows, parse_git_log_records};
+use gitgraph_core::log_parser::{
+    BRANCH_LIST_FORMAT, GraphBuilder, build_graph_rows, log_format, parse_branch_list,
+    parse_git_log_records,
+};
 use gitgraph_core::{
-    ActionCatalog, ActionContext, ActionRequest, ActionScope, CommitSearchQuery, GitLgError,
-    filter_commits,
+    ActionCatalog, ActionContext, ActionRequest, ActionScope, BranchInfo, CommitOptions,
+    CommitSearchQuery, GitLgError, LineHistoryEntry, StatusEntry, TextGraphStyle,
+    WorkingTreeStatus, commit_args, commit_row_args, filter_commits, graph_prefix,
+    line_history_args, parse_commit_row, parse_line_history, parse_status_porcelain_v2,
+    signature_badge, status_args,
 };
 use zed_extension_api as zed;
 
@@ -30,6 +36,18 @@ fn is_blame_command(name: &str) -> bool {
     matches!(name, "gitgraph-blame" | "gitlg-blame")
 }
 
+fn is_line_history_command(name: &str) -> bool {
+    name == "gitgraph-line-history"
+}
+
+fn is_status_command(name: &str) -> bool {
+    name == "gitgraph-status"
+}
+
+fn is_commit_command(name: &str) -> bool {
+    name == "gitgraph-commit"
+}
+
 fn is_tips_command(name: &str) -> bool {
     matches!(name, "gitgraph-tips" | "gitlg-tips")
 }
@@ -64,6 +82,18 @@ impl zed::Extension for GitGraphZedExtension {
                     run_command: false,
                 },
             ];
+            if is_log_command(command.name.as_str()) {
+                items.push(zed::SlashCommandArgumentCompletion {
+                    label: "page=2".to_string(),
+                    new_text: "page=2".to_string(),
+                    run_command: false,
+                });
+                items.push(zed::SlashCommandArgumentCompletion {
+                    label: "signatures".to_string(),
+                    new_text: "signatures".to_string(),
+                    run_command: false,
+                });
+            }
             if is_search_command(command.name.as_str()) {
                 items.push(zed::SlashCommandArgumentCompletion {
                     label: "path=src/main.rs".to_string(),
@@ -99,6 +129,23 @@ impl zed::Extension for GitGraphZedExtension {
                 },
             ]);
         }
+        if is_line_history_command(command.name.as_str()) {
+            return Ok(vec![zed::SlashCommandArgumentCompletion {
+                label: "src/main.rs 10 20".to_string(),
+                new_text: "src/main.rs 10 20".to_string(),
+                run_command: false,
+            }]);
+        }
+        if is_commit_command(command.name.as_str()) {
+            return Ok(["+all", "+amend", "+signoff", "+empty", "+fixup=HEAD~1"]
+                .into_iter()
+                .map(|token| zed::SlashCommandArgumentCompletion {
+                    label: token.to_string(),
+                    new_text: format!("{token} "),
+                    run_command: false,
+                })
+                .collect());
+        }
         Ok(Vec::new())
     }
 
@@ -131,6 +178,15 @@ impl zed::Extension for GitGraphZedExtension {
         if is_blame_command(command_name) {
             return run_gitgraph_blame(&root, args);
         }
+        if is_line_history_command(command_name) {
+            return run_gitgraph_line_history(&root, args);
+        }
+        if is_status_command(command_name) {
+            return run_gitgraph_status(&root);
+        }
+        if is_commit_command(command_name) {
+            return run_gitgraph_commit(&root, args);
+        }
         if is_tips_command(command_name) {
             return run_gitgraph_tips();
         }
@@ -139,16 +195,44 @@ impl zed::Extension for GitGraphZedExtension {
 }
 
 fn run_gitgraph_log(repo_root: &str, args: Vec<String>) -> Result<zed::SlashCommandOutput, String> {
-    let limit = parse_limit_arg(args.first().map(String::as_str))?;
-    let output = run_git_log(repo_root, limit)?;
-    let rows = build_graph_rows(
-        parse_git_log_records(&output).map_err(|e| format!("failed to parse git output: {e}"))?,
-    );
-    let text = render_rows(
+    let parsed = parse_log_args(args)?;
+    let skip = parsed.limit.saturating_mul(parsed.page - 1);
+    // Earlier pages are still fed through the builder so lanes on this page
+    // line up with what the previous page rendered.
+    let output = run_git_log(
         repo_root,
-        &rows,
-        &format!("Showing {} commit(s)", rows.len()),
-    );
+        skip.saturating_add(parsed.limit) + 1,
+        parsed.verify_signatures,
+    )?;
+    let mut builder = GraphBuilder::new();
+    let mut rows = parse_git_log_records(&output)
+        .map_err(|e| format!("failed to parse git output: {e}"))?
+        .into_iter()
+        .map(|commit| builder.push(commit))
+        .skip(skip)
+        .collect::<Vec<_>>();
+    let has_more = rows.len() > parsed.limit;
+    rows.truncate(parsed.limit);
+
+    let mut subtitle = format!("Showing {} commit(s), page {}", rows.len(), parsed.page);
+    if has_more {
+        subtitle.push_str(&format!(
+            " (next: `/gitgraph-log limit={} page={}{}`)",
+            parsed.limit,
+            parsed.page + 1,
+            if parsed.verify_signatures {
+                " signatures"
+            } else {
+                ""
+            }
+        ));
+    }
+    // The status line is best effort; the graph is still worth showing.
+    if let Ok(status) = run_git_status(repo_root) {
+        subtitle.push_str(&format!("\n\nWorking tree: {}", status.summary()));
+    }
+    subtitle.push_str(&render_branch_status(&run_git_branches(repo_root)?));
+    let text = render_rows(repo_root, &rows, &subtitle);
     Ok(build_output(text, "GitGraph graph"))
 }
 
@@ -157,7 +241,7 @@ fn run_gitgraph_search(
     args: Vec<String>,
 ) -> Result<zed::SlashCommandOutput, String> {
     let parsed = parse_search_args(args)?;
-    let output = run_git_log(repo_root, parsed.limit)?;
+    let output = run_git_log(repo_root, parsed.limit, false)?;
     let rows = build_graph_rows(
         parse_git_log_records(&output).map_err(|e| format!("failed to parse git output: {e}"))?,
     );
@@ -271,6 +355,15 @@ fn run_gitgraph_action(
             lookup_dynamic_placeholder(repo_root, placeholder)
         })
         .map_err(|e| format!("resolve action failed: {e}"))?;
+    if let Some(reason) = resolved.risk.reason()
+        && !parsed.confirmed
+    {
+        let text = format!(
+            "# GitGraph action: `{}`\n\nNot run: `git {}` is destructive ({reason}).\n\nAppend `+yes` to run it.\n",
+            resolved.id, resolved.command_line
+        );
+        return Ok(build_output(text, "GitGraph action (not run)"));
+    }
 
     let output = if let Some(script) = &resolved.shell_script {
         run_shell_command(repo_root, &format!("git {}", script))?
@@ -338,15 +431,69 @@ fn run_gitgraph_blame(
     Ok(build_output(text, "GitGraph blame"))
 }
 
+fn run_gitgraph_line_history(
+    repo_root: &str,
+    args: Vec<String>,
+) -> Result<zed::SlashCommandOutput, String> {
+    let (file, start, end) = parse_line_history_args(args)?;
+    let out = run_git_command(repo_root, &line_history_args("HEAD", &file, start, end))?;
+    if out.status != Some(0) {
+        return Err(format!(
+            "git log -L failed (exit {:?}): {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr)
+        ));
+    }
+    let entries = parse_line_history(&String::from_utf8_lossy(&out.stdout))
+        .map_err(|e| format!("failed to parse git output: {e}"))?;
+    let text = render_line_history_text(repo_root, &file, start, end, &entries);
+    Ok(build_output(text, "GitGraph line history"))
+}
+
+fn run_gitgraph_status(repo_root: &str) -> Result<zed::SlashCommandOutput, String> {
+    let status = run_git_status(repo_root)?;
+    Ok(build_output(
+        render_status_text(repo_root, &status),
+        "GitGraph status",
+    ))
+}
+
+fn run_gitgraph_commit(
+    repo_root: &str,
+    args: Vec<String>,
+) -> Result<zed::SlashCommandOutput, String> {
+    let options = parse_commit_args(args)?;
+    let commit = commit_args(&options).map_err(|e| e.to_string())?;
+    if options.stage_all {
+        run_git_checked(repo_root, &["add".to_string(), "-A".to_string()], "git add")?;
+    }
+    run_git_checked(repo_root, &commit, "git commit")?;
+    let out = run_git_checked(repo_root, &commit_row_args("HEAD"), "git log")?;
+    let row = parse_commit_row(&String::from_utf8_lossy(&out.stdout))
+        .map_err(|e| format!("failed to parse git output: {e}"))?;
+    // The commit already succeeded, so a failing status only drops its line.
+    let mut subtitle = format!("Committed `{}`.", row.short_hash);
+    if let Ok(status) = run_git_status(repo_root) {
+        subtitle.push_str(&format!(" Working tree: {}", status.summary()));
+    }
+    Ok(build_output(
+        render_rows(repo_root, &[row], &subtitle),
+        "GitGraph commit",
+    ))
+}
+
 fn run_gitgraph_tips() -> Result<zed::SlashCommandOutput, String> {
     let text = [
         "# GitGraph tips",
         "",
-        "- `/gitgraph-log [limit]` - show recent graph summary",
+        "- `/gitgraph-log [limit] [page=N] [signatures]` - show recent graph summary, one page at a time",
         "- `/gitgraph-search [limit=200] [path=src/file.rs] query` - search history",
         "- `/gitgraph-actions` - list action ids",
-        "- `/gitgraph-action <id> KEY=VALUE +opt:<option-id>` - run action",
+        "- `/gitgraph-action <id> KEY=VALUE +opt:<option-id> [+yes]` - run action (`+yes` confirms destructive ones)",
         "- `/gitgraph-blame <path> <line>` - single-line blame",
+        "- `/gitgraph-line-history <path> <start> <end>` - commits that changed a line range",
+        "- `/gitgraph-status` - staged, unstaged, untracked and conflicted files",
+        "- `/gitgraph-commit [+all] [+amend] [+signoff] [+empty] [+fixup=<rev>] [+author=Name <email>] [--] message` - commit staged changes",
         "",
         "For full-screen interactive graph use CLI TUI in terminal:",
         "`gitgraph`",
@@ -377,6 +524,44 @@ fn parse_limit_arg(arg: Option<&str>) -> Result<usize, String> {
     }
 }
 
+fn parse_log_args(args: Vec<String>) -> Result<ParsedLogArgs, String> {
+    let mut limit = None;
+    let mut page = 1;
+    let mut verify_signatures = false;
+    for arg in args {
+        if arg == "signatures" {
+            verify_signatures = true;
+            continue;
+        }
+        if let Some(raw) = arg.strip_prefix("page=") {
+            page = raw
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("invalid page {:?}: {}", raw, e))?;
+            if page == 0 {
+                return Err("page must be >= 1".to_string());
+            }
+            continue;
+        }
+        if limit.is_some() {
+            return Err("usage: /gitgraph-log [limit=100] [page=1] [signatures]".to_string());
+        }
+        limit = Some(parse_limit_arg(Some(&arg))?);
+    }
+    Ok(ParsedLogArgs {
+        limit: limit.unwrap_or(DEFAULT_LIMIT),
+        page,
+        verify_signatures,
+    })
+}
+
+#[derive(Debug)]
+struct ParsedLogArgs {
+    limit: usize,
+    page: usize,
+    verify_signatures: bool,
+}
+
 fn parse_search_args(args: Vec<String>) -> Result<ParsedSearchArgs, String> {
     if args.is_empty() {
         return Err("usage: /gitgraph-search [limit=200] [path=src/file.rs] <query>".to_string());
@@ -432,34 +617,108 @@ fn parse_blame_args(args: Vec<String>) -> Result<(String, usize), String> {
     Ok((file, line))
 }
 
+fn parse_line_history_args(args: Vec<String>) -> Result<(String, usize, usize), String> {
+    if args.len() < 3 {
+        return Err("usage: /gitgraph-line-history <path> <start> <end>".to_string());
+    }
+    let parse_line = |raw: &str| {
+        raw.parse::<usize>()
+            .map_err(|e| format!("invalid line {:?}: {}", raw, e))
+    };
+    let start = parse_line(&args[1])?;
+    let end = parse_line(&args[2])?;
+    if start == 0 || end < start {
+        return Err("lines must satisfy 1 <= start <= end".to_string());
+    }
+    Ok((args[0].clone(), start, end))
+}
+
+// `+`-prefixed option tokens come first; everything after them, or after a
+// `--` separator, is the message. A `+author=` value runs until the token
+// holding its closing `>`.
+fn parse_commit_args(args: Vec<String>) -> Result<CommitOptions, String> {
+    let mut options = CommitOptions::default();
+    let mut tokens = args.into_iter().peekable();
+    while let Some(token) = tokens.peek() {
+        if token == "--" {
+            tokens.next();
+            break;
+        }
+        let Some(option) = token.strip_prefix('+') else {
+            break;
+        };
+        match option {
+            "all" => options.stage_all = true,
+            "amend" => options.amend = true,
+            "signoff" => options.signoff = true,
+            "empty" => options.allow_empty = true,
+            _ => {
+                if let Some(target) = option.strip_prefix("fixup=") {
+                    options.fixup = Some(target.to_string());
+                } else if let Some(first) = option.strip_prefix("author=") {
+                    let mut author = first.to_string();
+                    while !author.ends_with('>') {
+                        tokens.next();
+                        let next = tokens
+                            .peek()
+                            .ok_or_else(|| "author must look like `Name <email>`".to_string())?;
+                        author.push(' ');
+                        author.push_str(next);
+                    }
+                    options.author = Some(author);
+                } else {
+                    return Err(format!(
+                        "unknown commit option `{token}`; put `--` before a message starting with `+`"
+                    ));
+                }
+            }
+        }
+        tokens.next();
+    }
+    options.message = tokens.collect::<Vec<_>>().join(" ");
+    Ok(options)
+}
+
 #[derive(Debug)]
 struct ParsedActionArgs {
     template_id: String,
     params: HashMap<String, String>,
     enabled_options: HashSet<String>,
     context: ActionContext,
+    // `+yes`: run the action even when it is destructive.
+    confirmed: bool,
 }
 
 fn parse_action_args(args: Vec<String>) -> Result<ParsedActionArgs, String> {
     let Some((template_id, tail)) = args.split_first() else {
         return Err(
-            "usage: /gitgraph-action <action-id> KEY=VALUE +opt:<option-id> (e.g. BRANCH_NAME=main)"
+            "usage: /gitgraph-action <action-id> KEY=VALUE +opt:<option-id> [+yes] (e.g. BRANCH_NAME=main)"
                 .to_string(),
         );
     };
     let mut params = HashMap::new();
     let mut enabled_options = HashSet::new();
-    let mut context = ActionContext::default();
-    context.default_remote_name = Some("origin".to_string());
+    let mut confirmed = false;
+    let mut context = ActionContext {
+        default_remote_name: Some("origin".to_string()),
+        ..ActionContext::default()
+    };
 
     for token in tail {
+        if token == "+yes" {
+            confirmed = true;
+            continue;
+        }
         if let Some(opt) = token.strip_prefix("+opt:") {
             enabled_options.insert(opt.to_string());
             continue;
         }
-        let (key, value) = token
-            .split_once('=')
-            .ok_or_else(|| format!("invalid token {:?}, expected KEY=VALUE or +opt:<id>", token))?;
+        let (key, value) = token.split_once('=').ok_or_else(|| {
+            format!(
+                "invalid token {:?}, expected KEY=VALUE, +opt:<id> or +yes",
+                token
+            )
+        })?;
         params.insert(key.to_string(), value.to_string());
         map_context_placeholder(&mut context, key, value);
     }
@@ -469,6 +728,7 @@ fn parse_action_args(args: Vec<String>) -> Result<ParsedActionArgs, String> {
         params,
         enabled_options,
         context,
+        confirmed,
     })
 }
 
@@ -490,6 +750,7 @@ fn map_context_placeholder(context: &mut ActionContext, key: &str, value: &str)
                 .collect()
         }
         "COMMIT_BODY" => context.commit_body = Some(value.to_string()),
+        "COMMIT_NOTE" => context.commit_note = Some(value.to_string()),
         "STASH_NAME" => context.stash_name = Some(value.to_string()),
         "TAG_NAME" => context.tag_name = Some(value.to_string()),
         "REMOTE_NAME" => context.remote_name = Some(value.to_string()),
@@ -531,7 +792,7 @@ fn lookup_dynamic_placeholder(
     Ok(None)
 }
 
-fn run_git_log(repo_root: &str, limit: usize) -> Result<String, String> {
+fn run_git_log(repo_root: &str, limit: usize, verify_signatures: bool) -> Result<String, String> {
     let args = vec![
         "-c".to_string(),
         "color.ui=never".to_string(),
@@ -542,11 +803,11 @@ fn run_git_log(repo_root: &str, limit: usize) -> Result<String, String> {
         "--color=never".to_string(),
         "--no-show-signature".to_string(),
         "--no-notes".to_string(),
+        "--exclude=refs/notes/*".to_string(),
         "--all".to_string(),
         "-n".to_string(),
         limit.to_string(),
-        "--pretty=format:%H%x1f%h%x1f%P%x1f%an%x1f%ae%x1f%at%x1f%ct%x1f%D%x1f%s%x1f%b%x1e"
-            .to_string(),
+        log_format(false, verify_signatures),
     ];
     let out = run_git_command(repo_root, &args)?;
     if out.status != Some(0) {
@@ -559,6 +820,112 @@ fn run_git_log(repo_root: &str, limit: usize) -> Result<String, String> {
     String::from_utf8(out.stdout).map_err(|e| format!("invalid utf-8 from git: {}", e))
 }
 
+fn run_git_branches(repo_root: &str) -> Result<Vec<BranchInfo>, String> {
+    let args = vec![
+        "branch".to_string(),
+        "--list".to_string(),
+        "--sort=-committerdate".to_string(),
+        BRANCH_LIST_FORMAT.to_string(),
+    ];
+    let out = run_git_command(repo_root, &args)?;
+    if out.status != Some(0) {
+        return Err(format!(
+            "git branch failed (exit {:?}): {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr)
+        ));
+    }
+    Ok(parse_branch_list(&String::from_utf8_lossy(&out.stdout)))
+}
+
+fn run_git_status(repo_root: &str) -> Result<WorkingTreeStatus, String> {
+    let out = run_git_command(repo_root, &status_args())?;
+    if out.status != Some(0) {
+        return Err(format!(
+            "git status failed (exit {:?}): {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr)
+        ));
+    }
+    parse_status_porcelain_v2(&String::from_utf8_lossy(&out.stdout))
+        .map_err(|e| format!("failed to parse git status: {e}"))
+}
+
+fn render_status_text(repo_root: &str, status: &WorkingTreeStatus) -> String {
+    let branch = &status.branch;
+    let mut out = format!(
+        "# GitGraph status for `{}`\n\nbranch: `{}`",
+        repo_root,
+        branch.head.as_deref().unwrap_or("(detached)")
+    );
+    if let Some(upstream) = branch.upstream.as_deref() {
+        out.push_str(&format!(
+            " → `{}` ({} to push, {} to pull)",
+            upstream, branch.ahead, branch.behind
+        ));
+    }
+    out.push_str(&format!("\nworking tree: {}\n", status.summary()));
+    let sections: [(&str, Vec<&StatusEntry>); 4] = [
+        ("Conflicted", status.conflicted().collect()),
+        ("Staged", status.staged().collect()),
+        ("Unstaged", status.unstaged().collect()),
+        ("Untracked", status.untracked().collect()),
+    ];
+    for (title, entries) in sections {
+        if entries.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {title}\n"));
+        for entry in entries {
+            out.push_str(&format!("- `{}`\n", entry.short_line()));
+        }
+    }
+    out
+}
+
+fn render_branch_status(branches: &[BranchInfo]) -> String {
+    let lines = branches
+        .iter()
+        .filter_map(|branch| {
+            let upstream = branch.upstream.as_deref()?;
+            let upstream = upstream.strip_prefix("refs/remotes/").unwrap_or(upstream);
+            let status = if branch.upstream_gone {
+                "upstream gone".to_string()
+            } else if branch.ahead == 0 && branch.behind == 0 {
+                return None;
+            } else {
+                format!("{} to push, {} to pull", branch.ahead, branch.behind)
+            };
+            Some(format!(
+                "- `{}` → `{}`: {}\n",
+                branch.name, upstream, status
+            ))
+        })
+        .collect::<String>();
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!("\n\nBranches out of sync:\n{lines}")
+    }
+}
+
+fn run_git_checked(
+    repo_root: &str,
+    args: &[String],
+    what: &str,
+) -> Result<zed::process::Output, String> {
+    let out = run_git_command(repo_root, args)?;
+    if out.status != Some(0) {
+        return Err(format!(
+            "{what} failed (exit {:?}): {}{}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr),
+            String::from_utf8_lossy(&out.stdout)
+        ));
+    }
+    Ok(out)
+}
+
 fn run_git_command(repo_root: &str, args: &[String]) -> Result<zed::process::Output, String> {
     let mut full_args = vec!["-C".to_string(), repo_root.to_string()];
     full_args.extend(args.to_vec());
@@ -593,7 +960,7 @@ fn render_rows(repo_root: &str, rows: &[gitgraph_core::GraphRow], subtitle: &str
     out.push_str("\n\n");
 
     for row in rows {
-        let graph_prefix = format!("{}*", "| ".repeat(row.lane));
+        let prefix = graph_prefix(row, TextGraphStyle::Unicode);
         let refs = if row.refs.is_empty() {
             String::new()
         } else {
@@ -605,9 +972,15 @@ fn render_rows(repo_root: &str, rows: &[gitgraph_core::GraphRow], subtitle: &str
                 .join(", ");
             format!(" ({})", names)
         };
+        let badge = row
+            .signature
+            .as_ref()
+            .and_then(|sig| signature_badge(sig.status, TextGraphStyle::Unicode))
+            .map(|badge| format!(" {badge}"))
+            .unwrap_or_default();
         out.push_str(&format!(
-            "- {} `{}` {}{} - {}\n",
-            graph_prefix, row.short_hash, row.subject, refs, row.author_name
+            "- `{}` `{}`{} {}{} - {}\n",
+            prefix, row.short_hash, badge, row.subject, refs, row.author_name
         ));
     }
 
@@ -658,6 +1031,44 @@ fn render_blame_text(repo_root: &str, file: &str, line: usize, raw: &str) -> Str
     .join("\n")
 }
 
+fn render_line_history_text(
+    repo_root: &str,
+    file: &str,
+    start: usize,
+    end: usize,
+    entries: &[LineHistoryEntry],
+) -> String {
+    let mut out = format!(
+        "# GitGraph line history for `{}`\n\n`{}` lines {}-{}: {} commit(s)\n",
+        repo_root,
+        file,
+        start,
+        end,
+        entries.len()
+    );
+    for entry in entries {
+        let row = &entry.row;
+        out.push_str(&format!(
+            "\n## `{}` {} - {}\n",
+            row.short_hash, row.subject, row.author_name
+        ));
+        if let Some(previous) = entry.previous_path.as_deref() {
+            out.push_str(&format!("renamed: `{}` -> `{}`\n", previous, entry.path));
+        }
+        out.push_str("```diff\n");
+        for hunk in &entry.hunks {
+            out.push_str(&hunk.header);
+            out.push('\n');
+            for line in &hunk.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out.push_str("```\n");
+    }
+    out
+}
+
 fn build_output(text: String, label: &str) -> zed::SlashCommandOutput {
     let end = text.len() as u32;
     zed::SlashCommandOutput {